@@ -0,0 +1,143 @@
+/// Settings `Game::run` uses to create the window and drive the loop.
+///
+/// Fields are public so a config can be filled in from a file; `AppBuilder` is the
+/// convenient way to build one in code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub resizable: bool,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub gl_version: (u8, u8),
+    pub msaa_samples: u8,
+    pub ticks_per_second: u32,
+    pub max_steps_per_frame: u32,
+}
+
+impl GameConfig {
+    pub fn builder() -> AppBuilder {
+        AppBuilder::new()
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            title: String::from("Divergence"),
+            width: 800,
+            height: 600,
+            resizable: true,
+            fullscreen: false,
+            vsync: true,
+            gl_version: (3, 3),
+            msaa_samples: 0,
+            ticks_per_second: 60,
+            max_steps_per_frame: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AppBuilder {
+    config: GameConfig,
+}
+
+impl AppBuilder {
+    pub fn new() -> AppBuilder {
+        AppBuilder {
+            config: GameConfig::default(),
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> AppBuilder {
+        self.config.title = String::from(title);
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> AppBuilder {
+        self.config.width = width;
+        self.config.height = height;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> AppBuilder {
+        self.config.resizable = resizable;
+        self
+    }
+
+    pub fn fullscreen(mut self, fullscreen: bool) -> AppBuilder {
+        self.config.fullscreen = fullscreen;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> AppBuilder {
+        self.config.vsync = vsync;
+        self
+    }
+
+    pub fn gl_version(mut self, major: u8, minor: u8) -> AppBuilder {
+        self.config.gl_version = (major, minor);
+        self
+    }
+
+    pub fn msaa_samples(mut self, samples: u8) -> AppBuilder {
+        self.config.msaa_samples = samples;
+        self
+    }
+
+    pub fn ticks_per_second(mut self, ticks_per_second: u32) -> AppBuilder {
+        self.config.ticks_per_second = ticks_per_second;
+        self
+    }
+
+    pub fn max_steps_per_frame(mut self, max_steps: u32) -> AppBuilder {
+        self.config.max_steps_per_frame = max_steps;
+        self
+    }
+
+    pub fn build(self) -> GameConfig {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default() {
+        let test = AppBuilder::new().build();
+        let correct = GameConfig::default();
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn builder() {
+        let test = GameConfig::builder()
+            .title("Test")
+            .size(1280, 720)
+            .resizable(false)
+            .fullscreen(true)
+            .vsync(false)
+            .gl_version(4, 1)
+            .msaa_samples(4)
+            .build();
+
+        let correct = GameConfig {
+            title: String::from("Test"),
+            width: 1280,
+            height: 720,
+            resizable: false,
+            fullscreen: true,
+            vsync: false,
+            gl_version: (4, 1),
+            msaa_samples: 4,
+            ..GameConfig::default()
+        };
+
+        assert_eq!(test, correct);
+    }
+}
@@ -1,7 +1,10 @@
 use crate::input::{ controller, keyboard, mouse, joy };
+use crate::core::user_event::UserData;
 use crate::maths::Vector2D;
+use sdl2::event::{ Event as SdlEvent, WindowEvent as SdlWindowEvent };
+use serde::{ Deserialize, Serialize };
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Event {
     Quit,
     AppTerminating,
@@ -201,15 +204,16 @@ pub enum Event {
         window_id: u32,
         kind: u32,
         code: i32,
-        data1: *mut std::os::raw::c_void,
-        data2: *mut std::os::raw::c_void
+        /// Payload pushed through `UserEvents`. Recordings keep only the kind and code.
+        #[serde(skip)]
+        data: Option<UserData>
     },
     Unknown {
         kind: u32
     },
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum WindowEvent {
     None,
     Shown,
@@ -229,3 +233,372 @@ pub enum WindowEvent {
     TakeFocus,
     HitTest,
 }
+
+impl From<SdlEvent> for Event {
+    fn from(event: SdlEvent) -> Event {
+        match event {
+            SdlEvent::Quit { .. } => Event::Quit,
+            SdlEvent::AppTerminating { .. } => Event::AppTerminating,
+            SdlEvent::AppLowMemory { .. } => Event::AppLowMemory,
+            SdlEvent::AppWillEnterBackground { .. } => Event::AppWillEnterBackground,
+            SdlEvent::AppDidEnterBackground { .. } => Event::AppDidEnterBackground,
+            SdlEvent::AppWillEnterForeground { .. } => Event::AppWillEnterForeground,
+            SdlEvent::AppDidEnterForeground { .. } => Event::AppDidEnterForeground,
+            SdlEvent::Window { window_id, win_event, .. } => Event::Window {
+                window_id,
+                window_event: WindowEvent::from(win_event),
+            },
+            SdlEvent::KeyDown { window_id, keycode, scancode, keymod, repeat, .. } => Event::KeyDown {
+                window_id,
+                keycode: keycode.map(keyboard::KeyCode::from),
+                scancode: scancode.map(keyboard::ScanCode::from),
+                key_mod: keyboard::Mod::from(keymod),
+                repeat,
+            },
+            SdlEvent::KeyUp { window_id, keycode, scancode, keymod, repeat, .. } => Event::KeyUp {
+                window_id,
+                keycode: keycode.map(keyboard::KeyCode::from),
+                scancode: scancode.map(keyboard::ScanCode::from),
+                key_mod: keyboard::Mod::from(keymod),
+                repeat,
+            },
+            SdlEvent::TextEditing { window_id, text, start, length, .. } => Event::TextEditing {
+                window_id,
+                text,
+                start,
+                length,
+            },
+            SdlEvent::TextInput { window_id, text, .. } => Event::TextInput {
+                window_id,
+                text,
+            },
+            SdlEvent::MouseMotion { window_id, which, mousestate, x, y, xrel, yrel, .. } => Event::MouseMotion {
+                window_id,
+                which,
+                mouse_state: mouse::State::from(mousestate),
+                x,
+                y,
+                xrel,
+                yrel,
+            },
+            SdlEvent::MouseButtonDown { window_id, which, mouse_btn, clicks, x, y, .. } => Event::MouseButtonDown {
+                window_id,
+                which,
+                mouse_button: mouse::Button::from(mouse_btn),
+                clicks,
+                x,
+                y,
+            },
+            SdlEvent::MouseButtonUp { window_id, which, mouse_btn, clicks, x, y, .. } => Event::MouseButtonUp {
+                window_id,
+                which,
+                mouse_button: mouse::Button::from(mouse_btn),
+                clicks,
+                x,
+                y,
+            },
+            SdlEvent::MouseWheel { window_id, which, x, y, direction, .. } => Event::MouseWheel {
+                window_id,
+                which,
+                x,
+                y,
+                direction: mouse::WheelDirection::from(direction),
+            },
+            SdlEvent::JoyAxisMotion { which, axis_idx, value, .. } => Event::JoyAxisMotion {
+                which,
+                axis_index: axis_idx,
+                value,
+            },
+            SdlEvent::JoyBallMotion { which, ball_idx, xrel, yrel, .. } => Event::JoyBallMotion {
+                which,
+                ball_index: ball_idx,
+                xrel,
+                yrel,
+            },
+            SdlEvent::JoyHatMotion { which, hat_idx, state, .. } => Event::JoyHatMotion {
+                which,
+                hat_index: hat_idx,
+                state: joy::HatState::from(state),
+            },
+            SdlEvent::JoyButtonDown { which, button_idx, .. } => Event::JoyButtonDown {
+                which,
+                button_index: button_idx,
+            },
+            SdlEvent::JoyButtonUp { which, button_idx, .. } => Event::JoyButtonUp {
+                which,
+                button_index: button_idx,
+            },
+            SdlEvent::JoyDeviceAdded { which, .. } => Event::JoyDeviceAdded { which },
+            SdlEvent::JoyDeviceRemoved { which, .. } => Event::JoyDeviceRemoved { which },
+            SdlEvent::ControllerAxisMotion { which, axis, value, .. } => Event::ControllerAxisMotion {
+                which,
+                axis: controller::Axis::from(axis),
+                value,
+            },
+            SdlEvent::ControllerButtonDown { which, button, .. } => Event::ControllerButtonDown {
+                which,
+                button: controller::Button::from(button),
+            },
+            SdlEvent::ControllerButtonUp { which, button, .. } => Event::ControllerButtonUp {
+                which,
+                button: controller::Button::from(button),
+            },
+            SdlEvent::ControllerDeviceAdded { which, .. } => Event::ControllerDeviceAdded { which },
+            SdlEvent::ControllerDeviceRemoved { which, .. } => Event::ControllerDeviceRemoved { which },
+            SdlEvent::ControllerDeviceRemapped { which, .. } => Event::ControllerDeviceRemapped { which },
+            SdlEvent::FingerDown { touch_id, finger_id, x, y, dx, dy, pressure, .. } => Event::FingerDown {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                dx,
+                dy,
+                pressure,
+            },
+            SdlEvent::FingerUp { touch_id, finger_id, x, y, dx, dy, pressure, .. } => Event::FingerUp {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                dx,
+                dy,
+                pressure,
+            },
+            SdlEvent::FingerMotion { touch_id, finger_id, x, y, dx, dy, pressure, .. } => Event::FingerMotion {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                dx,
+                dy,
+                pressure,
+            },
+            SdlEvent::DollarGesture { touch_id, gesture_id, num_fingers, error, x, y, .. } => Event::DollarGesture {
+                touch_id,
+                gesture_id,
+                num_fingers,
+                error,
+                x,
+                y,
+            },
+            SdlEvent::DollarRecord { touch_id, gesture_id, num_fingers, error, x, y, .. } => Event::DollarRecord {
+                touch_id,
+                gesture_id,
+                num_fingers,
+                error,
+                x,
+                y,
+            },
+            SdlEvent::MultiGesture { touch_id, d_theta, d_dist, x, y, num_fingers, .. } => Event::MultiGesture {
+                touch_id,
+                d_theta,
+                d_dist,
+                x,
+                y,
+                num_fingers,
+            },
+            SdlEvent::ClipboardUpdate { .. } => Event::ClipboardUpdate,
+            SdlEvent::DropFile { window_id, filename, .. } => Event::DropFile { window_id, filename },
+            SdlEvent::DropText { window_id, filename, .. } => Event::DropText { window_id, filename },
+            SdlEvent::DropBegin { window_id, .. } => Event::DropBegin { window_id },
+            SdlEvent::DropComplete { window_id, .. } => Event::DropComplete { window_id },
+            SdlEvent::AudioDeviceAdded { which, iscapture, .. } => Event::AudioDeviceAdded {
+                which,
+                is_capture: iscapture,
+            },
+            SdlEvent::AudioDeviceRemoved { which, iscapture, .. } => Event::AudioDeviceRemoved {
+                which,
+                is_capture: iscapture,
+            },
+            SdlEvent::RenderTargetsReset { .. } => Event::RenderTargetsReset,
+            SdlEvent::RenderDeviceReset { .. } => Event::RenderDeviceReset,
+            // Raw pointers from SDL can't be given a type, so only the kind and code come through
+            SdlEvent::User { window_id, type_, code, .. } => Event::User {
+                window_id,
+                kind: type_,
+                code,
+                data: None,
+            },
+            SdlEvent::Unknown { type_, .. } => Event::Unknown { kind: type_ },
+        }
+    }
+}
+
+impl From<SdlWindowEvent> for WindowEvent {
+    fn from(event: SdlWindowEvent) -> WindowEvent {
+        match event {
+            SdlWindowEvent::None              => WindowEvent::None,
+            SdlWindowEvent::Shown             => WindowEvent::Shown,
+            SdlWindowEvent::Hidden            => WindowEvent::Hidden,
+            SdlWindowEvent::Exposed           => WindowEvent::Exposed,
+            SdlWindowEvent::Moved(x, y)       => WindowEvent::Moved(Vector2D::from((x as f32, y as f32))),
+            SdlWindowEvent::Resized(x, y)     => WindowEvent::Resize(Vector2D::from((x as f32, y as f32))),
+            SdlWindowEvent::SizeChanged(x, y) => WindowEvent::SizeChanged(Vector2D::from((x as f32, y as f32))),
+            SdlWindowEvent::Minimized         => WindowEvent::Minimised,
+            SdlWindowEvent::Maximized         => WindowEvent::Maximised,
+            SdlWindowEvent::Restored          => WindowEvent::Restored,
+            SdlWindowEvent::Enter             => WindowEvent::Enter,
+            SdlWindowEvent::Leave             => WindowEvent::Leave,
+            SdlWindowEvent::FocusGained       => WindowEvent::FocusGained,
+            SdlWindowEvent::FocusLost         => WindowEvent::FocusLost,
+            SdlWindowEvent::Close             => WindowEvent::Close,
+            SdlWindowEvent::TakeFocus         => WindowEvent::TakeFocus,
+            SdlWindowEvent::HitTest           => WindowEvent::HitTest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::keyboard::{ Keycode, Scancode };
+
+    #[test]
+    fn quit() {
+        let test = Event::from(SdlEvent::Quit { timestamp: 0 });
+
+        assert!(matches!(test, Event::Quit));
+    }
+
+    #[test]
+    fn key_down() {
+        let test = Event::from(SdlEvent::KeyDown {
+            timestamp: 0,
+            window_id: 1,
+            keycode: Some(Keycode::LCtrl),
+            scancode: Some(Scancode::Kp0),
+            keymod: sdl2::keyboard::Mod::LCTRLMOD,
+            repeat: true,
+        });
+
+        assert!(matches!(test, Event::KeyDown {
+            window_id: 1,
+            keycode: Some(keyboard::KeyCode::LControl),
+            scancode: Some(keyboard::ScanCode::Keypad0),
+            key_mod: keyboard::Mod::LCONTROL,
+            repeat: true,
+        }));
+    }
+
+    #[test]
+    fn key_up_without_code() {
+        let test = Event::from(SdlEvent::KeyUp {
+            timestamp: 0,
+            window_id: 1,
+            keycode: None,
+            scancode: None,
+            keymod: sdl2::keyboard::Mod::NOMOD,
+            repeat: false,
+        });
+
+        assert!(matches!(test, Event::KeyUp {
+            keycode: None,
+            scancode: None,
+            key_mod: keyboard::Mod::NONE,
+            ..
+        }));
+    }
+
+    #[test]
+    fn mouse_motion() {
+        let test = Event::from(SdlEvent::MouseMotion {
+            timestamp: 0,
+            window_id: 1,
+            which: 0,
+            mousestate: sdl2::mouse::MouseState::from_sdl_state(0b101),
+            x: 10,
+            y: 20,
+            xrel: -1,
+            yrel: 2,
+        });
+
+        match test {
+            Event::MouseMotion { mouse_state, x: 10, y: 20, xrel: -1, yrel: 2, .. } => {
+                assert!(mouse_state.left());
+                assert!(!mouse_state.middle());
+                assert!(mouse_state.right());
+            },
+            _ => panic!("expected Event::MouseMotion"),
+        }
+    }
+
+    #[test]
+    fn mouse_button_down() {
+        let test = Event::from(SdlEvent::MouseButtonDown {
+            timestamp: 0,
+            window_id: 1,
+            which: 0,
+            mouse_btn: sdl2::mouse::MouseButton::X2,
+            clicks: 2,
+            x: 5,
+            y: 6,
+        });
+
+        assert!(matches!(test, Event::MouseButtonDown { mouse_button: mouse::Button::X2, clicks: 2, x: 5, y: 6, .. }));
+    }
+
+    #[test]
+    fn controller_axis_motion() {
+        let test = Event::from(SdlEvent::ControllerAxisMotion {
+            timestamp: 0,
+            which: 3,
+            axis: sdl2::controller::Axis::TriggerRight,
+            value: -42,
+        });
+
+        assert!(matches!(test, Event::ControllerAxisMotion { which: 3, axis: controller::Axis::TriggerRight, value: -42 }));
+    }
+
+    #[test]
+    fn controller_button_up() {
+        let test = Event::from(SdlEvent::ControllerButtonUp {
+            timestamp: 0,
+            which: 3,
+            button: sdl2::controller::Button::DPadUp,
+        });
+
+        assert!(matches!(test, Event::ControllerButtonUp { which: 3, button: controller::Button::DPadUp }));
+    }
+
+    #[test]
+    fn joy_hat_motion() {
+        let test = Event::from(SdlEvent::JoyHatMotion {
+            timestamp: 0,
+            which: 2,
+            hat_idx: 1,
+            state: sdl2::joystick::HatState::LeftDown,
+        });
+
+        assert!(matches!(test, Event::JoyHatMotion { which: 2, hat_index: 1, state: joy::HatState::LeftDown }));
+    }
+
+    #[test]
+    fn window_resized() {
+        let test = Event::from(SdlEvent::Window {
+            timestamp: 0,
+            window_id: 1,
+            win_event: SdlWindowEvent::Resized(800, 600),
+        });
+
+        match test {
+            Event::Window { window_id: 1, window_event: WindowEvent::Resize(size) } => {
+                assert_eq!(size, Vector2D::from((800., 600.)));
+            },
+            _ => panic!("expected Event::Window"),
+        }
+    }
+
+    #[test]
+    fn text_input() {
+        let test = Event::from(SdlEvent::TextInput {
+            timestamp: 0,
+            window_id: 1,
+            text: String::from("å"),
+        });
+
+        match test {
+            Event::TextInput { text, .. } => assert_eq!(text, "å"),
+            _ => panic!("expected Event::TextInput"),
+        }
+    }
+}
@@ -1,48 +1,291 @@
 use gl;
+use sdl2::video::SwapInterval;
+use crate::Error;
+use crate::core::config::GameConfig;
+use crate::core::event::{ Event, WindowEvent };
+use crate::core::game_loop::GameLoop;
+use crate::core::headless::{ Headless, Report };
+use crate::core::replay::Recorder;
+use crate::core::time::{ FixedTimestep, SystemClock };
+use crate::core::user_event::{ UserData, UserEventOrigin, UserEvents };
+use crate::core::window::Window;
+use crate::input::{ controller, keyboard, mouse, Gamepads, TextInput };
+use crate::maths::Vector2D;
 
 pub trait Game {
-    const title: &'static str;
-    const width: u32;
-    const height: u32;
-
-    pub fn run(&self) {
-        let sdl = sdl2::init().unwrap();
-        let video_subsystem = sdl.video().unwrap();
+    fn run(&mut self, config: GameConfig) -> Result<(), Error> {
+        let sdl = sdl2::init().map_err(Error::SdlInit)?;
+        let video_subsystem = sdl.video().map_err(Error::SdlInit)?;
 
         let gl_attr = video_subsystem.gl_attr();
         gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
-        gl_attr.set_context_version(3, 3);
-
-        let window = video_subsystem
-            .window(Self::title, Self::width, Self::height)
-            .opengl()
-            .resizable()
-            .build()
-            .unwrap();
-        
-        let _gl_context = window.gl_create_context().unwrap();
-        let _gl = gl::load_with(|s| video_subsystem.gl_get_proc_address(s) as *const std::os::raw::c_void);
+        gl_attr.set_context_version(config.gl_version.0, config.gl_version.1);
+
+        if config.msaa_samples > 0 {
+            gl_attr.set_multisample_buffers(1);
+            gl_attr.set_multisample_samples(config.msaa_samples);
+        }
+
+        let mut window_builder = video_subsystem.window(&config.title, config.width, config.height);
+        window_builder.opengl();
+
+        if config.resizable {
+            window_builder.resizable();
+        }
+
+        if config.fullscreen {
+            window_builder.fullscreen_desktop();
+        }
+
+        let window = Window::new(window_builder.build()?, sdl.clone());
+
+        let _gl_context = window.raw().gl_create_context().map_err(Error::GlContext)?;
+        gl::load_with(|s| video_subsystem.gl_get_proc_address(s) as *const std::os::raw::c_void);
+
+        let swap_interval = if config.vsync { SwapInterval::VSync } else { SwapInterval::Immediate };
+        video_subsystem.gl_set_swap_interval(swap_interval).map_err(Error::GlContext)?;
+        window.update_viewport();
+        self.on_window_created(window.clone());
+
+        if let Some(gamepads) = self.gamepads() {
+            gamepads.attach(sdl.game_controller().map_err(Error::SdlInit)?);
+        }
+
+        if let Some(text_input) = self.text_input() {
+            text_input.attach(video_subsystem.text_input(), video_subsystem.clipboard());
+        }
 
         self.init();
 
-        let mut event_pump = sdl.event_pump().unwrap();
+        let mut event_pump = sdl.event_pump().map_err(Error::SdlInit)?;
+        let timestep = FixedTimestep::new(config.ticks_per_second).with_max_steps(config.max_steps_per_frame);
+        let mut game_loop = GameLoop::new(SystemClock::new(), timestep);
 
         'main: loop {
-            for event in event_pump.poll_iter() {
-                // Handle user input here
-                match event {
-                    sdl2::event::Event::Quit { .. } => break 'main,
-                    _ => {}
+            let mut events: Vec<(Event, Option<UserEventOrigin>)> = event_pump.poll_iter().map(|x| (Event::from(x), None)).collect();
+
+            if let Some(user_events) = self.user_events() {
+                events.extend(user_events.drain_with_origin().into_iter().map(|(x, origin)| (x, Some(origin))));
+            }
+
+            for (event, origin) in events {
+                if let Some(recorder) = self.recorder() {
+                    recorder.record_from(&event, origin);
+                }
+
+                window.handle_event(&event);
+                feed_event(self, &event);
+
+                if let Event::Quit = event {
+                    break 'main;
                 }
             }
 
-            self.render();
+            let steps = game_loop.frame(self);
 
-            window.gl_swap_window();
+            if let Some(recorder) = self.recorder() {
+                recorder.advance(steps.count);
+            }
+
+            window.swap();
         }
+
+        Ok(())
+    }
+
+    /// Runs the game like `run`, but without a window or GL context. See `Headless`.
+    fn run_headless(&mut self, headless: &Headless) -> Report {
+        headless.run(self)
+    }
+
+    fn init(&mut self);
+    /// Called at a fixed rate of `ticks_per_second`, with `dt` being the tick length in seconds.
+    fn update(&mut self, dt: f32);
+    /// Called once per frame, with `alpha` being how far the frame is between the last update and the next.
+    fn render(&mut self, alpha: f32);
+
+    /// Gamepad manager `run` should attach to SDL and keep up to date, if the game uses one.
+    fn gamepads(&mut self) -> Option<&mut Gamepads> {
+        None
+    }
+
+    /// Text input service `run` should attach to SDL and keep up to date, if the game uses one.
+    fn text_input(&mut self) -> Option<&mut TextInput> {
+        None
+    }
+
+    /// Recorder `run` should pass every event to, if the game is recording its input.
+    fn recorder(&mut self) -> Option<&mut Recorder> {
+        None
+    }
+
+    /// Queue `run` should take user events from each frame, if the game sends any.
+    fn user_events(&mut self) -> Option<&mut UserEvents> {
+        None
+    }
+
+    /// Called once the window and GL context exist, before `init`. Keep the handle to change
+    /// the window later on.
+    fn on_window_created(&mut self, _window: Window) {}
+
+    /// Called for every event before the matching typed hook below.
+    fn on_event(&mut self, _event: &Event) {}
+
+    fn on_key_down(&mut self, _keycode: Option<keyboard::KeyCode>, _scancode: Option<keyboard::ScanCode>, _key_mod: keyboard::Mod, _repeat: bool) {}
+    fn on_key_up(&mut self, _keycode: Option<keyboard::KeyCode>, _scancode: Option<keyboard::ScanCode>, _key_mod: keyboard::Mod) {}
+    fn on_text_input(&mut self, _text: &str) {}
+    fn on_mouse_move(&mut self, _x: i32, _y: i32, _xrel: i32, _yrel: i32) {}
+    fn on_mouse_button_down(&mut self, _button: mouse::Button, _x: i32, _y: i32) {}
+    fn on_mouse_button_up(&mut self, _button: mouse::Button, _x: i32, _y: i32) {}
+    fn on_mouse_wheel(&mut self, _x: i32, _y: i32) {}
+    fn on_controller_button_down(&mut self, _which: u32, _button: controller::Button) {}
+    fn on_controller_button_up(&mut self, _which: u32, _button: controller::Button) {}
+    fn on_controller_axis_motion(&mut self, _which: u32, _axis: controller::Axis, _value: i16) {}
+    fn on_window_resize(&mut self, _size: Vector2D) {}
+    fn on_window_focus(&mut self, _focused: bool) {}
+    fn on_user_event(&mut self, _code: i32, _data: Option<&UserData>) {}
+    fn on_quit(&mut self) {}
+}
+
+/// Updates the game's input services with `event` and then dispatches it.
+pub(crate) fn feed_event<G: Game + ?Sized>(game: &mut G, event: &Event) {
+    if let Some(gamepads) = game.gamepads() {
+        gamepads.handle_event(event);
     }
 
-    pub fn init(&self);
-    pub fn update(&self);
-    pub fn render(&self);
+    if let Some(text_input) = game.text_input() {
+        text_input.handle_event(event);
+    }
+
+    dispatch_event(game, event);
+}
+
+/// Passes `event` to `Game::on_event` and then to the typed hook matching it, if any.
+pub fn dispatch_event<G: Game + ?Sized>(game: &mut G, event: &Event) {
+    game.on_event(event);
+
+    match event {
+        Event::Quit => game.on_quit(),
+        Event::KeyDown { keycode, scancode, key_mod, repeat, .. } => game.on_key_down(*keycode, *scancode, *key_mod, *repeat),
+        Event::KeyUp { keycode, scancode, key_mod, .. } => game.on_key_up(*keycode, *scancode, *key_mod),
+        Event::TextInput { text, .. } => game.on_text_input(text),
+        Event::MouseMotion { x, y, xrel, yrel, .. } => game.on_mouse_move(*x, *y, *xrel, *yrel),
+        Event::MouseButtonDown { mouse_button, x, y, .. } => game.on_mouse_button_down(*mouse_button, *x, *y),
+        Event::MouseButtonUp { mouse_button, x, y, .. } => game.on_mouse_button_up(*mouse_button, *x, *y),
+        Event::MouseWheel { x, y, .. } => game.on_mouse_wheel(*x, *y),
+        Event::ControllerButtonDown { which, button } => game.on_controller_button_down(*which, *button),
+        Event::ControllerButtonUp { which, button } => game.on_controller_button_up(*which, *button),
+        Event::ControllerAxisMotion { which, axis, value } => game.on_controller_axis_motion(*which, *axis, *value),
+        Event::User { code, data, .. } => game.on_user_event(*code, data.as_ref()),
+        Event::Window { window_event, .. } => match window_event {
+            WindowEvent::Resize(size) => game.on_window_resize(*size),
+            WindowEvent::FocusGained  => game.on_window_focus(true),
+            WindowEvent::FocusLost    => game.on_window_focus(false),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: usize,
+        keys_down: Vec<bool>,
+        mouse: Option<(i32, i32, i32, i32)>,
+        resize: Option<Vector2D>,
+        quit: bool,
+    }
+
+    impl Game for Recorder {
+        fn init(&mut self) {}
+        fn update(&mut self, _dt: f32) {}
+        fn render(&mut self, _alpha: f32) {}
+
+        fn on_event(&mut self, _event: &Event) {
+            self.events += 1;
+        }
+
+        fn on_key_down(&mut self, _keycode: Option<keyboard::KeyCode>, _scancode: Option<keyboard::ScanCode>, _key_mod: keyboard::Mod, repeat: bool) {
+            self.keys_down.push(repeat);
+        }
+
+        fn on_mouse_move(&mut self, x: i32, y: i32, xrel: i32, yrel: i32) {
+            self.mouse = Some((x, y, xrel, yrel));
+        }
+
+        fn on_window_resize(&mut self, size: Vector2D) {
+            self.resize = Some(size);
+        }
+
+        fn on_quit(&mut self) {
+            self.quit = true;
+        }
+    }
+
+    #[test]
+    fn on_event_sees_everything() {
+        let mut game = Recorder::default();
+
+        dispatch_event(&mut game, &Event::ClipboardUpdate);
+        dispatch_event(&mut game, &Event::Unknown { kind: 0 });
+        dispatch_event(&mut game, &Event::Quit);
+
+        assert_eq!(game.events, 3);
+        assert!(game.quit);
+    }
+
+    #[test]
+    fn key_down() {
+        let mut game = Recorder::default();
+
+        dispatch_event(&mut game, &Event::KeyDown {
+            window_id: 1,
+            keycode: Some(keyboard::KeyCode::Space),
+            scancode: Some(keyboard::ScanCode::Space),
+            key_mod: keyboard::Mod::NONE,
+            repeat: true,
+        });
+
+        assert_eq!(game.keys_down, vec![true]);
+    }
+
+    #[test]
+    fn mouse_move() {
+        let mut game = Recorder::default();
+
+        dispatch_event(&mut game, &Event::MouseMotion {
+            window_id: 1,
+            which: 0,
+            mouse_state: mouse::State::from(sdl2::mouse::MouseState::from_sdl_state(0)),
+            x: 1,
+            y: 2,
+            xrel: 3,
+            yrel: 4,
+        });
+
+        assert_eq!(game.mouse, Some((1, 2, 3, 4)));
+    }
+
+    #[test]
+    fn window_resize() {
+        let mut game = Recorder::default();
+
+        dispatch_event(&mut game, &Event::Window {
+            window_id: 1,
+            window_event: WindowEvent::SizeChanged(Vector2D::from((1., 1.))),
+        });
+
+        assert_eq!(game.resize, None);
+
+        dispatch_event(&mut game, &Event::Window {
+            window_id: 1,
+            window_event: WindowEvent::Resize(Vector2D::from((640., 480.))),
+        });
+
+        assert_eq!(game.resize, Some(Vector2D::from((640., 480.))));
+    }
 }
@@ -0,0 +1,117 @@
+use crate::core::game::Game;
+use crate::core::time::{ Clock, FixedTimestep, Steps };
+use std::time::Duration;
+
+/// Drives `Game::update` at a fixed rate and `Game::render` once per frame.
+pub struct GameLoop<C: Clock> {
+    clock: C,
+    timestep: FixedTimestep,
+    last: Duration,
+}
+
+impl<C: Clock> GameLoop<C> {
+    pub fn new(clock: C, timestep: FixedTimestep) -> GameLoop<C> {
+        let last = clock.now();
+
+        GameLoop {
+            clock,
+            timestep,
+            last,
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn clock_mut(&mut self) -> &mut C {
+        &mut self.clock
+    }
+
+    pub fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    /// Runs one frame: as many fixed updates as the elapsed time calls for, then a render
+    /// with the interpolation alpha between the last two updates.
+    pub fn frame<G: Game + ?Sized>(&mut self, game: &mut G) -> Steps {
+        let steps = self.update(game);
+
+        game.render(steps.alpha);
+
+        steps
+    }
+
+    /// Runs the fixed updates of a frame without rendering it.
+    pub fn update<G: Game + ?Sized>(&mut self, game: &mut G) -> Steps {
+        let now = self.clock.now();
+        let steps = self.timestep.advance(now - self.last);
+        self.last = now;
+
+        let dt = self.timestep.dt();
+
+        for _ in 0..steps.count {
+            game.update(dt);
+        }
+
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time::ManualClock;
+
+    #[derive(Default)]
+    struct Counter {
+        updates: Vec<f32>,
+        renders: Vec<f32>,
+    }
+
+    impl Game for Counter {
+        fn init(&mut self) {}
+
+        fn update(&mut self, dt: f32) {
+            self.updates.push(dt);
+        }
+
+        fn render(&mut self, alpha: f32) {
+            self.renders.push(alpha);
+        }
+    }
+
+    #[test]
+    fn frame() {
+        let mut game = Counter::default();
+        let mut game_loop = GameLoop::new(ManualClock::new(), FixedTimestep::new(50));
+
+        game_loop.clock_mut().advance(Duration::from_millis(50));
+        game_loop.frame(&mut game);
+
+        game_loop.clock_mut().advance(Duration::from_millis(10));
+        game_loop.frame(&mut game);
+
+        assert_eq!(game.updates, vec![0.02; 3]);
+        assert_eq!(game.renders.len(), 2);
+        assert!((game.renders[0] - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn frame_is_deterministic() {
+        let run = || {
+            let mut game = Counter::default();
+            let mut game_loop = GameLoop::new(ManualClock::new(), FixedTimestep::new(60));
+
+            for ms in [3, 17, 40, 1, 90, 16].iter() {
+                game_loop.clock_mut().advance(Duration::from_millis(*ms));
+                game_loop.frame(&mut game);
+            }
+
+            game.updates.len()
+        };
+
+        assert_eq!(run(), run());
+        assert_eq!(run(), 9);
+    }
+}
@@ -0,0 +1,277 @@
+use std::time::Duration;
+use crate::core::config::GameConfig;
+use crate::core::event::Event;
+use crate::core::game::{ self, Game };
+use crate::core::game_loop::GameLoop;
+use crate::core::time::{ FixedTimestep, ManualClock, Steps };
+use crate::core::user_event::UserEventOrigin;
+
+/// What a headless run does with `Game::render`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Never call it.
+    Skip,
+    /// Don't call it, but note each frame's alpha in the report.
+    Record,
+    /// Call it as usual, for games that don't touch GL when rendering.
+    Call,
+}
+
+/// Events to hand the game at given frames, standing in for SDL's event queue.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventScript {
+    events: Vec<(u64, Event)>,
+}
+
+impl EventScript {
+    pub fn new() -> EventScript {
+        EventScript::default()
+    }
+
+    /// Adds `event` at the start of `frame`. Events for the same frame keep their order.
+    pub fn at(mut self, frame: u64, event: Event) -> EventScript {
+        self.push(frame, event);
+        self
+    }
+
+    pub fn push(&mut self, frame: u64, event: Event) {
+        let index = self.events.iter().position(|(x, _)| *x > frame).unwrap_or(self.events.len());
+        self.events.insert(index, (frame, event));
+    }
+
+    pub fn events(&self) -> &[(u64, Event)] {
+        &self.events
+    }
+}
+
+/// What happened during a headless run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub frames: u64,
+    pub updates: u64,
+    /// Whether the run ended on a scripted `Quit` rather than running out of frames.
+    pub quit: bool,
+    /// Interpolation alpha of each frame, if rendering was recorded.
+    pub renders: Vec<f32>,
+}
+
+/// Runs a game without a window or GL context, on a simulated clock.
+///
+/// Each frame advances the clock by a fixed frame time, feeds the scripted events for that
+/// frame through the same path as `Game::run` and then runs the fixed updates. This makes a
+/// run fully deterministic, so gameplay can be checked from `cargo test` on machines without
+/// a display.
+pub struct Headless {
+    ticks_per_second: u32,
+    max_steps: u32,
+    frame_time: Duration,
+    frames: u64,
+    script: EventScript,
+    render: RenderMode,
+}
+
+impl Headless {
+    /// Takes the tick rate and step cap from `config` and runs for `frames` frames of one tick each.
+    pub fn new(config: &GameConfig, frames: u64) -> Headless {
+        let timestep = FixedTimestep::new(config.ticks_per_second);
+
+        Headless {
+            ticks_per_second: config.ticks_per_second,
+            max_steps: config.max_steps_per_frame,
+            frame_time: timestep.tick(),
+            frames,
+            script: EventScript::new(),
+            render: RenderMode::Record,
+        }
+    }
+
+    /// Sets how much simulated time passes per frame.
+    pub fn with_frame_time(mut self, frame_time: Duration) -> Headless {
+        self.frame_time = frame_time;
+        self
+    }
+
+    pub fn with_script(mut self, script: EventScript) -> Headless {
+        self.script = script;
+        self
+    }
+
+    pub fn with_render(mut self, render: RenderMode) -> Headless {
+        self.render = render;
+        self
+    }
+
+    /// Initialises the game and runs it until the frames run out or a scripted `Quit`.
+    pub fn run<G: Game + ?Sized>(&self, game: &mut G) -> Report {
+        let timestep = FixedTimestep::new(self.ticks_per_second).with_max_steps(self.max_steps);
+        let mut game_loop = GameLoop::new(ManualClock::new(), timestep);
+        let mut script = self.script.events.iter().peekable();
+        let mut report = Report::default();
+
+        game.init();
+
+        for frame in 0..self.frames {
+            game_loop.clock_mut().advance(self.frame_time);
+
+            let mut events: Vec<(Event, Option<UserEventOrigin>)> = Vec::new();
+
+            while let Some((_, event)) = script.next_if(|(x, _)| *x <= frame) {
+                events.push((event.clone(), None));
+            }
+
+            if let Some(user_events) = game.user_events() {
+                events.extend(user_events.drain_with_origin().into_iter().map(|(x, origin)| (x, Some(origin))));
+            }
+
+            for (event, origin) in events {
+                if let Some(recorder) = game.recorder() {
+                    recorder.record_from(&event, origin);
+                }
+
+                game::feed_event(game, &event);
+
+                if let Event::Quit = event {
+                    report.quit = true;
+                    return report;
+                }
+            }
+
+            let steps = match self.render {
+                RenderMode::Call => game_loop.frame(game),
+                _ => game_loop.update(game),
+            };
+
+            self.finish_frame(game, steps, &mut report);
+        }
+
+        report
+    }
+
+    fn finish_frame<G: Game + ?Sized>(&self, game: &mut G, steps: Steps, report: &mut Report) {
+        if let Some(recorder) = game.recorder() {
+            recorder.advance(steps.count);
+        }
+
+        if self.render != RenderMode::Skip {
+            report.renders.push(steps.alpha);
+        }
+
+        report.frames += 1;
+        report.updates += steps.count as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::replay::{ self, Recorder };
+    use crate::input::keyboard::{ KeyCode, Mod, ScanCode };
+
+    #[derive(Default)]
+    struct Jumper {
+        initialised: bool,
+        jumps: u32,
+        height: f32,
+        renders: u32,
+        recorder: Option<Recorder>,
+    }
+
+    impl Game for Jumper {
+        fn init(&mut self) {
+            self.initialised = true;
+        }
+
+        fn update(&mut self, dt: f32) {
+            self.height = (self.height - dt).max(0.);
+        }
+
+        fn render(&mut self, _alpha: f32) {
+            self.renders += 1;
+        }
+
+        fn recorder(&mut self) -> Option<&mut Recorder> {
+            self.recorder.as_mut()
+        }
+
+        fn on_key_down(&mut self, keycode: Option<KeyCode>, _scancode: Option<ScanCode>, _key_mod: Mod, _repeat: bool) {
+            if keycode == Some(KeyCode::Space) {
+                self.jumps += 1;
+                self.height = 1.;
+            }
+        }
+    }
+
+    fn space() -> Event {
+        Event::KeyDown { window_id: 0, keycode: Some(KeyCode::Space), scancode: None, key_mod: Mod::NONE, repeat: false }
+    }
+
+    fn config() -> GameConfig {
+        GameConfig::builder().ticks_per_second(10).build()
+    }
+
+    #[test]
+    fn script() {
+        let test = EventScript::new().at(5, Event::Quit).at(2, space()).at(5, space());
+        let correct = [(2, space()), (5, Event::Quit), (5, space())];
+
+        assert_eq!(test.events(), &correct[..]);
+    }
+
+    #[test]
+    fn run() {
+        let mut game = Jumper::default();
+        let headless = Headless::new(&config(), 8).with_script(EventScript::new().at(2, space()));
+
+        let test = headless.run(&mut game);
+
+        assert!(game.initialised);
+        assert_eq!(game.jumps, 1);
+        assert!((game.height - 0.4).abs() < 1e-5);
+        assert_eq!(game.renders, 0);
+        assert_eq!((test.frames, test.updates, test.quit), (8, 8, false));
+        assert_eq!(test.renders.len(), 8);
+    }
+
+    #[test]
+    fn frame_time_and_render() {
+        let mut game = Jumper::default();
+        let headless = Headless::new(&config(), 4)
+            .with_frame_time(Duration::from_millis(250))
+            .with_render(RenderMode::Call);
+
+        let test = headless.run(&mut game);
+
+        assert_eq!(test.updates, 10);
+        assert_eq!(game.renders, 4);
+        assert_eq!(test.renders, vec![0.5, 0., 0.5, 0.]);
+    }
+
+    #[test]
+    fn quit() {
+        let mut game = Jumper::default();
+        let headless = Headless::new(&config(), 100)
+            .with_script(EventScript::new().at(3, Event::Quit))
+            .with_render(RenderMode::Skip);
+
+        let test = headless.run(&mut game);
+
+        assert_eq!((test.frames, test.updates, test.quit), (3, 3, true));
+        assert!(test.renders.is_empty());
+    }
+
+    #[test]
+    fn replays_recorded_run() {
+        let mut game = Jumper { recorder: Some(Recorder::new(10)), ..Jumper::default() };
+        let script = EventScript::new().at(1, space()).at(6, space());
+
+        Headless::new(&config(), 9).with_script(script).run(&mut game);
+
+        let recording = game.recorder.take().unwrap().finish();
+        let mut test = Jumper::default();
+
+        replay::replay(&mut test, &recording);
+
+        assert_eq!(test.jumps, game.jumps);
+        assert!((test.height - game.height).abs() < 1e-6);
+    }
+}
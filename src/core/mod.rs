@@ -1,2 +1,9 @@
+pub mod config;
 pub mod game;
+pub mod game_loop;
+pub mod headless;
 pub mod event;
+pub mod replay;
+pub mod time;
+pub mod user_event;
+pub mod window;
@@ -0,0 +1,445 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use bincode::Options;
+use serde::{ Deserialize, Serialize };
+use crate::Error;
+use crate::core::event::Event;
+use crate::core::game::{ self, Game };
+use crate::core::time::FixedTimestep;
+use crate::core::user_event::UserEventOrigin;
+
+const MAGIC: &[u8; 4] = b"DVRC";
+const VERSION: u8 = 2;
+
+/// An event together with the frame it arrived in and the number of fixed updates that had
+/// run by then.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub frame: u64,
+    pub tick: u64,
+    pub event: Event,
+    /// How the event got into `Game::user_events`, for user events that came through it.
+    pub origin: Option<UserEventOrigin>,
+}
+
+/// Recorded input, enough to replay a run update for update.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    ticks_per_second: u32,
+    ticks: u64,
+    /// Number of fixed updates each frame ran.
+    frames: Vec<u32>,
+    events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    pub fn ticks_per_second(&self) -> u32 {
+        self.ticks_per_second
+    }
+
+    /// Number of fixed updates the recording covers.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Number of fixed updates each frame ran.
+    pub fn frames(&self) -> &[u32] {
+        &self.frames
+    }
+
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+
+        // Variable length integers keep the mostly small tick indices and coordinates compact
+        bincode::DefaultOptions::new()
+            .serialize_into(&mut bytes, self)
+            .expect("a recording always serialises");
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Recording, Error> {
+        if bytes.len() < MAGIC.len() + 1 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(Error::Recording(String::from("missing header")));
+        }
+
+        let version = bytes[MAGIC.len()];
+
+        if version != VERSION {
+            return Err(Error::Recording(format!("unsupported version {}", version)));
+        }
+
+        bincode::DefaultOptions::new()
+            .deserialize(&bytes[MAGIC.len() + 1..])
+            .map_err(|x| Error::Recording(x.to_string()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+
+        fs::write(path, self.to_bytes()).map_err(|error| Error::AssetIo { path: path.to_path_buf(), error })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Recording, Error> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|error| Error::AssetIo { path: path.to_path_buf(), error })?;
+
+        Recording::from_bytes(&bytes)
+    }
+}
+
+/// Records events against the frame and fixed update they precede.
+///
+/// Return it from `Game::recorder` and `Game::run` keeps it up to date.
+pub struct Recorder {
+    recording: Recording,
+}
+
+impl Recorder {
+    pub fn new(ticks_per_second: u32) -> Recorder {
+        Recorder {
+            recording: Recording {
+                ticks_per_second,
+                ticks: 0,
+                frames: Vec::new(),
+                events: Vec::new(),
+            },
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.recording.ticks
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        self.record_from(event, None);
+    }
+
+    /// Records an event, with `origin` saying how it was queued if it came from
+    /// `Game::user_events`.
+    pub fn record_from(&mut self, event: &Event, origin: Option<UserEventOrigin>) {
+        self.recording.events.push(RecordedEvent {
+            frame: self.recording.frames.len() as u64,
+            tick: self.recording.ticks,
+            event: event.clone(),
+            origin,
+        });
+    }
+
+    /// Ends the frame, which ran `ticks` fixed updates after its events.
+    pub fn advance(&mut self, ticks: u32) {
+        self.recording.frames.push(ticks);
+        self.recording.ticks += ticks as u64;
+    }
+
+    pub fn finish(self) -> Recording {
+        self.recording
+    }
+}
+
+/// Steps a recording through a game one fixed update at a time, without a window.
+///
+/// Events go through the same path as in `Game::run`, but `Game::render` is never called,
+/// so replays work for games whose rendering needs a GL context.
+///
+/// Recordings don't keep `User` payloads, so recorded user events come back with
+/// `data: None`. The exception is events the game queues itself with `UserEvents::push`:
+/// the game's queue is drained at the start of every recorded frame, as `Game::run` does,
+/// and those events take the place of their recorded copies with their payloads intact.
+/// Events sent through a `UserEventSender` are replayed from the recording, and any sent
+/// during the replay are dropped.
+pub struct Replay<'a> {
+    recording: &'a Recording,
+    dt: f32,
+    tick: u64,
+    next: usize,
+    frame: usize,
+    /// Updates left to run in the current frame.
+    remaining: u32,
+    finished: bool,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(recording: &'a Recording) -> Replay<'a> {
+        Replay {
+            recording,
+            dt: FixedTimestep::new(recording.ticks_per_second).dt(),
+            tick: 0,
+            next: 0,
+            frame: 0,
+            remaining: 0,
+            finished: false,
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Runs the next update, first feeding the events of any frames that start before it.
+    /// Returns false once the recording is over or a `Quit` event has been replayed.
+    pub fn step<G: Game + ?Sized>(&mut self, game: &mut G) -> bool {
+        while !self.finished && self.remaining == 0 {
+            self.start_frame(game);
+        }
+
+        if self.finished {
+            return false;
+        }
+
+        game.update(self.dt);
+        self.tick += 1;
+        self.remaining -= 1;
+
+        true
+    }
+
+    /// Feeds the events of the next frame. Events after the last recorded frame, such as the
+    /// `Quit` that ended the run, are fed before finishing.
+    fn start_frame<G: Game + ?Sized>(&mut self, game: &mut G) {
+        let queue = game.user_events();
+        let has_queue = queue.is_some();
+
+        // Only events the game queues again are used, in place of their recorded copies
+        let mut queued: VecDeque<Event> = queue
+            .map(|x| x.drain_with_origin())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, origin)| *origin == UserEventOrigin::Queue)
+            .map(|(x, _)| x)
+            .collect();
+
+        let mut events = Vec::new();
+
+        while let Some(x) = self.recording.events.get(self.next) {
+            if x.frame != self.frame as u64 {
+                break;
+            }
+
+            self.next += 1;
+
+            match x.origin {
+                Some(UserEventOrigin::Queue) if has_queue => events.push(queued.pop_front().unwrap_or_else(|| x.event.clone())),
+                _ => events.push(x.event.clone()),
+            }
+        }
+
+        for event in events {
+            game::feed_event(game, &event);
+
+            if let Event::Quit = event {
+                self.finished = true;
+                return;
+            }
+        }
+
+        match self.recording.frames.get(self.frame) {
+            Some(x) => self.remaining = *x,
+            None => self.finished = true,
+        }
+
+        self.frame += 1;
+    }
+}
+
+/// Replays a whole recording into `game`. The game isn't initialised first, so call
+/// `Game::init` beforehand if it needs to be.
+pub fn replay<G: Game + ?Sized>(game: &mut G, recording: &Recording) {
+    let mut replay = Replay::new(recording);
+
+    while replay.step(game) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::GameConfig;
+    use crate::core::headless::Headless;
+    use crate::core::user_event::{ UserData, UserEvents };
+    use crate::input::keyboard::{ KeyCode, Mod, ScanCode };
+
+    #[derive(Default)]
+    struct Mover {
+        held: bool,
+        position: u32,
+        log: Vec<(u32, bool)>,
+    }
+
+    impl Game for Mover {
+        fn init(&mut self) {}
+
+        fn update(&mut self, _dt: f32) {
+            if self.held {
+                self.position += 1;
+            }
+
+            self.log.push((self.position, self.held));
+        }
+
+        fn render(&mut self, _alpha: f32) {}
+
+        fn on_key_down(&mut self, _keycode: Option<KeyCode>, _scancode: Option<ScanCode>, _key_mod: Mod, _repeat: bool) {
+            self.held = true;
+        }
+
+        fn on_key_up(&mut self, _keycode: Option<KeyCode>, _scancode: Option<ScanCode>, _key_mod: Mod) {
+            self.held = false;
+        }
+    }
+
+    fn key_down() -> Event {
+        Event::KeyDown { window_id: 1, keycode: Some(KeyCode::Right), scancode: None, key_mod: Mod::NONE, repeat: false }
+    }
+
+    fn key_up() -> Event {
+        Event::KeyUp { window_id: 1, keycode: Some(KeyCode::Right), scancode: None, key_mod: Mod::NONE, repeat: false }
+    }
+
+    fn record() -> (Mover, Recording) {
+        let mut game = Mover::default();
+        let mut recorder = Recorder::new(60);
+
+        // Frames with an uneven number of updates, as a real clock would give
+        for (events, updates) in [(vec![], 2), (vec![key_down()], 0), (vec![], 3), (vec![key_up(), key_down()], 1), (vec![key_up()], 2)] {
+            for event in events {
+                recorder.record(&event);
+                game::feed_event(&mut game, &event);
+            }
+
+            for _ in 0..updates {
+                game.update(1. / 60.);
+            }
+
+            recorder.advance(updates);
+        }
+
+        (game, recorder.finish())
+    }
+
+    #[test]
+    fn replay_matches() {
+        let (recorded, recording) = record();
+        let mut test = Mover::default();
+
+        replay(&mut test, &recording);
+
+        assert_eq!(recording.ticks(), 8);
+        assert_eq!(test.log, recorded.log);
+        assert_eq!(test.position, 4);
+    }
+
+    #[test]
+    fn round_trip() {
+        let (_, recording) = record();
+        let test = Recording::from_bytes(&recording.to_bytes()).unwrap();
+
+        assert_eq!(test, recording);
+    }
+
+    #[test]
+    fn user_data_is_dropped() {
+        let mut recorder = Recorder::new(60);
+
+        recorder.record(&Event::User { window_id: 0, kind: 0x8000, code: 3, data: Some(UserData::new(5)) });
+
+        let test = Recording::from_bytes(&recorder.finish().to_bytes()).unwrap();
+        let correct = Event::User { window_id: 0, kind: 0x8000, code: 3, data: None };
+
+        assert_eq!(test.events()[0].event, correct);
+    }
+
+    /// Queues an event on every other update and notes when each user event arrives.
+    #[derive(Default)]
+    struct Queuer {
+        queue: UserEvents,
+        recorder: Option<Recorder>,
+        ticks: u32,
+        received: Vec<(i32, u32, bool)>,
+    }
+
+    impl Game for Queuer {
+        fn init(&mut self) {}
+
+        fn update(&mut self, _dt: f32) {
+            self.ticks += 1;
+
+            if self.ticks % 2 == 1 {
+                self.queue.push(self.ticks as i32, self.ticks);
+            }
+        }
+
+        fn render(&mut self, _alpha: f32) {}
+
+        fn recorder(&mut self) -> Option<&mut Recorder> {
+            self.recorder.as_mut()
+        }
+
+        fn user_events(&mut self) -> Option<&mut UserEvents> {
+            Some(&mut self.queue)
+        }
+
+        fn on_user_event(&mut self, code: i32, data: Option<&UserData>) {
+            self.received.push((code, self.ticks, data.is_some()));
+        }
+    }
+
+    #[test]
+    fn user_events_arrive_at_frame_starts() {
+        let config = GameConfig::builder().ticks_per_second(60).build();
+        let frame_time = FixedTimestep::new(60).tick() * 3;
+        let mut game = Queuer { recorder: Some(Recorder::new(60)), ..Queuer::default() };
+        game.queue.sender().push(100, ());
+
+        Headless::new(&config, 3).with_frame_time(frame_time).run(&mut game);
+
+        // Saved and loaded, which drops payloads
+        let recording = Recording::from_bytes(&game.recorder.take().unwrap().finish().to_bytes()).unwrap();
+        let mut test = Queuer::default();
+
+        replay(&mut test, &recording);
+
+        // Events queued during a frame's updates only arrive once the next frame starts
+        assert_eq!(recording.frames(), &[3, 3, 3]);
+        assert_eq!(game.received, vec![(100, 0, true), (1, 3, true), (3, 3, true), (5, 6, true)]);
+        assert_eq!(test.received, vec![(100, 0, false), (1, 3, true), (3, 3, true), (5, 6, true)]);
+        assert_eq!(test.ticks, 9);
+    }
+
+    #[test]
+    fn stops_at_quit() {
+        let mut recorder = Recorder::new(60);
+        recorder.advance(2);
+        recorder.record(&Event::Quit);
+        recorder.advance(5);
+
+        let recording = recorder.finish();
+        let mut game = Mover::default();
+        let mut replay = Replay::new(&recording);
+
+        while replay.step(&mut game) {}
+
+        assert_eq!(replay.tick(), 2);
+        assert_eq!(game.log.len(), 2);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Recording::from_bytes(b"nope").is_err());
+        assert!(Recording::from_bytes(b"DVRC\x09").is_err());
+        assert!(Recording::from_bytes(b"DVRC\x01\xff").is_err());
+    }
+}
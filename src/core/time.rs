@@ -0,0 +1,167 @@
+use std::time::{ Duration, Instant };
+
+/// Source of monotonic time for the game loop.
+pub trait Clock {
+    /// Time elapsed since the clock was created.
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock(Instant);
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock(Instant::now())
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// Clock that only moves when told to, for stepping the loop deterministically.
+#[derive(Default)]
+pub struct ManualClock(Duration);
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock(Duration::from_secs(0))
+    }
+
+    pub fn advance(&mut self, duration: Duration) {
+        self.0 += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.0
+    }
+}
+
+/// Result of advancing a `FixedTimestep`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Steps {
+    /// Number of fixed updates to run this frame.
+    pub count: u32,
+    /// How far into the next tick the frame is, in `[0, 1)`.
+    pub alpha: f32,
+}
+
+pub struct FixedTimestep {
+    tick: Duration,
+    accumulator: Duration,
+    max_steps: u32,
+}
+
+impl FixedTimestep {
+    /// Rates above a billion ticks per second run at one tick per nanosecond.
+    pub fn new(ticks_per_second: u32) -> FixedTimestep {
+        FixedTimestep {
+            tick: (Duration::from_secs(1) / ticks_per_second.max(1)).max(Duration::from_nanos(1)),
+            accumulator: Duration::from_secs(0),
+            max_steps: 5,
+        }
+    }
+
+    /// Caps the number of updates a single frame may run. Time beyond the cap is dropped
+    /// so a slow frame can't snowball into ever slower ones.
+    pub fn with_max_steps(mut self, max_steps: u32) -> FixedTimestep {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    pub fn dt(&self) -> f32 {
+        self.tick.as_secs_f32()
+    }
+
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) -> Steps {
+        self.accumulator += elapsed;
+
+        let mut count = 0;
+
+        while self.accumulator >= self.tick && count < self.max_steps {
+            self.accumulator -= self.tick;
+            count += 1;
+        }
+
+        if self.accumulator >= self.tick {
+            self.accumulator = Duration::from_nanos((self.accumulator.as_nanos() % self.tick.as_nanos()) as u64);
+        }
+
+        Steps {
+            count,
+            alpha: self.accumulator.as_secs_f32() / self.tick.as_secs_f32(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock() {
+        let mut clock = ManualClock::new();
+        clock.advance(Duration::from_millis(5));
+        clock.advance(Duration::from_millis(10));
+
+        assert_eq!(clock.now(), Duration::from_millis(15));
+    }
+
+    #[test]
+    fn advance_less_than_tick() {
+        let mut timestep = FixedTimestep::new(10);
+        let test = timestep.advance(Duration::from_millis(50));
+        let correct = Steps { count: 0, alpha: 0.5 };
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn advance_accumulates() {
+        let mut timestep = FixedTimestep::new(10);
+        timestep.advance(Duration::from_millis(75));
+        let test = timestep.advance(Duration::from_millis(75));
+
+        assert_eq!(test.count, 1);
+        assert!((test.alpha - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn advance_caps_steps() {
+        let mut timestep = FixedTimestep::new(10).with_max_steps(3);
+        let test = timestep.advance(Duration::from_millis(1025));
+
+        assert_eq!(test.count, 3);
+        assert!((test.alpha - 0.25).abs() < 1e-5);
+
+        let test = timestep.advance(Duration::from_millis(0));
+
+        assert_eq!(test.count, 0);
+    }
+
+    #[test]
+    fn huge_rate() {
+        let mut timestep = FixedTimestep::new(u32::MAX).with_max_steps(3);
+        let test = timestep.advance(Duration::from_millis(1));
+
+        assert_eq!(timestep.tick(), Duration::from_nanos(1));
+        assert_eq!(test.count, 3);
+    }
+}
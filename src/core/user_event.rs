@@ -0,0 +1,202 @@
+use std::any::{ self, Any };
+use std::fmt;
+use std::sync::{ Arc, Mutex, MutexGuard };
+use std::sync::mpsc::{ self, Receiver, Sender };
+use serde::{ Deserialize, Serialize };
+use crate::core::event::Event;
+
+/// Event kind given to user events pushed through `UserEvents`, the same as `SDL_USEREVENT`.
+pub const USER_EVENT_KIND: u32 = 0x8000;
+
+type Payload = Option<Box<dyn Any + Send>>;
+
+/// Payload carried by `Event::User`, downcast back to its type on the receiving side.
+///
+/// Clones share the same value, so whichever clone takes it first gets it.
+#[derive(Clone)]
+pub struct UserData {
+    type_name: &'static str,
+    value: Arc<Mutex<Payload>>,
+}
+
+impl UserData {
+    pub fn new<T: Any + Send>(value: T) -> UserData {
+        UserData {
+            type_name: any::type_name::<T>(),
+            value: Arc::new(Mutex::new(Some(Box::new(value)))),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Whether the payload is a `T` and hasn't been taken yet.
+    pub fn is<T: Any>(&self) -> bool {
+        self.with(|_: &T| ()).is_some()
+    }
+
+    /// Calls `f` with the payload if it's a `T`.
+    pub fn with<T: Any, R, F: FnOnce(&T) -> R>(&self, f: F) -> Option<R> {
+        self.lock().as_ref().and_then(|x| x.downcast_ref::<T>()).map(f)
+    }
+
+    /// Moves the payload out if it's a `T`, leaving it empty.
+    pub fn take<T: Any>(&self) -> Option<T> {
+        let mut value = self.lock();
+
+        if value.as_ref()?.is::<T>() {
+            value.take()?.downcast().ok().map(|x| *x)
+        } else {
+            None
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Payload> {
+        // The payload is never left half written, so a panic elsewhere doesn't matter
+        self.value.lock().unwrap_or_else(|x| x.into_inner())
+    }
+}
+
+impl fmt::Debug for UserData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UserData({})", self.type_name)
+    }
+}
+
+impl PartialEq for UserData {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+/// How an event got into `UserEvents`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UserEventOrigin {
+    /// `UserEvents::push`, from the game itself. Replays get these from the game again.
+    Queue,
+    /// A `UserEventSender`, possibly on another thread. Replays take these from the recording.
+    Sender,
+}
+
+/// Sending half of `UserEvents`, cheap to clone and usable from any thread.
+#[derive(Clone)]
+pub struct UserEventSender {
+    sender: Sender<(Event, UserEventOrigin)>,
+}
+
+impl UserEventSender {
+    /// Queues an `Event::User` with `code` and `payload`. Returns false if the queue is gone.
+    pub fn push<T: Any + Send>(&self, code: i32, payload: T) -> bool {
+        self.sender.send((user_event(code, payload), UserEventOrigin::Sender)).is_ok()
+    }
+}
+
+/// Queue of user events that `Game::run` merges into the event stream after SDL's events.
+pub struct UserEvents {
+    sender: Sender<(Event, UserEventOrigin)>,
+    receiver: Receiver<(Event, UserEventOrigin)>,
+}
+
+impl UserEvents {
+    pub fn new() -> UserEvents {
+        let (sender, receiver) = mpsc::channel();
+
+        UserEvents {
+            sender,
+            receiver,
+        }
+    }
+
+    pub fn sender(&self) -> UserEventSender {
+        UserEventSender {
+            sender: self.sender.clone(),
+        }
+    }
+
+    pub fn push<T: Any + Send>(&self, code: i32, payload: T) {
+        // The receiver lives in self, so this can't fail
+        let _ = self.sender.send((user_event(code, payload), UserEventOrigin::Queue));
+    }
+
+    /// Takes every event queued so far.
+    pub fn drain(&mut self) -> Vec<Event> {
+        self.receiver.try_iter().map(|(x, _)| x).collect()
+    }
+
+    /// Takes every event queued so far along with how each was queued.
+    pub fn drain_with_origin(&mut self) -> Vec<(Event, UserEventOrigin)> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Default for UserEvents {
+    fn default() -> UserEvents {
+        UserEvents::new()
+    }
+}
+
+fn user_event<T: Any + Send>(code: i32, payload: T) -> Event {
+    Event::User {
+        window_id: 0,
+        kind: USER_EVENT_KIND,
+        code,
+        data: Some(UserData::new(payload)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn event_is_send() {
+        assert_send::<Event>();
+        assert_send::<UserEventSender>();
+    }
+
+    #[test]
+    fn downcast() {
+        let test = UserData::new(String::from("level 2"));
+
+        assert!(test.is::<String>());
+        assert!(!test.is::<u32>());
+        assert_eq!(test.with(|x: &String| x.len()), Some(7));
+        assert_eq!(test.take::<u32>(), None);
+        assert_eq!(test.clone().take::<String>(), Some(String::from("level 2")));
+        assert!(!test.is::<String>());
+    }
+
+    #[test]
+    fn from_other_thread() {
+        let mut events = UserEvents::new();
+        let sender = events.sender();
+
+        thread::spawn(move || sender.push(7, vec![1u8, 2, 3])).join().unwrap();
+        events.push(8, ());
+
+        let test = events.drain();
+
+        assert_eq!(test.len(), 2);
+
+        match &test[0] {
+            Event::User { kind, code, data: Some(data), .. } => {
+                assert_eq!((*kind, *code), (USER_EVENT_KIND, 7));
+                assert_eq!(data.take::<Vec<u8>>(), Some(vec![1, 2, 3]));
+            },
+            x => panic!("unexpected {:?}", x),
+        }
+
+        assert!(events.drain().is_empty());
+
+        events.push(1, ());
+        events.sender().push(2, ());
+
+        let test: Vec<UserEventOrigin> = events.drain_with_origin().into_iter().map(|(_, x)| x).collect();
+
+        assert_eq!(test, vec![UserEventOrigin::Queue, UserEventOrigin::Sender]);
+    }
+}
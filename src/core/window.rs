@@ -0,0 +1,282 @@
+use gl;
+use sdl2::Sdl;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+use sdl2::video::{ FullscreenType, SwapInterval, Window as SdlWindow };
+use crate::Error;
+use crate::core::event::{ Event, WindowEvent };
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Windowed,
+    /// Exclusive fullscreen, changing the display mode to the window size.
+    Fullscreen,
+    /// Borderless window covering the display at its desktop resolution.
+    Borderless,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Dpi {
+    pub diagonal: f32,
+    pub horizontal: f32,
+    pub vertical: f32,
+}
+
+/// A connected display and its desktop mode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Display {
+    pub index: i32,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: i32,
+    /// Not every platform can report DPI.
+    pub dpi: Option<Dpi>,
+}
+
+/// Handle to the game window, passed to `Game::on_window_created`.
+///
+/// Clones refer to the same window. `Game::run` keeps one too, and uses it to keep the GL
+/// viewport the size of the drawable area.
+pub struct Window {
+    window: SdlWindow,
+    sdl: Sdl,
+}
+
+impl Window {
+    pub fn new(window: SdlWindow, sdl: Sdl) -> Window {
+        Window {
+            window,
+            sdl,
+        }
+    }
+
+    pub fn raw(&self) -> &SdlWindow {
+        &self.window
+    }
+
+    pub fn id(&self) -> u32 {
+        self.window.id()
+    }
+
+    pub fn title(&self) -> &str {
+        self.window.title()
+    }
+
+    pub fn set_title(&mut self, title: &str) -> Result<(), Error> {
+        self.window.set_title(title).map_err(|x| Error::Window(x.to_string()))
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.window.size()
+    }
+
+    /// Size in pixels, which is larger than `size` on high DPI displays.
+    pub fn drawable_size(&self) -> (u32, u32) {
+        self.window.drawable_size()
+    }
+
+    /// Ratio of drawable pixels to window units.
+    pub fn scale_factor(&self) -> f32 {
+        let (width, _) = self.window.size();
+        let (drawable_width, _) = self.window.drawable_size();
+
+        if width == 0 { 1. } else { drawable_width as f32 / width as f32 }
+    }
+
+    pub fn set_size(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.window.set_size(width, height).map_err(|x| Error::Window(x.to_string()))
+    }
+
+    pub fn minimum_size(&self) -> (u32, u32) {
+        self.window.minimum_size()
+    }
+
+    pub fn set_minimum_size(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.window.set_minimum_size(width, height).map_err(|x| Error::Window(x.to_string()))
+    }
+
+    pub fn maximum_size(&self) -> (u32, u32) {
+        self.window.maximum_size()
+    }
+
+    pub fn set_maximum_size(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        self.window.set_maximum_size(width, height).map_err(|x| Error::Window(x.to_string()))
+    }
+
+    pub fn set_bordered(&mut self, bordered: bool) {
+        self.window.set_bordered(bordered);
+    }
+
+    pub fn fullscreen(&self) -> FullscreenMode {
+        FullscreenMode::from(self.window.fullscreen_state())
+    }
+
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) -> Result<(), Error> {
+        self.window.set_fullscreen(FullscreenType::from(mode)).map_err(Error::Window)?;
+        self.update_viewport();
+
+        Ok(())
+    }
+
+    /// Switches between `mode` and windowed.
+    pub fn toggle_fullscreen(&mut self, mode: FullscreenMode) -> Result<(), Error> {
+        if self.fullscreen() == FullscreenMode::Windowed {
+            self.set_fullscreen(mode)
+        } else {
+            self.set_fullscreen(FullscreenMode::Windowed)
+        }
+    }
+
+    pub fn vsync(&self) -> bool {
+        self.window.subsystem().gl_get_swap_interval() != SwapInterval::Immediate
+    }
+
+    pub fn set_vsync(&mut self, vsync: bool) -> Result<(), Error> {
+        let swap_interval = if vsync { SwapInterval::VSync } else { SwapInterval::Immediate };
+
+        self.window.subsystem().gl_set_swap_interval(swap_interval).map_err(Error::Window)
+    }
+
+    pub fn is_cursor_shown(&self) -> bool {
+        self.sdl.mouse().is_cursor_showing()
+    }
+
+    pub fn show_cursor(&mut self, show: bool) {
+        self.sdl.mouse().show_cursor(show);
+    }
+
+    pub fn is_cursor_grabbed(&self) -> bool {
+        self.window.grab()
+    }
+
+    /// Keeps the cursor inside the window.
+    pub fn set_cursor_grab(&mut self, grab: bool) {
+        self.window.set_grab(grab);
+    }
+
+    pub fn relative_mouse_mode(&self) -> bool {
+        self.sdl.mouse().relative_mouse_mode()
+    }
+
+    /// Hides the cursor and reports only relative motion, for mouse look.
+    pub fn set_relative_mouse_mode(&mut self, relative: bool) {
+        self.sdl.mouse().set_relative_mouse_mode(relative);
+    }
+
+    /// Sets the window icon from tightly packed RGBA pixels.
+    pub fn set_icon(&mut self, width: u32, height: u32, rgba: &[u8]) -> Result<(), Error> {
+        let too_large = || Error::Window(format!("icon of {}x{} is too large", width, height));
+        let pitch = width.checked_mul(4).ok_or_else(too_large)?;
+        let size = (pitch as usize).checked_mul(height as usize).ok_or_else(too_large)?;
+
+        if rgba.len() != size {
+            return Err(Error::Window(format!("icon is {} bytes, expected {} for {}x{} RGBA", rgba.len(), size, width, height)));
+        }
+
+        let mut pixels = rgba.to_vec();
+        let surface = Surface::from_data(&mut pixels, width, height, pitch, PixelFormatEnum::RGBA32).map_err(Error::Window)?;
+        self.window.set_icon(surface);
+
+        Ok(())
+    }
+
+    /// The display the window is mostly on.
+    pub fn display(&self) -> Result<Display, Error> {
+        let index = self.window.display_index().map_err(Error::Window)?;
+
+        self.display_info(index)
+    }
+
+    pub fn displays(&self) -> Result<Vec<Display>, Error> {
+        let count = self.window.subsystem().num_video_displays().map_err(Error::Window)?;
+
+        (0..count).map(|x| self.display_info(x)).collect()
+    }
+
+    pub fn swap(&self) {
+        self.window.gl_swap_window();
+    }
+
+    /// Sets the GL viewport to cover the whole drawable area.
+    pub fn update_viewport(&self) {
+        let (width, height) = self.window.drawable_size();
+
+        unsafe {
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+    }
+
+    /// Keeps the viewport in step with the window size.
+    pub fn handle_event(&self, event: &Event) {
+        if let Event::Window { window_id, window_event: WindowEvent::Resize(_), .. }
+            | Event::Window { window_id, window_event: WindowEvent::SizeChanged(_), .. } = event {
+            if *window_id == self.window.id() {
+                self.update_viewport();
+            }
+        }
+    }
+
+    fn display_info(&self, index: i32) -> Result<Display, Error> {
+        let video = self.window.subsystem();
+        let bounds = video.display_bounds(index).map_err(Error::Window)?;
+        let mode = video.desktop_display_mode(index).map_err(Error::Window)?;
+
+        Ok(Display {
+            index,
+            name: video.display_name(index).map_err(Error::Window)?,
+            x: bounds.x(),
+            y: bounds.y(),
+            width: bounds.width(),
+            height: bounds.height(),
+            refresh_rate: mode.refresh_rate,
+            dpi: video.display_dpi(index).ok().map(|(diagonal, horizontal, vertical)| Dpi { diagonal, horizontal, vertical }),
+        })
+    }
+}
+
+impl Clone for Window {
+    fn clone(&self) -> Window {
+        Window {
+            // Safe as the context is reference counted, so the window lives until the last clone drops
+            window: unsafe { SdlWindow::from_ref(self.window.context()) },
+            sdl: self.sdl.clone(),
+        }
+    }
+}
+
+impl From<FullscreenType> for FullscreenMode {
+    fn from(fullscreen_type: FullscreenType) -> FullscreenMode {
+        match fullscreen_type {
+            FullscreenType::Off     => FullscreenMode::Windowed,
+            FullscreenType::True    => FullscreenMode::Fullscreen,
+            FullscreenType::Desktop => FullscreenMode::Borderless,
+        }
+    }
+}
+
+impl From<FullscreenMode> for FullscreenType {
+    fn from(mode: FullscreenMode) -> FullscreenType {
+        match mode {
+            FullscreenMode::Windowed   => FullscreenType::Off,
+            FullscreenMode::Fullscreen => FullscreenType::True,
+            FullscreenMode::Borderless => FullscreenType::Desktop,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fullscreen_mode() {
+        for mode in &[FullscreenMode::Windowed, FullscreenMode::Fullscreen, FullscreenMode::Borderless] {
+            let test = FullscreenMode::from(FullscreenType::from(*mode));
+
+            assert_eq!(test, *mode);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::path::PathBuf;
+use crate::graphics::renderer::ShaderStage;
+
+/// Errors produced by the engine.
+#[derive(Debug)]
+pub enum Error {
+    /// SDL or one of its subsystems failed to initialise.
+    SdlInit(String),
+    WindowCreation(String),
+    /// Changing a window setting such as its size, display mode or icon failed.
+    Window(String),
+    GlContext(String),
+    /// A game controller operation such as rumble or adding a mapping failed.
+    Controller(String),
+    ShaderCompile {
+        stage: ShaderStage,
+        log: String,
+    },
+    /// Shader source couldn't be preprocessed, such as an include that can't be found.
+    ShaderSource(String),
+    ProgramLink(String),
+    /// A uniform or uniform block is missing from a program or was set to the wrong type.
+    Uniform(String),
+    /// A render backend rejected a resource or draw.
+    Render(String),
+    /// Image data couldn't be decoded or encoded.
+    Image(String),
+    AssetIo {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// An input recording couldn't be encoded or decoded.
+    Recording(String),
+    /// A colour string isn't a hex code or a known name.
+    Colour(String),
+    /// Images couldn't be packed into a texture atlas, or an atlas descriptor is invalid.
+    Atlas(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::SdlInit(x)                   => write!(f, "failed to initialise SDL: {}", x),
+            Error::WindowCreation(x)            => write!(f, "failed to create window: {}", x),
+            Error::Window(x)                    => write!(f, "window error: {}", x),
+            Error::GlContext(x)                 => write!(f, "failed to set up OpenGL context: {}", x),
+            Error::Controller(x)                => write!(f, "game controller error: {}", x),
+            Error::ShaderCompile { stage, log } => write!(f, "failed to compile {} shader: {}", stage, log),
+            Error::ShaderSource(x)              => write!(f, "invalid shader source: {}", x),
+            Error::ProgramLink(x)               => write!(f, "failed to link shader program: {}", x),
+            Error::Uniform(x)                   => write!(f, "uniform error: {}", x),
+            Error::Render(x)                    => write!(f, "render error: {}", x),
+            Error::Image(x)                     => write!(f, "invalid image: {}", x),
+            Error::AssetIo { path, error }      => write!(f, "failed to access {}: {}", path.display(), error),
+            Error::Recording(x)                 => write!(f, "invalid input recording: {}", x),
+            Error::Colour(x)                    => write!(f, "invalid colour: {}", x),
+            Error::Atlas(x)                     => write!(f, "texture atlas error: {}", x),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::AssetIo { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<sdl2::video::WindowBuildError> for Error {
+    fn from(error: sdl2::video::WindowBuildError) -> Error {
+        Error::WindowCreation(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn display() {
+        let test = Error::ShaderCompile {
+            stage: ShaderStage::Fragment,
+            log: String::from("0:1: syntax error"),
+        }.to_string();
+        let correct = "failed to compile fragment shader: 0:1: syntax error";
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn source() {
+        let test = Error::AssetIo {
+            path: PathBuf::from("missing.png"),
+            error: std::io::Error::from(std::io::ErrorKind::NotFound),
+        };
+
+        assert!(test.source().is_some());
+        assert!(Error::SdlInit(String::new()).source().is_none());
+    }
+}
@@ -0,0 +1,467 @@
+use std::time::Duration;
+use crate::maths::{ Matrix4x4, Vector2D };
+
+/// The part of the screen a camera draws to, in pixels from the top left.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Viewport {
+        Viewport {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The whole of a `width` by `height` screen.
+    pub fn full(width: u32, height: u32) -> Viewport {
+        Viewport::new(0., 0., width as f32, height as f32)
+    }
+
+    pub fn centre(&self) -> Vector2D {
+        Vector2D::from((self.x + self.width / 2., self.y + self.height / 2.))
+    }
+
+    pub fn contains(&self, point: Vector2D) -> bool {
+        let (x, y) = point.xy();
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    /// Sets the GL viewport and scissor box to this on a screen `screen_height` pixels tall,
+    /// so draws land inside it and nothing outside is cleared. Needs a current context.
+    pub fn apply(&self, screen_height: u32) {
+        let bottom = screen_height as i32 - (self.y + self.height).round() as i32;
+        let (x, width, height) = (self.x.round() as i32, self.width.round() as i32, self.height.round() as i32);
+
+        unsafe {
+            gl::Viewport(x, bottom, width, height);
+            gl::Scissor(x, bottom, width, height);
+            gl::Enable(gl::SCISSOR_TEST);
+        }
+    }
+}
+
+/// Trauma based screen shake: the offset grows with the square of the trauma, which wears off
+/// over time.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Shake {
+    trauma: f32,
+    /// Trauma lost per second.
+    decay: f32,
+    max_offset: f32,
+    max_angle: f32,
+    /// Rough number of direction changes per second.
+    frequency: f32,
+    time: f32,
+}
+
+impl Shake {
+    fn amount(&self) -> f32 {
+        self.trauma * self.trauma
+    }
+
+    fn offset(&self) -> Vector2D {
+        let t = self.time * self.frequency;
+        Vector2D::from((noise(t, 0.), noise(t, 1.))) * (self.max_offset * self.amount())
+    }
+
+    fn angle(&self) -> f32 {
+        noise(self.time * self.frequency, 2.) * self.max_angle * self.amount()
+    }
+}
+
+/// Smooth noise in `[-1, 1]` from a few sine waves, with `seed` picking an independent curve.
+fn noise(t: f32, seed: f32) -> f32 {
+    0.5 * (t + seed * 1.7).sin() + 0.3 * (t * 2.3 + seed * 3.1).sin() + 0.2 * (t * 4.7 + seed * 5.3).sin()
+}
+
+/// A 2D camera looking at `position` with the world y axis pointing down the screen, like
+/// pixel coordinates, so at zoom 1 with no rotation a world unit is a pixel.
+///
+/// There are three kinds of coordinates:
+/// - screen: pixels from the top left of the window, as in mouse events
+/// - world: what the game places things in
+/// - normalized: `[-1, 1]` across the viewport with y up, the clip space GL draws to
+///
+/// The transform from `view_projection` assumes the GL viewport is set to the camera's, such
+/// as by `Viewport::apply`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera2D {
+    position: Vector2D,
+    zoom: f32,
+    rotation: f32,
+    viewport: Viewport,
+    bounds: Option<(Vector2D, Vector2D)>,
+    follow_speed: f32,
+    deadzone: Vector2D,
+    shake: Shake,
+}
+
+impl Camera2D {
+    /// A camera centred on the world origin, drawing to `viewport`.
+    pub fn new(viewport: Viewport) -> Camera2D {
+        Camera2D {
+            position: Vector2D::from((0., 0.)),
+            zoom: 1.,
+            rotation: 0.,
+            viewport,
+            bounds: None,
+            follow_speed: 8.,
+            deadzone: Vector2D::from((0., 0.)),
+            shake: Shake {
+                trauma: 0.,
+                decay: 1.5,
+                max_offset: 16.,
+                max_angle: 0.05,
+                frequency: 25.,
+                time: 0.,
+            },
+        }
+    }
+
+    /// Keeps the view inside the world rectangle from `min` to `max`.
+    pub fn with_bounds(mut self, min: Vector2D, max: Vector2D) -> Camera2D {
+        self.set_bounds(Some((min, max)));
+        self
+    }
+
+    /// How quickly `follow` catches up: the gap to the target shrinks by a factor of e every
+    /// 1 / `speed` seconds. Infinity snaps straight to the target.
+    pub fn with_follow_speed(mut self, speed: f32) -> Camera2D {
+        self.follow_speed = speed;
+        self
+    }
+
+    /// Half the size, in world units, of the box around the centre of the view a followed
+    /// target can move in without the camera moving.
+    pub fn with_deadzone(mut self, half_size: Vector2D) -> Camera2D {
+        self.deadzone = half_size;
+        self
+    }
+
+    /// Largest shake offset in world units and rotation in radians, reached at full trauma.
+    pub fn with_shake(mut self, max_offset: f32, max_angle: f32) -> Camera2D {
+        self.shake.max_offset = max_offset;
+        self.shake.max_angle = max_angle;
+        self
+    }
+
+    /// Trauma lost per second, so 1 / `decay` seconds to settle from full trauma.
+    pub fn with_shake_decay(mut self, decay: f32) -> Camera2D {
+        self.shake.decay = decay;
+        self
+    }
+
+    pub fn position(&self) -> Vector2D {
+        self.position
+    }
+
+    /// Centres the view on `position`, kept inside the bounds.
+    pub fn set_position(&mut self, position: Vector2D) {
+        self.position = position;
+        self.clamp();
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Sets how many pixels a world unit covers. Must be positive.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+        self.clamp();
+    }
+
+    /// Zooms by `factor` keeping the world point under `screen` in place, as for zooming
+    /// towards the mouse.
+    pub fn zoom_at(&mut self, factor: f32, screen: Vector2D) {
+        let before = self.screen_to_world(screen);
+        self.zoom *= factor;
+
+        let after = self.screen_to_world(screen);
+        self.set_position(self.position + (before - after));
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// Turns the camera `rotation` radians clockwise on screen, which turns the world the
+    /// other way.
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+        self.clamp();
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+        self.clamp();
+    }
+
+    pub fn bounds(&self) -> Option<(Vector2D, Vector2D)> {
+        self.bounds
+    }
+
+    pub fn set_bounds(&mut self, bounds: Option<(Vector2D, Vector2D)>) {
+        self.bounds = bounds;
+        self.clamp();
+    }
+
+    /// Half the width and height of the world area in view, including the corners a rotated
+    /// view reaches.
+    pub fn half_extents(&self) -> Vector2D {
+        let (sin, cos) = (self.rotation.sin().abs(), self.rotation.cos().abs());
+        let (width, height) = (self.viewport.width, self.viewport.height);
+
+        Vector2D::from((cos * width + sin * height, sin * width + cos * height)) / (2. * self.zoom)
+    }
+
+    /// Moves towards `target` for `elapsed`, easing in exponentially so the speed doesn't
+    /// depend on the frame rate. A target inside the deadzone doesn't move the camera.
+    pub fn follow(&mut self, target: Vector2D, elapsed: Duration) {
+        let outside = |offset: f32, half: f32| {
+            if offset > half {
+                offset - half
+            } else if offset < -half {
+                offset + half
+            } else {
+                0.
+            }
+        };
+
+        let offset = target - self.position;
+        let gap = Vector2D::from((outside(offset.x(), self.deadzone.x()), outside(offset.y(), self.deadzone.y())));
+        let t = 1. - (-self.follow_speed * elapsed.as_secs_f32()).exp();
+
+        self.set_position(self.position + gap * if t.is_nan() { 1. } else { t });
+    }
+
+    /// Adds `trauma`, with 1 shaking as hard as the camera allows.
+    pub fn shake(&mut self, trauma: f32) {
+        self.shake.trauma = (self.shake.trauma + trauma).clamp(0., 1.);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.shake.trauma
+    }
+
+    /// Advances the shake by `elapsed`. Call once a frame.
+    pub fn update(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f32();
+
+        self.shake.time += seconds;
+        self.shake.trauma = (self.shake.trauma - self.shake.decay * seconds).max(0.);
+
+        if self.shake.trauma == 0. {
+            self.shake.time = 0.;
+        }
+    }
+
+    /// Where the view is centred this frame, which is `position` moved by any shake.
+    pub fn eye(&self) -> Vector2D {
+        self.position + self.shake.offset()
+    }
+
+    fn angle(&self) -> f32 {
+        self.rotation + self.shake.angle()
+    }
+
+    /// Takes world coordinates to pixels from the centre of the viewport.
+    pub fn view(&self) -> Matrix4x4 {
+        let (sin, cos) = self.angle().sin_cos();
+        let (x, y) = self.eye().xy();
+        let zoom = self.zoom;
+
+        Matrix4x4::from([
+            zoom * cos, zoom * sin, 0., -zoom * (cos * x + sin * y),
+            -zoom * sin, zoom * cos, 0., zoom * (sin * x - cos * y),
+            0., 0., 1., 0.,
+            0., 0., 0., 1.,
+        ])
+    }
+
+    /// Takes pixels from the centre of the viewport to normalized coordinates.
+    pub fn projection(&self) -> Matrix4x4 {
+        Matrix4x4::from([
+            2. / self.viewport.width, 0., 0., 0.,
+            0., -2. / self.viewport.height, 0., 0.,
+            0., 0., 1., 0.,
+            0., 0., 0., 1.,
+        ])
+    }
+
+    /// Takes world coordinates to normalized coordinates, for `DrawCall::transform`.
+    pub fn view_projection(&self) -> Matrix4x4 {
+        self.projection() * self.view()
+    }
+
+    pub fn world_to_screen(&self, world: Vector2D) -> Vector2D {
+        let (sin, cos) = self.angle().sin_cos();
+        let (x, y) = (world - self.eye()).xy();
+        let local = Vector2D::from((cos * x + sin * y, cos * y - sin * x)) * self.zoom;
+
+        self.viewport.centre() + local
+    }
+
+    /// The world point under `screen`, such as the mouse position from a motion event.
+    pub fn screen_to_world(&self, screen: Vector2D) -> Vector2D {
+        let (sin, cos) = self.angle().sin_cos();
+        let (x, y) = ((screen - self.viewport.centre()) / self.zoom).xy();
+
+        self.eye() + Vector2D::from((cos * x - sin * y, sin * x + cos * y))
+    }
+
+    pub fn screen_to_normalized(&self, screen: Vector2D) -> Vector2D {
+        let (x, y) = (screen - self.viewport.centre()).xy();
+        Vector2D::from((2. * x / self.viewport.width, -2. * y / self.viewport.height))
+    }
+
+    pub fn normalized_to_screen(&self, normalized: Vector2D) -> Vector2D {
+        let (x, y) = normalized.xy();
+        self.viewport.centre() + Vector2D::from((x * self.viewport.width / 2., -y * self.viewport.height / 2.))
+    }
+
+    pub fn world_to_normalized(&self, world: Vector2D) -> Vector2D {
+        self.screen_to_normalized(self.world_to_screen(world))
+    }
+
+    pub fn normalized_to_world(&self, normalized: Vector2D) -> Vector2D {
+        self.screen_to_world(self.normalized_to_screen(normalized))
+    }
+
+    /// Moves `position` so the view stays inside the bounds, centring it on any axis where
+    /// the bounds are smaller than the view. Shake can still briefly show past them.
+    fn clamp(&mut self) {
+        let (min, max) = match self.bounds {
+            Some(x) => x,
+            None => return,
+        };
+
+        let half = self.half_extents();
+        let axis = |position: f32, min: f32, max: f32, half: f32| {
+            if max - min < 2. * half {
+                (min + max) / 2.
+            } else {
+                position.clamp(min + half, max - half)
+            }
+        };
+
+        self.position = Vector2D::from((
+            axis(self.position.x(), min.x(), max.x(), half.x()),
+            axis(self.position.y(), min.y(), max.y(), half.y()),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maths::Vector4D;
+
+    fn close(a: Vector2D, b: Vector2D) -> bool {
+        (a - b).norm() < 1e-3
+    }
+
+    fn camera() -> Camera2D {
+        let mut camera = Camera2D::new(Viewport::new(100., 50., 200., 100.));
+        camera.set_position(Vector2D::from((10., 20.)));
+        camera.set_zoom(2.);
+        camera
+    }
+
+    #[test]
+    fn conversions() {
+        let mut camera = camera();
+
+        assert_eq!(camera.world_to_screen(Vector2D::from((10., 20.))), Vector2D::from((200., 100.)));
+        assert_eq!(camera.world_to_screen(Vector2D::from((15., 25.))), Vector2D::from((210., 110.)));
+        assert_eq!(camera.screen_to_normalized(Vector2D::from((300., 50.))), Vector2D::from((1., 1.)));
+
+        camera.set_rotation(std::f32::consts::FRAC_PI_2);
+        let test = camera.world_to_screen(Vector2D::from((15., 20.)));
+
+        assert!(close(test, Vector2D::from((200., 90.))));
+        assert!(close(camera.screen_to_world(test), Vector2D::from((15., 20.))));
+        assert!(close(camera.normalized_to_world(camera.world_to_normalized(Vector2D::from((-3., 7.)))), Vector2D::from((-3., 7.))));
+    }
+
+    #[test]
+    fn matrices() {
+        let mut camera = camera();
+        camera.set_rotation(0.7);
+
+        let world = Vector2D::from((-4., 9.));
+        let clip = camera.view_projection() * Vector4D::from((world.x(), world.y(), 0., 1.));
+
+        assert!(close(Vector2D::from((clip.x(), clip.y())), camera.world_to_normalized(world)));
+    }
+
+    #[test]
+    fn zoom_at() {
+        let mut camera = camera();
+        let mouse = Vector2D::from((250., 60.));
+        let before = camera.screen_to_world(mouse);
+
+        camera.zoom_at(1.5, mouse);
+
+        assert_eq!(camera.zoom(), 3.);
+        assert!(close(camera.screen_to_world(mouse), before));
+    }
+
+    #[test]
+    fn follow() {
+        let mut camera = Camera2D::new(Viewport::full(100, 100)).with_deadzone(Vector2D::from((5., 5.)));
+
+        camera.follow(Vector2D::from((3., -4.)), Duration::from_millis(100));
+        assert_eq!(camera.position(), Vector2D::from((0., 0.)));
+
+        camera.follow(Vector2D::from((25., 0.)), Duration::from_secs(10));
+        assert!(close(camera.position(), Vector2D::from((20., 0.))));
+
+        let mut camera = camera.with_follow_speed(f32::INFINITY).with_deadzone(Vector2D::from((0., 0.)));
+        camera.follow(Vector2D::from((-8., 2.)), Duration::from_millis(16));
+        assert_eq!(camera.position(), Vector2D::from((-8., 2.)));
+    }
+
+    #[test]
+    fn bounds() {
+        let mut camera = Camera2D::new(Viewport::full(100, 50)).with_bounds(Vector2D::from((0., 0.)), Vector2D::from((400., 40.)));
+
+        assert_eq!(camera.position(), Vector2D::from((50., 20.)));
+
+        camera.set_position(Vector2D::from((1000., 1000.)));
+        assert_eq!(camera.position(), Vector2D::from((350., 20.)));
+
+        camera.set_zoom(2.);
+        camera.set_position(Vector2D::from((0., 0.)));
+        assert_eq!(camera.position(), Vector2D::from((25., 12.5)));
+    }
+
+    #[test]
+    fn shake() {
+        let mut camera = Camera2D::new(Viewport::full(100, 100)).with_shake_decay(2.);
+
+        camera.shake(0.8);
+        camera.shake(0.8);
+        assert_eq!(camera.trauma(), 1.);
+
+        camera.update(Duration::from_millis(100));
+        assert!(camera.eye() != camera.position());
+        assert!((camera.eye() - camera.position()).norm() <= 16. * 2f32.sqrt());
+
+        camera.update(Duration::from_secs(1));
+        assert_eq!(camera.trauma(), 0.);
+        assert_eq!(camera.eye(), camera.position());
+    }
+}
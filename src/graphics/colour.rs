@@ -1,3 +1,405 @@
-use crate::Vector4D;
+use std::fmt;
+use std::str::FromStr;
+use serde::{ Deserialize, Deserializer, Serialize, Serializer };
+use crate::Error;
+use crate::graphics::renderer::backend::BlendMode;
+use crate::maths::Vector4D;
 
+/// Decodes an sRGB channel to linear light.
+pub fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a linear light channel as sRGB.
+pub fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.003_130_8 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// RGBA colour with components in `[0, 1]`, stored sRGB encoded as they're written to the
+/// screen. Alpha is always linear.
+///
+/// Colours read from and written as strings, which is also how they're serialised, are hex
+/// codes such as `#ff8800cc` or one of the names in `Colour::NAMED`.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Colour(Vector4D);
+
+impl Colour {
+    pub const TRANSPARENT: Colour = Colour::new(0., 0., 0., 0.);
+    pub const BLACK: Colour = Colour::rgb(0., 0., 0.);
+    pub const WHITE: Colour = Colour::rgb(1., 1., 1.);
+    pub const GREY: Colour = Colour::rgb(0.5, 0.5, 0.5);
+    pub const RED: Colour = Colour::rgb(1., 0., 0.);
+    pub const GREEN: Colour = Colour::rgb(0., 1., 0.);
+    pub const BLUE: Colour = Colour::rgb(0., 0., 1.);
+    pub const YELLOW: Colour = Colour::rgb(1., 1., 0.);
+    pub const CYAN: Colour = Colour::rgb(0., 1., 1.);
+    pub const MAGENTA: Colour = Colour::rgb(1., 0., 1.);
+    pub const ORANGE: Colour = Colour::rgb(1., 0.5, 0.);
+    pub const PURPLE: Colour = Colour::rgb(0.5, 0., 0.5);
+    pub const PINK: Colour = Colour::rgb(1., 0.75, 0.8);
+    pub const BROWN: Colour = Colour::rgb(0.6, 0.4, 0.2);
+
+    /// The named constants by the lowercase names strings can use for them.
+    pub const NAMED: [(&'static str, Colour); 14] = [
+        ("transparent", Colour::TRANSPARENT),
+        ("black", Colour::BLACK),
+        ("white", Colour::WHITE),
+        ("grey", Colour::GREY),
+        ("red", Colour::RED),
+        ("green", Colour::GREEN),
+        ("blue", Colour::BLUE),
+        ("yellow", Colour::YELLOW),
+        ("cyan", Colour::CYAN),
+        ("magenta", Colour::MAGENTA),
+        ("orange", Colour::ORANGE),
+        ("purple", Colour::PURPLE),
+        ("pink", Colour::PINK),
+        ("brown", Colour::BROWN),
+    ];
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Colour {
+        Colour(Vector4D::new(r, g, b, a))
+    }
+
+    /// An opaque colour.
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Colour {
+        Colour::new(r, g, b, 1.)
+    }
+
+    pub fn from_rgba8(rgba: [u8; 4]) -> Colour {
+        let channel = |x: u8| x as f32 / 255.;
+
+        Colour::new(channel(rgba[0]), channel(rgba[1]), channel(rgba[2]), channel(rgba[3]))
+    }
+
+    /// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, with or without the `#`.
+    pub fn from_hex(hex: &str) -> Result<Colour, Error> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        let invalid = || Error::Colour(format!("'{}' isn't a hex colour", hex));
+
+        if !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |i: usize, len: usize| {
+            let x = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).unwrap();
+            if len == 1 { x * 17 } else { x }
+        };
+
+        match digits.len() {
+            3 => Ok(Colour::from_rgba8([channel(0, 1), channel(1, 1), channel(2, 1), 255])),
+            4 => Ok(Colour::from_rgba8([channel(0, 1), channel(1, 1), channel(2, 1), channel(3, 1)])),
+            6 => Ok(Colour::from_rgba8([channel(0, 2), channel(1, 2), channel(2, 2), 255])),
+            8 => Ok(Colour::from_rgba8([channel(0, 2), channel(1, 2), channel(2, 2), channel(3, 2)])),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// From hue in degrees, and saturation and value in `[0, 1]`.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Colour {
+        let chroma = value * saturation;
+
+        Colour::from_chroma(hue, chroma, value - chroma, alpha)
+    }
+
+    /// From hue in degrees, and saturation and lightness in `[0, 1]`.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Colour {
+        let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+
+        Colour::from_chroma(hue, chroma, lightness - chroma / 2., alpha)
+    }
+
+    fn from_chroma(hue: f32, chroma: f32, lightest: f32, alpha: f32) -> Colour {
+        let sector = hue.rem_euclid(360.) / 60.;
+        let x = chroma * (1. - (sector % 2. - 1.).abs());
+
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.),
+            1 => (x, chroma, 0.),
+            2 => (0., chroma, x),
+            3 => (0., x, chroma),
+            4 => (x, 0., chroma),
+            _ => (chroma, 0., x),
+        };
+
+        Colour::new(r + lightest, g + lightest, b + lightest, alpha)
+    }
+
+    /// From components in linear light, encoding them as sRGB.
+    pub fn from_linear(r: f32, g: f32, b: f32, a: f32) -> Colour {
+        Colour::new(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a)
+    }
+
+    /// Looks up one of `Colour::NAMED`, ignoring case.
+    pub fn named(name: &str) -> Option<Colour> {
+        Colour::NAMED.iter().find(|(x, _)| x.eq_ignore_ascii_case(name)).map(|(_, x)| *x)
+    }
+
+    pub fn r(&self) -> f32 {
+        self.0.x()
+    }
+
+    pub fn g(&self) -> f32 {
+        self.0.y()
+    }
+
+    pub fn b(&self) -> f32 {
+        self.0.z()
+    }
+
+    pub fn a(&self) -> f32 {
+        self.0.w()
+    }
+
+    pub fn with_alpha(&self, a: f32) -> Colour {
+        Colour::new(self.r(), self.g(), self.b(), a)
+    }
+
+    pub fn to_array(&self) -> [f32; 4] {
+        [self.r(), self.g(), self.b(), self.a()]
+    }
+
+    /// Components scaled to bytes, clamped to `[0, 1]` first.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        let channel = |x: f32| (x.clamp(0., 1.) * 255.).round() as u8;
+
+        [channel(self.r()), channel(self.g()), channel(self.b()), channel(self.a())]
+    }
+
+    /// The components in linear light, for lighting maths or a shader expecting them.
+    pub fn to_linear(&self) -> [f32; 4] {
+        [srgb_to_linear(self.r()), srgb_to_linear(self.g()), srgb_to_linear(self.b()), self.a()]
+    }
+
+    /// With the colour multiplied by alpha.
+    pub fn premultiplied(&self) -> Colour {
+        let a = self.a();
+
+        Colour::new(self.r() * a, self.g() * a, self.b() * a, a)
+    }
+
+    /// Undoes `premultiplied`. Fully transparent colours come back black.
+    pub fn unpremultiplied(&self) -> Colour {
+        match self.a() {
+            a if a > 0. => Colour::new(self.r() / a, self.g() / a, self.b() / a, a),
+            _           => Colour::TRANSPARENT,
+        }
+    }
+
+    /// Interpolates each component, giving `self` at 0 and `other` at 1.
+    pub fn lerp(&self, other: Colour, t: f32) -> Colour {
+        let (a, b) = (self.to_array(), other.to_array());
+        let channel = |i: usize| a[i] + (b[i] - a[i]) * t;
+
+        Colour::new(channel(0), channel(1), channel(2), channel(3))
+    }
+
+    /// This colour drawn over `destination` the way a pipeline with `mode` would. Results
+    /// aren't clamped, so additive blending can go over 1.
+    pub fn blend(&self, destination: Colour, mode: BlendMode) -> Colour {
+        let (source, destination) = (self.to_array(), destination.to_array());
+        let alpha = source[3];
+        let mut result = [0.; 4];
+
+        for (i, x) in result.iter_mut().enumerate() {
+            *x = match mode {
+                BlendMode::Replace         => source[i],
+                BlendMode::Alpha if i == 3 => alpha + destination[3] * (1. - alpha),
+                BlendMode::Alpha           => source[i] * alpha + destination[i] * (1. - alpha),
+                BlendMode::Additive        => source[i] * alpha + destination[i],
+                BlendMode::Multiply        => source[i] * destination[i],
+                BlendMode::Screen          => source[i] + destination[i] * (1. - source[i]),
+            };
+        }
+
+        Colour::from(result)
+    }
+}
+
+impl Default for Colour {
+    /// Opaque white, which leaves whatever it tints unchanged.
+    fn default() -> Colour {
+        Colour::WHITE
+    }
+}
+
+impl From<[f32; 4]> for Colour {
+    fn from(rgba: [f32; 4]) -> Colour {
+        Colour::new(rgba[0], rgba[1], rgba[2], rgba[3])
+    }
+}
+
+impl From<Colour> for [f32; 4] {
+    fn from(colour: Colour) -> [f32; 4] {
+        colour.to_array()
+    }
+}
+
+impl fmt::Display for Colour {
+    /// Formats as `#rrggbb`, or `#rrggbbaa` when not opaque.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.to_rgba8();
+
+        match a {
+            255 => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            _   => write!(f, "#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
+        }
+    }
+}
+
+impl FromStr for Colour {
+    type Err = Error;
+
+    /// Parses a hex code or a colour name.
+    fn from_str(s: &str) -> Result<Colour, Error> {
+        let s = s.trim();
+
+        match Colour::named(s) {
+            Some(x) => Ok(x),
+            None if s.starts_with('#') => Colour::from_hex(s),
+            None => Err(Error::Colour(format!("'{}' isn't a hex colour or a colour name", s))),
+        }
+    }
+}
+
+impl Serialize for Colour {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Colour {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Colour, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rounded(colour: Colour) -> [f32; 4] {
+        let mut rgba = colour.to_array();
+
+        for x in rgba.iter_mut() {
+            *x = (*x * 1000.).round() / 1000.;
+        }
+
+        rgba
+    }
+
+    #[test]
+    fn hex() {
+        let test = [
+            Colour::from_hex("#ff8800cc").unwrap().to_rgba8(),
+            Colour::from_hex("ff8800").unwrap().to_rgba8(),
+            Colour::from_hex("#f80").unwrap().to_rgba8(),
+            Colour::from_hex("#f80c").unwrap().to_rgba8(),
+        ];
+        let correct = [[255, 136, 0, 204], [255, 136, 0, 255], [255, 136, 0, 255], [255, 136, 0, 204]];
+
+        assert_eq!(test, correct);
+        assert!(Colour::from_hex("#ff88").is_ok());
+        assert!(Colour::from_hex("#ff880").is_err());
+        assert!(Colour::from_hex("#gg8800").is_err());
+        assert!(Colour::from_hex("#ff88é").is_err());
+    }
+
+    #[test]
+    fn strings() {
+        let colour: Colour = "#ff8800cc".parse().unwrap();
+
+        assert_eq!(colour.to_string(), "#ff8800cc");
+        assert_eq!(Colour::RED.to_string(), "#ff0000");
+        assert_eq!(" Orange ".parse::<Colour>().unwrap(), Colour::ORANGE);
+        assert!(matches!("ff8800".parse::<Colour>(), Err(Error::Colour(_))));
+        assert!(Colour::NAMED.iter().all(|(name, x)| name.parse::<Colour>().unwrap() == *x));
+    }
+
+    #[test]
+    fn serde() {
+        let test = serde_json::to_string(&[Colour::from_rgba8([1, 2, 3, 4]), Colour::WHITE]).unwrap();
+        let correct = "[\"#01020304\",\"#ffffff\"]";
+
+        assert_eq!(test, correct);
+        assert_eq!(serde_json::from_str::<Colour>("\"blue\"").unwrap(), Colour::BLUE);
+    }
+
+    #[test]
+    fn hsv_and_hsl() {
+        let test = [
+            rounded(Colour::from_hsv(0., 1., 1., 1.)),
+            rounded(Colour::from_hsv(120., 0.5, 0.8, 1.)),
+            rounded(Colour::from_hsv(-120., 1., 1., 0.5)),
+            rounded(Colour::from_hsl(30., 1., 0.5, 1.)),
+            rounded(Colour::from_hsl(200., 0., 0.25, 1.)),
+        ];
+        let correct = [
+            [1., 0., 0., 1.],
+            [0.4, 0.8, 0.4, 1.],
+            [0., 0., 1., 0.5],
+            [1., 0.5, 0., 1.],
+            [0.25, 0.25, 0.25, 1.],
+        ];
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn linear() {
+        let colour = Colour::from_linear(0.2, 0.5, 1., 0.5);
+
+        assert_eq!(rounded(Colour::from(colour.to_linear())), [0.2, 0.5, 1., 0.5]);
+        assert!(Colour::GREY.to_linear()[0] < 0.22);
+        assert_eq!(srgb_to_linear(0.), 0.);
+        assert!((linear_to_srgb(1.) - 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn premultiplied() {
+        let colour = Colour::new(1., 0.5, 0.25, 0.5);
+
+        assert_eq!(colour.premultiplied(), Colour::new(0.5, 0.25, 0.125, 0.5));
+        assert_eq!(colour.premultiplied().unpremultiplied(), colour);
+        assert_eq!(Colour::new(1., 1., 1., 0.).unpremultiplied(), Colour::TRANSPARENT);
+    }
+
+    #[test]
+    fn lerp() {
+        let test = Colour::BLACK.lerp(Colour::new(1., 0.5, 0., 0.), 0.5);
+        let correct = Colour::new(0.5, 0.25, 0., 0.5);
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn blend() {
+        let source = Colour::new(1., 0.5, 0., 0.5);
+        let destination = Colour::new(0., 0.5, 1., 1.);
+
+        let test = [
+            source.blend(destination, BlendMode::Replace),
+            source.blend(destination, BlendMode::Alpha),
+            source.blend(destination, BlendMode::Additive),
+            source.blend(destination, BlendMode::Multiply),
+            source.blend(destination, BlendMode::Screen),
+        ];
+        let correct = [
+            source,
+            Colour::new(0.5, 0.5, 0.5, 1.),
+            Colour::new(0.5, 0.75, 1., 1.25),
+            Colour::new(0., 0.25, 0., 0.5),
+            Colour::new(1., 0.75, 1., 1.),
+        ];
+
+        assert_eq!(test, correct);
+    }
+}
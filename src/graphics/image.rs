@@ -0,0 +1,255 @@
+use std::path::Path;
+use crate::Error;
+
+/// Encodings images can be decoded from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Png,
+    Bmp,
+    Tga,
+}
+
+impl ImageFormat {
+    /// Guesses the format from a file extension, ignoring case.
+    pub fn from_extension(extension: &str) -> Option<ImageFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png"                 => Some(ImageFormat::Png),
+            "bmp" | "dib"         => Some(ImageFormat::Bmp),
+            "tga" | "icb" | "vda" => Some(ImageFormat::Tga),
+            _                     => None,
+        }
+    }
+
+    /// Guesses the format from the signature at the start of `bytes`. TGA has no signature so
+    /// is never guessed.
+    pub fn from_bytes(bytes: &[u8]) -> Option<ImageFormat> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if bytes.starts_with(b"BM") {
+            Some(ImageFormat::Bmp)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<ImageFormat> for ::image::ImageFormat {
+    fn from(format: ImageFormat) -> ::image::ImageFormat {
+        match format {
+            ImageFormat::Png => ::image::ImageFormat::Png,
+            ImageFormat::Bmp => ::image::ImageFormat::Bmp,
+            ImageFormat::Tga => ::image::ImageFormat::Tga,
+        }
+    }
+}
+
+/// RGBA8 pixels stored row by row from the top left.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates a fully transparent image. Panics if its size in bytes doesn't fit in a `usize`.
+    pub fn new(width: u32, height: u32) -> Image {
+        let len = byte_len(width, height).unwrap_or_else(|| panic!("a {}x{} image is too large", width, height));
+
+        Image {
+            width,
+            height,
+            pixels: vec![0; len],
+        }
+    }
+
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Result<Image, Error> {
+        if byte_len(width, height) != Some(pixels.len()) {
+            return Err(Error::Image(format!("{} bytes of pixels don't make a {}x{} RGBA image", pixels.len(), width, height)));
+        }
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Decodes a PNG or BMP, working out which from the data.
+    pub fn decode(bytes: &[u8]) -> Result<Image, Error> {
+        let format = ImageFormat::from_bytes(bytes).ok_or_else(|| Error::Image(String::from("unrecognised image format")))?;
+
+        Image::decode_as(bytes, format)
+    }
+
+    /// Decodes `bytes` as `format`, converting whatever pixel format it holds to RGBA8.
+    pub fn decode_as(bytes: &[u8], format: ImageFormat) -> Result<Image, Error> {
+        let decoded = ::image::load_from_memory_with_format(bytes, format.into())
+            .map_err(|x| Error::Image(x.to_string()))?
+            .to_rgba8();
+
+        Image::from_rgba(decoded.width(), decoded.height(), decoded.into_raw())
+    }
+
+    /// Reads and decodes an image file, using the extension to tell the format and falling back
+    /// on the data when there isn't a known one.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Image, Error> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|error| Error::AssetIo { path: path.to_path_buf(), error })?;
+
+        match path.extension().and_then(|x| x.to_str()).and_then(ImageFormat::from_extension) {
+            Some(format) => Image::decode_as(&bytes, format),
+            None         => Image::decode(&bytes),
+        }
+    }
+
+    pub fn encode_png(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        let encoder = ::image::codecs::png::PngEncoder::new(&mut bytes);
+
+        ::image::ImageEncoder::write_image(encoder, &self.pixels, self.width, self.height, ::image::ColorType::Rgba8)
+            .map_err(|x| Error::Image(x.to_string()))?;
+
+        Ok(bytes)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+
+        std::fs::write(path, self.encode_png()?).map_err(|error| Error::AssetIo { path: path.to_path_buf(), error })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = self.index(x, y);
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let i = self.index(x, y);
+        self.pixels[i..i + 4].copy_from_slice(&rgba);
+    }
+
+    pub fn fill(&mut self, rgba: [u8; 4]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+
+    pub fn flip_vertically(&mut self) {
+        let row = self.width as usize * 4;
+
+        for y in 0..(self.height / 2) as usize {
+            let (top, bottom) = self.pixels.split_at_mut((self.height as usize - 1 - y) * row);
+            top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+        }
+    }
+
+    /// Largest per-channel difference to `other`, or `None` if the sizes differ. Golden image
+    /// tests compare this against a small tolerance rather than requiring exact output.
+    pub fn max_difference(&self, other: &Image) -> Option<u8> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+
+        Some(self.pixels.iter().zip(&other.pixels).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0))
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel ({}, {}) is outside a {}x{} image", x, y, self.width, self.height);
+
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+}
+
+/// Bytes needed for `width` by `height` RGBA pixels, if that fits in a `usize`.
+fn byte_len(width: u32, height: u32) -> Option<usize> {
+    (width as usize).checked_mul(height as usize)?.checked_mul(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels() {
+        let mut test = Image::new(2, 2);
+        test.set_pixel(1, 0, [1, 2, 3, 4]);
+
+        assert_eq!(test.pixel(1, 0), [1, 2, 3, 4]);
+        assert_eq!(test.pixels()[4..8], [1, 2, 3, 4]);
+        assert!(matches!(Image::from_rgba(2, 2, vec![0; 15]), Err(Error::Image(_))));
+        assert!(Image::from_rgba(u32::MAX, u32::MAX, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn png() {
+        let correct = Image::from_rgba(2, 1, vec![255, 0, 0, 255, 0, 255, 0, 128]).unwrap();
+        let test = Image::decode(&correct.encode_png().unwrap()).unwrap();
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn decode_tga() {
+        // Uncompressed 24 bit, 1x2, rows stored bottom up
+        let bytes = [
+            0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 0, 24, 0,
+            255, 0, 0,
+            0, 0, 255,
+        ];
+
+        let test = Image::decode_as(&bytes, ImageFormat::Tga).unwrap();
+        let correct = Image::from_rgba(1, 2, vec![255, 0, 0, 255, 0, 0, 255, 255]).unwrap();
+
+        assert_eq!(test, correct);
+        assert!(Image::decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(ImageFormat::from_extension("PNG"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_extension("tga"), Some(ImageFormat::Tga));
+        assert_eq!(ImageFormat::from_extension("gif"), None);
+        assert_eq!(ImageFormat::from_bytes(b"BM\0\0"), Some(ImageFormat::Bmp));
+        assert!(matches!(Image::decode_as(b"BMnonsense", ImageFormat::Bmp), Err(Error::Image(_))));
+    }
+
+    #[test]
+    fn flip_vertically() {
+        let mut test = Image::from_rgba(1, 3, vec![1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3]).unwrap();
+        test.flip_vertically();
+
+        let correct = Image::from_rgba(1, 3, vec![3, 3, 3, 3, 2, 2, 2, 2, 1, 1, 1, 1]).unwrap();
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn max_difference() {
+        let a = Image::from_rgba(1, 1, vec![10, 20, 30, 255]).unwrap();
+        let b = Image::from_rgba(1, 1, vec![12, 17, 30, 255]).unwrap();
+
+        assert_eq!(a.max_difference(&b), Some(3));
+        assert_eq!(a.max_difference(&Image::new(2, 1)), None);
+    }
+}
@@ -1,3 +1,11 @@
+pub mod camera;
+pub mod colour;
+pub mod image;
 pub mod renderer;
 pub mod shapes;
 pub mod sprite;
+pub mod texture;
+
+pub use camera::Camera2D;
+pub use colour::Colour;
+pub use texture::Texture;
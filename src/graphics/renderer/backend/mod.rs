@@ -0,0 +1,207 @@
+use crate::Error;
+use crate::graphics::image::Image;
+use crate::graphics::renderer::vertex::Vertex;
+use crate::maths::Matrix4x4;
+
+pub mod opengl;
+pub mod software;
+
+pub use opengl::GlBackend;
+pub use software::SoftwareBackend;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BufferId(pub(crate) u32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextureId(pub(crate) u32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PipelineId(pub(crate) u32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RenderTargetId(pub(crate) u32);
+
+/// Where a render pass draws to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// The window, or the backend's own image for backends without one.
+    Screen,
+    Texture(RenderTargetId),
+}
+
+/// How fragments are combined with what's already in the target.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    Replace,
+    Alpha,
+    Additive,
+    Multiply,
+    Screen,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShaderSource {
+    /// Built in shading: the texture colour, or white, multiplied by the vertex colour.
+    Default,
+    /// Custom GLSL. Backends that can't run GLSL refuse to create the pipeline.
+    Glsl {
+        vertex: String,
+        fragment: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PipelineDescriptor {
+    pub shader: ShaderSource,
+    pub blend: BlendMode,
+}
+
+impl Default for PipelineDescriptor {
+    fn default() -> PipelineDescriptor {
+        PipelineDescriptor {
+            shader: ShaderSource::Default,
+            blend: BlendMode::Alpha,
+        }
+    }
+}
+
+/// A single draw of triangles.
+#[derive(Copy, Clone, Debug)]
+pub struct DrawCall {
+    pub pipeline: PipelineId,
+    pub vertices: BufferId,
+    /// Index buffer to draw through. Without one the vertices are drawn in order.
+    pub indices: Option<BufferId>,
+    /// First index, or vertex without an index buffer, to draw from.
+    pub first: usize,
+    /// Number of indices or vertices to draw, a multiple of three.
+    pub count: usize,
+    pub texture: Option<TextureId>,
+    /// Takes vertex positions to clip space.
+    pub transform: Matrix4x4,
+}
+
+impl DrawCall {
+    pub fn new(pipeline: PipelineId, vertices: BufferId, count: usize) -> DrawCall {
+        DrawCall {
+            pipeline,
+            vertices,
+            indices: None,
+            first: 0,
+            count,
+            texture: None,
+            transform: Matrix4x4::identity(),
+        }
+    }
+}
+
+/// Graphics API the renderer draws through.
+///
+/// Resources are referred to by ids that are only meaningful to the backend that made them.
+/// Texture data is RGBA8 with the first row at the top, which is also how render targets are
+/// laid out, so the same draws give the same image on every backend.
+pub trait Backend {
+    fn create_vertex_buffer(&mut self, vertices: &[Vertex]) -> Result<BufferId, Error>;
+    fn create_index_buffer(&mut self, indices: &[u32]) -> Result<BufferId, Error>;
+    /// Overwrites vertices from `offset` onwards. The buffer doesn't grow.
+    fn update_vertex_buffer(&mut self, buffer: BufferId, offset: usize, vertices: &[Vertex]) -> Result<(), Error>;
+    /// Overwrites indices from `offset` onwards. The buffer doesn't grow.
+    fn update_index_buffer(&mut self, buffer: BufferId, offset: usize, indices: &[u32]) -> Result<(), Error>;
+    fn destroy_buffer(&mut self, buffer: BufferId);
+
+    fn create_texture(&mut self, image: &Image) -> Result<TextureId, Error>;
+    /// Replaces the pixels of the texture with `image`, with its top left corner at `x`, `y`.
+    fn update_texture(&mut self, texture: TextureId, x: u32, y: u32, image: &Image) -> Result<(), Error>;
+    fn destroy_texture(&mut self, texture: TextureId);
+
+    fn create_pipeline(&mut self, descriptor: &PipelineDescriptor) -> Result<PipelineId, Error>;
+    fn destroy_pipeline(&mut self, pipeline: PipelineId);
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> Result<RenderTargetId, Error>;
+    /// The texture a render target draws into, for sampling in later passes.
+    fn render_target_texture(&self, target: RenderTargetId) -> Option<TextureId>;
+    fn destroy_render_target(&mut self, target: RenderTargetId);
+
+    /// Makes `target` the destination of the following draws, clearing it first if asked.
+    fn begin_pass(&mut self, target: Target, clear: Option<[f32; 4]>) -> Result<(), Error>;
+    fn draw(&mut self, draw: &DrawCall) -> Result<(), Error>;
+    fn read_pixels(&mut self, target: Target) -> Result<Image, Error>;
+}
+
+/// The default pipeline and the vertex and index buffers a batch draws through. They're kept
+/// between frames and only recreated when what's queued no longer fits, so a batch should
+/// always be flushed to the same backend.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct BatchBuffers {
+    pub pipeline: PipelineId,
+    pub vertex_buffer: BufferId,
+    pub index_buffer: BufferId,
+    pub vertex_capacity: usize,
+    pub index_capacity: usize,
+}
+
+impl BatchBuffers {
+    /// Makes sure `buffers` can hold `vertices` and `indices`, replacing them with ones of the
+    /// next power of two vertices if not. `index_data` fills the new index buffer given its
+    /// vertex capacity, and must return at least `indices` of them. The old buffers are only
+    /// destroyed once the new ones exist, so they're left as they were on error.
+    pub fn reserve<B, F>(buffers: &mut Option<BatchBuffers>, backend: &mut B, vertices: usize, indices: usize, index_data: F) -> Result<BatchBuffers, Error>
+    where
+        B: Backend + ?Sized,
+        F: FnOnce(usize) -> Vec<u32>,
+    {
+        let (pipeline, new_pipeline) = match buffers {
+            Some(x) if x.vertex_capacity >= vertices && x.index_capacity >= indices => return Ok(*x),
+            Some(x) => (x.pipeline, false),
+            None => (backend.create_pipeline(&PipelineDescriptor::default())?, true),
+        };
+
+        let vertex_capacity = vertices.next_power_of_two().max(256);
+        let index_data = index_data(vertex_capacity);
+        let empty = Vertex::new([0., 0.], [0., 0.], [0., 0., 0., 0.]);
+
+        let created = backend.create_vertex_buffer(&vec![empty; vertex_capacity]).and_then(|vertex_buffer| {
+            match backend.create_index_buffer(&index_data) {
+                Ok(index_buffer) => Ok((vertex_buffer, index_buffer)),
+                Err(x) => {
+                    backend.destroy_buffer(vertex_buffer);
+                    Err(x)
+                },
+            }
+        });
+
+        let (vertex_buffer, index_buffer) = match created {
+            Ok(x) => x,
+            Err(x) => {
+                if new_pipeline {
+                    backend.destroy_pipeline(pipeline);
+                }
+
+                return Err(x);
+            },
+        };
+
+        let reserved = BatchBuffers {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            vertex_capacity,
+            index_capacity: index_data.len(),
+        };
+
+        if let Some(old) = buffers.replace(reserved) {
+            backend.destroy_buffer(old.vertex_buffer);
+            backend.destroy_buffer(old.index_buffer);
+        }
+
+        Ok(reserved)
+    }
+}
+
+fn missing(kind: &str, id: u32) -> Error {
+    Error::Render(format!("no {} with id {}", kind, id))
+}
+
+fn out_of_bounds(offset: usize, len: usize, capacity: usize) -> Error {
+    Error::Render(format!("writing {} elements at {} overflows a buffer of {}", len, offset, capacity))
+}
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use crate::Error;
+use crate::graphics::image::Image;
+use crate::graphics::renderer::{ Program, Shader, ShaderStage };
+use crate::graphics::renderer::buffer::{ BufferUsage, IndexBuffer, Primitive, VertexArray, VertexBuffer };
+use crate::graphics::renderer::uniform::TextureUnit;
+use crate::graphics::renderer::vertex::Vertex;
+use crate::graphics::texture::{ Texture, TextureOptions };
+use crate::maths::Matrix4x4;
+use super::{ missing, Backend, BlendMode, BufferId, DrawCall, PipelineDescriptor, PipelineId, RenderTargetId, ShaderSource, Target, TextureId };
+
+const DEFAULT_VERTEX_SHADER: &str = "#version 330 core
+layout (location = 0) in vec2 position;
+layout (location = 1) in vec2 uv;
+layout (location = 2) in vec4 colour;
+
+uniform mat4 transform;
+
+out vec2 frag_uv;
+out vec4 frag_colour;
+
+void main() {
+    frag_uv = uv;
+    frag_colour = colour;
+    gl_Position = transform * vec4(position, 0.0, 1.0);
+}
+";
+
+const DEFAULT_FRAGMENT_SHADER: &str = "#version 330 core
+in vec2 frag_uv;
+in vec4 frag_colour;
+
+uniform sampler2D texture0;
+
+out vec4 colour;
+
+void main() {
+    colour = texture(texture0, frag_uv) * frag_colour;
+}
+";
+
+enum Buffer {
+    Vertex(VertexBuffer<Vertex>),
+    /// Indices are kept on the CPU too, to check draws against the vertex buffer.
+    Index(IndexBuffer, Vec<u32>),
+}
+
+struct Pipeline {
+    program: Program,
+    blend: BlendMode,
+}
+
+struct RenderTarget {
+    framebuffer: gl::types::GLuint,
+    texture: TextureId,
+}
+
+/// Draws through OpenGL 3.3. Needs a current context, so create it after the window.
+///
+/// Custom GLSL pipelines get the vertex attributes at locations 0 (position), 1 (uv) and
+/// 2 (colour), and the `transform` and `texture0` uniforms, like the default shader.
+pub struct GlBackend {
+    screen: (u32, u32),
+    vertex_array: VertexArray,
+    white: TextureId,
+    buffers: HashMap<u32, Buffer>,
+    textures: HashMap<u32, Texture>,
+    pipelines: HashMap<u32, Pipeline>,
+    render_targets: HashMap<u32, RenderTarget>,
+    target: Target,
+    next_id: u32,
+}
+
+impl GlBackend {
+    pub fn new(width: u32, height: u32) -> Result<GlBackend, Error> {
+        let mut backend = GlBackend {
+            screen: (width, height),
+            vertex_array: VertexArray::new(),
+            white: TextureId(0),
+            buffers: HashMap::new(),
+            textures: HashMap::new(),
+            pipelines: HashMap::new(),
+            render_targets: HashMap::new(),
+            target: Target::Screen,
+            next_id: 1,
+        };
+
+        // Untextured draws sample this so one shader covers both
+        let mut white = Image::new(1, 1);
+        white.fill([255; 4]);
+        backend.white = backend.create_texture(&white)?;
+
+        Ok(backend)
+    }
+
+    /// Sets the size of the default framebuffer, which should follow the window's drawable size.
+    pub fn set_screen_size(&mut self, width: u32, height: u32) {
+        self.screen = (width, height);
+    }
+
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn insert_buffer(&mut self, buffer: Buffer) -> BufferId {
+        let key = self.next_id();
+        self.buffers.insert(key, buffer);
+
+        BufferId(key)
+    }
+
+    fn target_size(&self, target: Target) -> Result<(gl::types::GLuint, u32, u32), Error> {
+        match target {
+            Target::Screen => Ok((0, self.screen.0, self.screen.1)),
+            Target::Texture(x) => {
+                let render_target = self.render_targets.get(&x.0).ok_or_else(|| missing("render target", x.0))?;
+                let texture = self.textures.get(&render_target.texture.0).ok_or_else(|| missing("texture", render_target.texture.0))?;
+
+                Ok((render_target.framebuffer, texture.width(), texture.height()))
+            },
+        }
+    }
+}
+
+impl Backend for GlBackend {
+    fn create_vertex_buffer(&mut self, vertices: &[Vertex]) -> Result<BufferId, Error> {
+        Ok(self.insert_buffer(Buffer::Vertex(VertexBuffer::new(vertices, BufferUsage::Dynamic))))
+    }
+
+    fn create_index_buffer(&mut self, indices: &[u32]) -> Result<BufferId, Error> {
+        Ok(self.insert_buffer(Buffer::Index(IndexBuffer::new(indices, BufferUsage::Dynamic), indices.to_vec())))
+    }
+
+    fn update_vertex_buffer(&mut self, buffer: BufferId, offset: usize, vertices: &[Vertex]) -> Result<(), Error> {
+        match self.buffers.get_mut(&buffer.0) {
+            Some(Buffer::Vertex(x)) => x.update(offset, vertices),
+            _ => Err(missing("vertex buffer", buffer.0)),
+        }
+    }
+
+    fn update_index_buffer(&mut self, buffer: BufferId, offset: usize, indices: &[u32]) -> Result<(), Error> {
+        match self.buffers.get_mut(&buffer.0) {
+            Some(Buffer::Index(x, copy)) => {
+                x.update(offset, indices)?;
+                copy[offset..offset + indices.len()].copy_from_slice(indices);
+
+                Ok(())
+            },
+            _ => Err(missing("index buffer", buffer.0)),
+        }
+    }
+
+    fn destroy_buffer(&mut self, buffer: BufferId) {
+        self.buffers.remove(&buffer.0);
+    }
+
+    fn create_texture(&mut self, image: &Image) -> Result<TextureId, Error> {
+        let texture = Texture::from_image(image, TextureOptions::default())?;
+
+        let key = self.next_id();
+        self.textures.insert(key, texture);
+
+        Ok(TextureId(key))
+    }
+
+    fn update_texture(&mut self, texture: TextureId, x: u32, y: u32, image: &Image) -> Result<(), Error> {
+        self.textures.get_mut(&texture.0).ok_or_else(|| missing("texture", texture.0))?.update(x, y, image)
+    }
+
+    fn destroy_texture(&mut self, texture: TextureId) {
+        self.textures.remove(&texture.0);
+    }
+
+    fn create_pipeline(&mut self, descriptor: &PipelineDescriptor) -> Result<PipelineId, Error> {
+        let (vertex, fragment) = match &descriptor.shader {
+            ShaderSource::Default => (DEFAULT_VERTEX_SHADER, DEFAULT_FRAGMENT_SHADER),
+            ShaderSource::Glsl { vertex, fragment } => (vertex.as_str(), fragment.as_str()),
+        };
+
+        let program = Program::from(&[Shader::compile(vertex, ShaderStage::Vertex)?, Shader::compile(fragment, ShaderStage::Fragment)?])?;
+
+        let pipeline = Pipeline {
+            program,
+            blend: descriptor.blend,
+        };
+
+        let key = self.next_id();
+        self.pipelines.insert(key, pipeline);
+
+        Ok(PipelineId(key))
+    }
+
+    fn destroy_pipeline(&mut self, pipeline: PipelineId) {
+        self.pipelines.remove(&pipeline.0);
+    }
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> Result<RenderTargetId, Error> {
+        let texture = self.create_texture(&Image::new(width, height))?;
+        let texture_id = self.textures.get(&texture.0).ok_or_else(|| missing("texture", texture.0))?.id();
+        let mut framebuffer = 0;
+
+        let status = unsafe {
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture_id, 0);
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            status
+        };
+
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            unsafe { gl::DeleteFramebuffers(1, &framebuffer) };
+            self.destroy_texture(texture);
+
+            return Err(Error::Render(format!("framebuffer is incomplete ({:#x})", status)));
+        }
+
+        let key = self.next_id();
+        self.render_targets.insert(key, RenderTarget { framebuffer, texture });
+
+        Ok(RenderTargetId(key))
+    }
+
+    fn render_target_texture(&self, target: RenderTargetId) -> Option<TextureId> {
+        self.render_targets.get(&target.0).map(|x| x.texture)
+    }
+
+    fn destroy_render_target(&mut self, target: RenderTargetId) {
+        if let Some(x) = self.render_targets.remove(&target.0) {
+            unsafe { gl::DeleteFramebuffers(1, &x.framebuffer) };
+            self.destroy_texture(x.texture);
+        }
+    }
+
+    fn begin_pass(&mut self, target: Target, clear: Option<[f32; 4]>) -> Result<(), Error> {
+        let (framebuffer, width, height) = self.target_size(target)?;
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            gl::Viewport(0, 0, width as i32, height as i32);
+
+            if let Some([r, g, b, a]) = clear {
+                gl::ClearColor(r, g, b, a);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+        }
+
+        self.target = target;
+
+        Ok(())
+    }
+
+    fn draw(&mut self, draw: &DrawCall) -> Result<(), Error> {
+        let pipeline = self.pipelines.get(&draw.pipeline.0).ok_or_else(|| missing("pipeline", draw.pipeline.0))?;
+
+        let vertices = match self.buffers.get(&draw.vertices.0) {
+            Some(Buffer::Vertex(x)) => x,
+            _ => return Err(missing("vertex buffer", draw.vertices.0)),
+        };
+
+        let indices = match draw.indices {
+            Some(buffer) => match self.buffers.get(&buffer.0) {
+                Some(Buffer::Index(x, copy)) => {
+                    let range = draw.first.checked_add(draw.count)
+                        .and_then(|end| copy.get(draw.first..end))
+                        .ok_or_else(|| Error::Render(String::from("draw range is outside the index buffer")))?;
+
+                    if range.iter().any(|x| *x as usize >= vertices.len()) {
+                        return Err(Error::Render(String::from("draw references vertices past the end of the buffer")));
+                    }
+
+                    Some(x)
+                },
+                _ => return Err(missing("index buffer", buffer.0)),
+            },
+            None => match draw.first.checked_add(draw.count) {
+                Some(end) if end <= vertices.len() => None,
+                _ => return Err(Error::Render(String::from("draw references vertices past the end of the buffer"))),
+            },
+        };
+
+        let texture = draw.texture.unwrap_or(self.white);
+        let texture = self.textures.get(&texture.0).ok_or_else(|| missing("texture", texture.0))?;
+
+        // Framebuffer rows run bottom up, so flip offscreen draws to store them top row first
+        // like every other texture
+        let transform = match self.target {
+            Target::Screen => draw.transform,
+            Target::Texture(_) => Matrix4x4::from([
+                1., 0., 0., 0.,
+                0., -1., 0., 0.,
+                0., 0., 1., 0.,
+                0., 0., 0., 1.,
+            ]) * draw.transform,
+        };
+
+        // Custom shaders may leave either uniform out, in which case the driver drops it
+        pipeline.program.activate();
+
+        if pipeline.program.has_uniform("transform") {
+            pipeline.program.set_uniform("transform", transform)?;
+        }
+
+        if pipeline.program.has_uniform("texture0") {
+            pipeline.program.set_uniform("texture0", TextureUnit(0))?;
+        }
+
+        texture.bind(0);
+
+        unsafe {
+            match pipeline.blend {
+                BlendMode::Replace  => gl::Disable(gl::BLEND),
+                BlendMode::Alpha    => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+                },
+                BlendMode::Additive => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::SRC_ALPHA, gl::ONE);
+                },
+                BlendMode::Multiply => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::DST_COLOR, gl::ZERO);
+                },
+                BlendMode::Screen   => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_COLOR);
+                },
+            }
+
+        }
+
+        self.vertex_array.set_vertex_buffer(vertices);
+
+        // The range and indices were checked against the buffers above
+        match indices {
+            Some(x) => {
+                self.vertex_array.set_index_buffer(x);
+                unsafe { self.vertex_array.draw_elements(Primitive::Triangles, draw.first, draw.count) };
+            },
+            None => unsafe { self.vertex_array.draw_arrays(Primitive::Triangles, draw.first, draw.count) },
+        }
+
+        Ok(())
+    }
+
+    fn read_pixels(&mut self, target: Target) -> Result<Image, Error> {
+        let (framebuffer, width, height) = self.target_size(target)?;
+        let mut image = Image::new(width, height);
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(0, 0, width as i32, height as i32, gl::RGBA, gl::UNSIGNED_BYTE, image.pixels_mut().as_mut_ptr() as *mut _);
+        }
+
+        // Offscreen targets are already stored top row first
+        if target == Target::Screen {
+            image.flip_vertically();
+        }
+
+        self.begin_pass(self.target, None)?;
+
+        Ok(image)
+    }
+}
+
+impl Drop for GlBackend {
+    fn drop(&mut self) {
+        unsafe {
+            for x in self.render_targets.values() {
+                gl::DeleteFramebuffers(1, &x.framebuffer);
+            }
+        }
+    }
+}
@@ -0,0 +1,535 @@
+use std::collections::HashMap;
+use crate::Error;
+use crate::graphics::Colour;
+use crate::graphics::image::Image;
+use crate::graphics::renderer::vertex::Vertex;
+use crate::maths::Vector4D;
+use super::{ missing, out_of_bounds, Backend, BlendMode, BufferId, DrawCall, PipelineDescriptor, PipelineId, RenderTargetId, ShaderSource, Target, TextureId };
+
+enum Buffer {
+    Vertices(Vec<Vertex>),
+    Indices(Vec<u32>),
+}
+
+/// Vertex after the transform, in pixels from the top left of the target.
+#[derive(Copy, Clone)]
+struct Projected {
+    x: f32,
+    y: f32,
+    /// 1 / w, for perspective correct interpolation.
+    inverse_w: f32,
+    uv: [f32; 2],
+    colour: [f32; 4],
+}
+
+/// CPU rasteriser drawing into RGBA images, for checking rendering without a GPU.
+///
+/// Pixels are covered when their centre is inside a triangle, with the top left rule for
+/// centres exactly on an edge, so triangles sharing an edge never both draw a pixel. Textures
+/// are sampled nearest with clamping at the edges.
+pub struct SoftwareBackend {
+    screen: Image,
+    buffers: HashMap<u32, Buffer>,
+    textures: HashMap<u32, Image>,
+    pipelines: HashMap<u32, BlendMode>,
+    render_targets: HashMap<u32, TextureId>,
+    target: Target,
+    next_id: u32,
+}
+
+impl SoftwareBackend {
+    pub fn new(width: u32, height: u32) -> SoftwareBackend {
+        SoftwareBackend {
+            screen: Image::new(width, height),
+            buffers: HashMap::new(),
+            textures: HashMap::new(),
+            pipelines: HashMap::new(),
+            render_targets: HashMap::new(),
+            target: Target::Screen,
+            next_id: 1,
+        }
+    }
+
+    pub fn screen(&self) -> &Image {
+        &self.screen
+    }
+
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn image(&self, target: Target) -> Result<&Image, Error> {
+        match target {
+            Target::Screen => Ok(&self.screen),
+            Target::Texture(x) => {
+                let texture = self.render_targets.get(&x.0).ok_or_else(|| missing("render target", x.0))?;
+                self.textures.get(&texture.0).ok_or_else(|| missing("texture", texture.0))
+            },
+        }
+    }
+
+    fn image_mut(&mut self, target: Target) -> Result<&mut Image, Error> {
+        match target {
+            Target::Screen => Ok(&mut self.screen),
+            Target::Texture(x) => {
+                let texture = self.render_targets.get(&x.0).ok_or_else(|| missing("render target", x.0))?;
+                self.textures.get_mut(&texture.0).ok_or_else(|| missing("texture", texture.0))
+            },
+        }
+    }
+}
+
+impl Backend for SoftwareBackend {
+    fn create_vertex_buffer(&mut self, vertices: &[Vertex]) -> Result<BufferId, Error> {
+        let id = self.next_id();
+        self.buffers.insert(id, Buffer::Vertices(vertices.to_vec()));
+
+        Ok(BufferId(id))
+    }
+
+    fn create_index_buffer(&mut self, indices: &[u32]) -> Result<BufferId, Error> {
+        let id = self.next_id();
+        self.buffers.insert(id, Buffer::Indices(indices.to_vec()));
+
+        Ok(BufferId(id))
+    }
+
+    fn update_vertex_buffer(&mut self, buffer: BufferId, offset: usize, vertices: &[Vertex]) -> Result<(), Error> {
+        match self.buffers.get_mut(&buffer.0) {
+            Some(Buffer::Vertices(x)) => update(x, offset, vertices),
+            _ => Err(missing("vertex buffer", buffer.0)),
+        }
+    }
+
+    fn update_index_buffer(&mut self, buffer: BufferId, offset: usize, indices: &[u32]) -> Result<(), Error> {
+        match self.buffers.get_mut(&buffer.0) {
+            Some(Buffer::Indices(x)) => update(x, offset, indices),
+            _ => Err(missing("index buffer", buffer.0)),
+        }
+    }
+
+    fn destroy_buffer(&mut self, buffer: BufferId) {
+        self.buffers.remove(&buffer.0);
+    }
+
+    fn create_texture(&mut self, image: &Image) -> Result<TextureId, Error> {
+        let id = self.next_id();
+        self.textures.insert(id, image.clone());
+
+        Ok(TextureId(id))
+    }
+
+    fn update_texture(&mut self, texture: TextureId, x: u32, y: u32, image: &Image) -> Result<(), Error> {
+        let target = self.textures.get_mut(&texture.0).ok_or_else(|| missing("texture", texture.0))?;
+
+        let inside = match (x.checked_add(image.width()), y.checked_add(image.height())) {
+            (Some(right), Some(bottom)) => right <= target.width() && bottom <= target.height(),
+            _ => false,
+        };
+
+        if !inside {
+            return Err(Error::Render(format!("{}x{} update at ({}, {}) is outside the texture", image.width(), image.height(), x, y)));
+        }
+
+        for row in 0..image.height() {
+            for column in 0..image.width() {
+                target.set_pixel(x + column, y + row, image.pixel(column, row));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn destroy_texture(&mut self, texture: TextureId) {
+        self.textures.remove(&texture.0);
+    }
+
+    fn create_pipeline(&mut self, descriptor: &PipelineDescriptor) -> Result<PipelineId, Error> {
+        if let ShaderSource::Glsl { .. } = descriptor.shader {
+            return Err(Error::Render(String::from("the software backend can't run GLSL shaders")));
+        }
+
+        let id = self.next_id();
+        self.pipelines.insert(id, descriptor.blend);
+
+        Ok(PipelineId(id))
+    }
+
+    fn destroy_pipeline(&mut self, pipeline: PipelineId) {
+        self.pipelines.remove(&pipeline.0);
+    }
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> Result<RenderTargetId, Error> {
+        let texture = self.create_texture(&Image::new(width, height))?;
+        let id = self.next_id();
+        self.render_targets.insert(id, texture);
+
+        Ok(RenderTargetId(id))
+    }
+
+    fn render_target_texture(&self, target: RenderTargetId) -> Option<TextureId> {
+        self.render_targets.get(&target.0).copied()
+    }
+
+    fn destroy_render_target(&mut self, target: RenderTargetId) {
+        if let Some(texture) = self.render_targets.remove(&target.0) {
+            self.destroy_texture(texture);
+        }
+    }
+
+    fn begin_pass(&mut self, target: Target, clear: Option<[f32; 4]>) -> Result<(), Error> {
+        let image = self.image_mut(target)?;
+
+        if let Some(colour) = clear {
+            image.fill(Colour::from(colour).to_rgba8());
+        }
+
+        self.target = target;
+
+        Ok(())
+    }
+
+    fn draw(&mut self, draw: &DrawCall) -> Result<(), Error> {
+        let blend = *self.pipelines.get(&draw.pipeline.0).ok_or_else(|| missing("pipeline", draw.pipeline.0))?;
+
+        let vertices = match self.buffers.get(&draw.vertices.0) {
+            Some(Buffer::Vertices(x)) => x,
+            _ => return Err(missing("vertex buffer", draw.vertices.0)),
+        };
+
+        let past_end = || Error::Render(String::from("draw references vertices past the end of the buffer"));
+        let end = draw.first.checked_add(draw.count).ok_or_else(|| Error::Render(String::from("draw range overflows")))?;
+
+        // Copied, as the image is borrowed mutably to draw into
+        let indices: Option<Vec<u32>> = match draw.indices {
+            Some(buffer) => match self.buffers.get(&buffer.0) {
+                Some(Buffer::Indices(x)) => {
+                    let range = x.get(draw.first..end).ok_or_else(|| Error::Render(String::from("draw range is outside the index buffer")))?;
+
+                    if range.iter().any(|x| *x as usize >= vertices.len()) {
+                        return Err(past_end());
+                    }
+
+                    Some(range.to_vec())
+                },
+                _ => return Err(missing("index buffer", buffer.0)),
+            },
+            None if end <= vertices.len() => None,
+            None => return Err(past_end()),
+        };
+
+        let texture = match draw.texture {
+            Some(x) => Some(self.textures.get(&x.0).ok_or_else(|| missing("texture", x.0))?.clone()),
+            None => None,
+        };
+
+        let (width, height) = {
+            let image = self.image(self.target)?;
+            (image.width(), image.height())
+        };
+
+        let projected: Vec<Option<Projected>> = vertices.iter().map(|vertex| {
+            let clip = draw.transform * Vector4D::from((vertex.position[0], vertex.position[1], 0., 1.));
+
+            // Anything behind the eye would need clipping, which 2D drawing never calls for
+            if clip.w() <= 0. {
+                return None;
+            }
+
+            Some(Projected {
+                x: (clip.x() / clip.w() + 1.) / 2. * width as f32,
+                y: (1. - clip.y() / clip.w()) / 2. * height as f32,
+                inverse_w: 1. / clip.w(),
+                uv: vertex.uv,
+                colour: vertex.colour,
+            })
+        }).collect();
+
+        let image = self.image_mut(self.target)?;
+
+        let vertex = |i: usize| match &indices {
+            Some(x) => projected[x[i] as usize],
+            None => projected[draw.first + i],
+        };
+
+        for i in (0..draw.count - draw.count % 3).step_by(3) {
+            if let (Some(a), Some(b), Some(c)) = (vertex(i), vertex(i + 1), vertex(i + 2)) {
+                rasterise(image, [a, b, c], texture.as_ref(), blend);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_pixels(&mut self, target: Target) -> Result<Image, Error> {
+        self.image(target).cloned()
+    }
+}
+
+fn update<T: Copy>(buffer: &mut [T], offset: usize, data: &[T]) -> Result<(), Error> {
+    match buffer.get_mut(offset..offset + data.len()) {
+        Some(x) => {
+            x.copy_from_slice(data);
+            Ok(())
+        },
+        None => Err(out_of_bounds(offset, data.len(), buffer.len())),
+    }
+}
+
+fn edge(a: &Projected, b: &Projected, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+/// Whether pixel centres exactly on the edge from `a` to `b` belong to the triangle.
+fn is_top_left(a: &Projected, b: &Projected) -> bool {
+    (a.y == b.y && b.x > a.x) || b.y < a.y
+}
+
+fn rasterise(image: &mut Image, mut vertices: [Projected; 3], texture: Option<&Image>, blend: BlendMode) {
+    let mut area = edge(&vertices[0], &vertices[1], vertices[2].x, vertices[2].y);
+
+    if area == 0. {
+        return;
+    }
+
+    // Nothing is culled, so wind every triangle the same way
+    if area < 0. {
+        vertices.swap(1, 2);
+        area = -area;
+    }
+
+    let [a, b, c] = vertices;
+
+    let min_x = a.x.min(b.x).min(c.x).floor().max(0.) as u32;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(0.) as u32;
+    let max_x = (a.x.max(b.x).max(c.x).ceil().max(0.) as u32).min(image.width());
+    let max_y = (a.y.max(b.y).max(c.y).ceil().max(0.) as u32).min(image.height());
+
+    let edges = [is_top_left(&b, &c), is_top_left(&c, &a), is_top_left(&a, &b)];
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [edge(&b, &c, px, py), edge(&c, &a, px, py), edge(&a, &b, px, py)];
+
+            let inside = weights.iter().zip(&edges).all(|(w, top_left)| *w > 0. || (*w == 0. && *top_left));
+
+            if !inside {
+                continue;
+            }
+
+            // Barycentric weights corrected for perspective
+            let weights = [weights[0] / area * a.inverse_w, weights[1] / area * b.inverse_w, weights[2] / area * c.inverse_w];
+            let total = weights[0] + weights[1] + weights[2];
+            let interpolate = |f: &dyn Fn(&Projected) -> f32| (f(&a) * weights[0] + f(&b) * weights[1] + f(&c) * weights[2]) / total;
+
+            let uv = [interpolate(&|v| v.uv[0]), interpolate(&|v| v.uv[1])];
+            let mut colour = [0.; 4];
+
+            for (i, channel) in colour.iter_mut().enumerate() {
+                *channel = interpolate(&|v| v.colour[i]);
+            }
+
+            if let Some(texture) = texture {
+                let texel = sample(texture, uv);
+
+                for (channel, texel) in colour.iter_mut().zip(&texel) {
+                    *channel *= texel;
+                }
+            }
+
+            let destination = Colour::from_rgba8(image.pixel(x, y));
+            image.set_pixel(x, y, Colour::from(colour).blend(destination, blend).to_rgba8());
+        }
+    }
+}
+
+fn sample(texture: &Image, uv: [f32; 2]) -> [f32; 4] {
+    if texture.width() == 0 || texture.height() == 0 {
+        return [1.; 4];
+    }
+
+    let x = ((uv[0] * texture.width() as f32).floor().max(0.) as u32).min(texture.width() - 1);
+    let y = ((uv[1] * texture.height() as f32).floor().max(0.) as u32).min(texture.height() - 1);
+
+    Colour::from_rgba8(texture.pixel(x, y)).to_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::renderer::pixel_projection;
+
+    const WHITE: [f32; 4] = [1., 1., 1., 1.];
+
+    fn quad(x: f32, y: f32, size: f32, colour: [f32; 4]) -> Vec<Vertex> {
+        let corner = |dx: f32, dy: f32| Vertex::new([x + dx * size, y + dy * size], [dx, dy], colour);
+
+        vec![corner(0., 0.), corner(1., 0.), corner(1., 1.), corner(0., 0.), corner(1., 1.), corner(0., 1.)]
+    }
+
+    fn draw(backend: &mut SoftwareBackend, vertices: &[Vertex], blend: BlendMode, texture: Option<TextureId>) {
+        let pipeline = backend.create_pipeline(&PipelineDescriptor { blend, ..PipelineDescriptor::default() }).unwrap();
+        let buffer = backend.create_vertex_buffer(vertices).unwrap();
+        let (width, height) = (backend.screen().width() as f32, backend.screen().height() as f32);
+
+        let mut call = DrawCall::new(pipeline, buffer, vertices.len());
+        call.texture = texture;
+        call.transform = pixel_projection(width, height);
+
+        backend.draw(&call).unwrap();
+    }
+
+    fn golden(rows: &[&str]) -> Image {
+        let mut image = Image::new(rows[0].len() as u32, rows.len() as u32);
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let pixel = match c {
+                    'r' => [255, 0, 0, 255],
+                    'g' => [0, 255, 0, 255],
+                    'b' => [0, 0, 255, 255],
+                    'w' => [255, 255, 255, 255],
+                    _   => [0, 0, 0, 255],
+                };
+
+                image.set_pixel(x as u32, y as u32, pixel);
+            }
+        }
+
+        image
+    }
+
+    #[test]
+    fn clear_and_quad() {
+        let mut backend = SoftwareBackend::new(4, 4);
+        backend.begin_pass(Target::Screen, Some([0., 0., 0., 1.])).unwrap();
+        draw(&mut backend, &quad(1., 1., 2., [1., 0., 0., 1.]), BlendMode::Alpha, None);
+
+        let test = backend.read_pixels(Target::Screen).unwrap();
+        let correct = golden(&[
+            "....",
+            ".rr.",
+            ".rr.",
+            "....",
+        ]);
+
+        assert_eq!(test.max_difference(&correct), Some(0));
+    }
+
+    #[test]
+    fn shared_edges_draw_once() {
+        let mut backend = SoftwareBackend::new(8, 8);
+        backend.begin_pass(Target::Screen, Some([0., 0., 0., 0.])).unwrap();
+        draw(&mut backend, &quad(0., 0., 8., [0.5, 0.5, 0.5, 1.]), BlendMode::Additive, None);
+
+        assert!(backend.screen().pixels().chunks(4).all(|x| x == [128, 128, 128, 255]));
+    }
+
+    #[test]
+    fn textured() {
+        let mut backend = SoftwareBackend::new(4, 4);
+        let image = golden(&["rg", "bw"]);
+        let texture = backend.create_texture(&image).unwrap();
+
+        backend.begin_pass(Target::Screen, Some([0., 0., 0., 1.])).unwrap();
+        draw(&mut backend, &quad(0., 0., 4., WHITE), BlendMode::Replace, Some(texture));
+
+        let test = backend.read_pixels(Target::Screen).unwrap();
+        let correct = golden(&[
+            "rrgg",
+            "rrgg",
+            "bbww",
+            "bbww",
+        ]);
+
+        assert_eq!(test.max_difference(&correct), Some(0));
+    }
+
+    #[test]
+    fn blending() {
+        let mut backend = SoftwareBackend::new(1, 1);
+        backend.begin_pass(Target::Screen, Some([0., 0., 1., 1.])).unwrap();
+        draw(&mut backend, &quad(0., 0., 1., [1., 0., 0., 0.5]), BlendMode::Alpha, None);
+
+        assert_eq!(backend.screen().pixel(0, 0), [128, 0, 128, 255]);
+
+        draw(&mut backend, &quad(0., 0., 1., [0.5, 1., 1., 1.]), BlendMode::Multiply, None);
+
+        assert_eq!(backend.screen().pixel(0, 0), [64, 0, 128, 255]);
+    }
+
+    #[test]
+    fn render_target() {
+        let mut backend = SoftwareBackend::new(2, 2);
+        let target = backend.create_render_target(2, 2).unwrap();
+
+        backend.begin_pass(Target::Texture(target), Some([0., 1., 0., 1.])).unwrap();
+        draw(&mut backend, &quad(0., 0., 1., [1., 0., 0., 1.]), BlendMode::Replace, None);
+
+        let texture = backend.render_target_texture(target);
+        backend.begin_pass(Target::Screen, None).unwrap();
+        draw(&mut backend, &quad(0., 0., 2., WHITE), BlendMode::Replace, texture);
+
+        let test = backend.read_pixels(Target::Screen).unwrap();
+        let correct = golden(&["rg", "gg"]);
+
+        assert_eq!(test.max_difference(&correct), Some(0));
+    }
+
+    #[test]
+    fn indexed() {
+        let mut backend = SoftwareBackend::new(2, 2);
+        let pipeline = backend.create_pipeline(&PipelineDescriptor::default()).unwrap();
+        let corners = [[0., 0.], [2., 0.], [2., 2.], [0., 2.]];
+        let vertices: Vec<Vertex> = corners.iter().map(|x| Vertex::new(*x, [0., 0.], [0., 0., 1., 1.])).collect();
+        let buffer = backend.create_vertex_buffer(&vertices).unwrap();
+        let indices = backend.create_index_buffer(&[0, 1, 2, 0, 2, 3]).unwrap();
+
+        let mut call = DrawCall::new(pipeline, buffer, 3);
+        call.indices = Some(indices);
+        call.first = 3;
+        call.transform = pixel_projection(2., 2.);
+
+        backend.begin_pass(Target::Screen, Some([0., 0., 0., 1.])).unwrap();
+        backend.draw(&call).unwrap();
+
+        let test = backend.read_pixels(Target::Screen).unwrap();
+        // The diagonal belongs to the other half of the quad
+        let correct = golden(&["..", "b."]);
+
+        assert_eq!(test.max_difference(&correct), Some(0));
+    }
+
+    #[test]
+    fn errors() {
+        let mut backend = SoftwareBackend::new(1, 1);
+        let buffer = backend.create_index_buffer(&[0, 1, 2]).unwrap();
+        let glsl = PipelineDescriptor {
+            shader: ShaderSource::Glsl { vertex: String::new(), fragment: String::new() },
+            blend: BlendMode::Alpha,
+        };
+
+        assert!(backend.create_pipeline(&glsl).is_err());
+        assert!(backend.update_index_buffer(buffer, 2, &[0, 0]).is_err());
+        assert!(backend.update_vertex_buffer(buffer, 0, &[]).is_err());
+        assert!(backend.begin_pass(Target::Texture(RenderTargetId(99)), None).is_err());
+
+        let pipeline = backend.create_pipeline(&PipelineDescriptor::default()).unwrap();
+        let vertices = backend.create_vertex_buffer(&[Vertex::new([0., 0.], [0., 0.], [1.; 4]); 3]).unwrap();
+        let mut call = DrawCall::new(pipeline, vertices, usize::MAX);
+        call.first = 1;
+
+        assert!(backend.draw(&call).is_err());
+        call.first = 0;
+        assert!(backend.draw(&call).is_err());
+        call.indices = Some(buffer);
+        assert!(backend.draw(&call).is_err());
+
+        let texture = backend.create_texture(&Image::new(2, 2)).unwrap();
+        assert!(backend.update_texture(texture, u32::MAX, 0, &Image::new(1, 1)).is_err());
+        assert!(backend.update_texture(texture, 1, 1, &Image::new(1, 1)).is_ok());
+    }
+}
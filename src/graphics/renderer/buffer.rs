@@ -0,0 +1,342 @@
+use std::marker::PhantomData;
+use std::mem::{ size_of, size_of_val };
+use crate::Error;
+use super::vertex::VertexLayout;
+
+/// How often a buffer's contents are expected to change, which lets the driver decide where
+/// to keep it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BufferUsage {
+    /// Written once and drawn many times.
+    Static,
+    /// Rewritten now and then and drawn many times.
+    Dynamic,
+    /// Rewritten for nearly every draw.
+    Stream,
+}
+
+impl From<BufferUsage> for gl::types::GLenum {
+    fn from(usage: BufferUsage) -> gl::types::GLenum {
+        match usage {
+            BufferUsage::Static  => gl::STATIC_DRAW,
+            BufferUsage::Dynamic => gl::DYNAMIC_DRAW,
+            BufferUsage::Stream  => gl::STREAM_DRAW,
+        }
+    }
+}
+
+/// How vertices are assembled into primitives.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Primitive {
+    Points,
+    Lines,
+    LineStrip,
+    LineLoop,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+impl From<Primitive> for gl::types::GLenum {
+    fn from(primitive: Primitive) -> gl::types::GLenum {
+        match primitive {
+            Primitive::Points        => gl::POINTS,
+            Primitive::Lines         => gl::LINES,
+            Primitive::LineStrip     => gl::LINE_STRIP,
+            Primitive::LineLoop      => gl::LINE_LOOP,
+            Primitive::Triangles     => gl::TRIANGLES,
+            Primitive::TriangleStrip => gl::TRIANGLE_STRIP,
+            Primitive::TriangleFan   => gl::TRIANGLE_FAN,
+        }
+    }
+}
+
+fn check_range(offset: usize, len: usize, capacity: usize) -> Result<(), Error> {
+    match offset.checked_add(len) {
+        Some(end) if end <= capacity => Ok(()),
+        _ => Err(Error::Render(format!("writing {} elements at {} overflows a buffer of {}", len, offset, capacity))),
+    }
+}
+
+/// A GL buffer object of `len` elements of `T`.
+#[derive(Debug)]
+struct Buffer<T> {
+    id: gl::types::GLuint,
+    target: gl::types::GLenum,
+    len: usize,
+    usage: BufferUsage,
+    element: PhantomData<T>,
+}
+
+impl<T: Copy> Buffer<T> {
+    fn new(target: gl::types::GLenum, data: &[T], usage: BufferUsage) -> Buffer<T> {
+        let mut buffer = Buffer {
+            id: 0,
+            target,
+            len: 0,
+            usage,
+            element: PhantomData,
+        };
+
+        unsafe { gl::GenBuffers(1, &mut buffer.id) };
+        buffer.replace(data);
+
+        buffer
+    }
+
+    fn with_capacity(target: gl::types::GLenum, len: usize, usage: BufferUsage) -> Buffer<T> {
+        let mut id = 0;
+
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+            gl::BindBuffer(target, id);
+            gl::BufferData(target, (len * size_of::<T>()) as gl::types::GLsizeiptr, std::ptr::null(), usage.into());
+            gl::BindBuffer(target, 0);
+        }
+
+        Buffer {
+            id,
+            target,
+            len,
+            usage,
+            element: PhantomData,
+        }
+    }
+
+    fn replace(&mut self, data: &[T]) {
+        unsafe {
+            gl::BindBuffer(self.target, self.id);
+            gl::BufferData(self.target, size_of_val(data) as gl::types::GLsizeiptr, data.as_ptr() as *const _, self.usage.into());
+            gl::BindBuffer(self.target, 0);
+        }
+
+        self.len = data.len();
+    }
+
+    fn update(&mut self, offset: usize, data: &[T]) -> Result<(), Error> {
+        check_range(offset, data.len(), self.len)?;
+
+        unsafe {
+            gl::BindBuffer(self.target, self.id);
+            gl::BufferSubData(self.target, (offset * size_of::<T>()) as gl::types::GLintptr, size_of_val(data) as gl::types::GLsizeiptr, data.as_ptr() as *const _);
+            gl::BindBuffer(self.target, 0);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.id) };
+    }
+}
+
+/// Vertices of type `T` in GPU memory, deleted when dropped. Needs a current context.
+#[derive(Debug)]
+pub struct VertexBuffer<T: VertexLayout>(Buffer<T>);
+
+impl<T: VertexLayout> VertexBuffer<T> {
+    pub fn new(vertices: &[T], usage: BufferUsage) -> VertexBuffer<T> {
+        VertexBuffer(Buffer::new(gl::ARRAY_BUFFER, vertices, usage))
+    }
+
+    /// Room for `len` vertices with undefined contents, to be filled with `update`.
+    pub fn with_capacity(len: usize, usage: BufferUsage) -> VertexBuffer<T> {
+        VertexBuffer(Buffer::with_capacity(gl::ARRAY_BUFFER, len, usage))
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.0.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        self.0.usage
+    }
+
+    /// Overwrites vertices from `offset` onwards. The buffer doesn't grow.
+    pub fn update(&mut self, offset: usize, vertices: &[T]) -> Result<(), Error> {
+        self.0.update(offset, vertices)
+    }
+
+    /// Replaces the whole contents, resizing the buffer to fit.
+    pub fn replace(&mut self, vertices: &[T]) {
+        self.0.replace(vertices)
+    }
+}
+
+/// `u32` indices in GPU memory, deleted when dropped. Needs a current context.
+#[derive(Debug)]
+pub struct IndexBuffer(Buffer<u32>);
+
+impl IndexBuffer {
+    pub fn new(indices: &[u32], usage: BufferUsage) -> IndexBuffer {
+        IndexBuffer(Buffer::new(gl::ELEMENT_ARRAY_BUFFER, indices, usage))
+    }
+
+    /// Room for `len` indices with undefined contents, to be filled with `update`.
+    pub fn with_capacity(len: usize, usage: BufferUsage) -> IndexBuffer {
+        IndexBuffer(Buffer::with_capacity(gl::ELEMENT_ARRAY_BUFFER, len, usage))
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.0.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        self.0.usage
+    }
+
+    /// Overwrites indices from `offset` onwards. The buffer doesn't grow.
+    pub fn update(&mut self, offset: usize, indices: &[u32]) -> Result<(), Error> {
+        self.0.update(offset, indices)
+    }
+
+    /// Replaces the whole contents, resizing the buffer to fit.
+    pub fn replace(&mut self, indices: &[u32]) {
+        self.0.replace(indices)
+    }
+}
+
+/// Records which buffers vertex attributes and indices are read from, deleted when dropped.
+/// Needs a current context.
+#[derive(Debug)]
+pub struct VertexArray {
+    id: gl::types::GLuint,
+}
+
+impl VertexArray {
+    pub fn new() -> VertexArray {
+        let mut id = 0;
+        unsafe { gl::GenVertexArrays(1, &mut id) };
+
+        VertexArray { id }
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn bind(&self) {
+        unsafe { gl::BindVertexArray(self.id) };
+    }
+
+    pub fn unbind(&self) {
+        unsafe { gl::BindVertexArray(0) };
+    }
+
+    /// Reads the attributes of `T` from `buffer`, at shader locations counting up from 0.
+    pub fn set_vertex_buffer<T: VertexLayout>(&mut self, buffer: &VertexBuffer<T>) {
+        self.set_vertex_buffer_at(buffer, 0);
+    }
+
+    /// Reads the attributes of `T` from `buffer`, at shader locations counting up from
+    /// `location`. Use this to read per-vertex and per-instance data from separate buffers.
+    pub fn set_vertex_buffer_at<T: VertexLayout>(&mut self, buffer: &VertexBuffer<T>, location: u32) {
+        let stride = T::stride() as gl::types::GLsizei;
+
+        unsafe {
+            gl::BindVertexArray(self.id);
+            gl::BindBuffer(gl::ARRAY_BUFFER, buffer.id());
+
+            for (i, attribute) in T::attributes().iter().enumerate() {
+                let index = location + i as u32;
+                let components = attribute.components as gl::types::GLint;
+                let offset = attribute.offset as *const _;
+
+                gl::EnableVertexAttribArray(index);
+
+                if attribute.kind.is_integer() && !attribute.normalized {
+                    gl::VertexAttribIPointer(index, components, attribute.kind.into(), stride, offset);
+                } else {
+                    let normalized = if attribute.normalized { gl::TRUE } else { gl::FALSE };
+                    gl::VertexAttribPointer(index, components, attribute.kind.into(), normalized, stride, offset);
+                }
+            }
+
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+
+    /// Makes the following `draw_elements` calls read indices from `buffer`.
+    pub fn set_index_buffer(&mut self, buffer: &IndexBuffer) {
+        unsafe {
+            gl::BindVertexArray(self.id);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, buffer.id());
+            gl::BindVertexArray(0);
+        }
+    }
+
+    /// Draws `count` vertices in order from `first`.
+    ///
+    /// # Safety
+    ///
+    /// Needs a current context, and `first + count` must be within every attached vertex
+    /// buffer. GL doesn't check, so drawing past the end reads whatever memory follows.
+    pub unsafe fn draw_arrays(&self, primitive: Primitive, first: usize, count: usize) {
+        gl::BindVertexArray(self.id);
+        gl::DrawArrays(primitive.into(), first as gl::types::GLint, count as gl::types::GLsizei);
+        gl::BindVertexArray(0);
+    }
+
+    /// Draws through `count` indices from `first` in the index buffer.
+    ///
+    /// # Safety
+    ///
+    /// Needs a current context, `first + count` must be within the index buffer and every index
+    /// in that range must be within every attached vertex buffer.
+    pub unsafe fn draw_elements(&self, primitive: Primitive, first: usize, count: usize) {
+        gl::BindVertexArray(self.id);
+        gl::DrawElements(primitive.into(), count as gl::types::GLsizei, gl::UNSIGNED_INT, (first * size_of::<u32>()) as *const _);
+        gl::BindVertexArray(0);
+    }
+}
+
+impl Default for VertexArray {
+    fn default() -> VertexArray {
+        VertexArray::new()
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteVertexArrays(1, &self.id) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        assert!(check_range(2, 3, 5).is_ok());
+        assert!(check_range(3, 3, 5).is_err());
+        assert!(check_range(usize::MAX, 1, 5).is_err());
+    }
+
+    #[test]
+    fn enums() {
+        let test: [gl::types::GLenum; 3] = [BufferUsage::Static.into(), BufferUsage::Stream.into(), Primitive::TriangleFan.into()];
+        let correct = [gl::STATIC_DRAW, gl::STREAM_DRAW, gl::TRIANGLE_FAN];
+
+        assert_eq!(test, correct);
+    }
+}
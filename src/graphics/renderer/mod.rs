@@ -1,6 +1,18 @@
+use std::collections::HashMap;
 use std::ffi::{ CStr, CString };
+use std::fmt;
+use crate::Error;
+use crate::maths::Matrix4x4;
 
+pub mod backend;
+pub mod buffer;
+pub mod preprocessor;
+pub mod uniform;
 pub mod vertex;
+pub mod watch;
+
+use preprocessor::ShaderSources;
+use uniform::{ glsl_name, split_index, ActiveUniform, UniformElement, UniformValue };
 
 fn create_whitespace_cstring_with_len(len: usize) -> CString {
     let mut buffer: Vec<u8> = Vec::with_capacity(len + 1);
@@ -8,10 +20,31 @@ fn create_whitespace_cstring_with_len(len: usize) -> CString {
     unsafe { CString::from_vec_unchecked(buffer) }
 }
 
-pub struct Program(gl::types::GLuint);
+/// Projection taking pixel coordinates, with the origin at the top left and y pointing down,
+/// to clip space for a `width` by `height` target.
+pub fn pixel_projection(width: f32, height: f32) -> Matrix4x4 {
+    Matrix4x4::from([
+        2. / width, 0., 0., -1.,
+        0., -2. / height, 0., 1.,
+        0., 0., 1., 0.,
+        0., 0., 0., 1.,
+    ])
+}
+
+/// A linked shader program, deleted when dropped.
+///
+/// The uniforms the program uses, and the location of every element of uniform arrays, are
+/// looked up once when it's linked. Uniforms the shaders declare but never use are removed by
+/// the driver, so setting them is an error.
+pub struct Program {
+    id: gl::types::GLuint,
+    uniforms: HashMap<String, ActiveUniform>,
+    /// Locations of each element of the uniform arrays, which needn't be consecutive.
+    elements: HashMap<String, Vec<gl::types::GLint>>,
+}
 
 impl Program {
-    pub fn from(shaders: &[Shader]) -> Result<Program, String> {
+    pub fn from(shaders: &[Shader]) -> Result<Program, Error> {
         let id = unsafe { gl::CreateProgram() };
 
         for shader in shaders {
@@ -30,41 +63,256 @@ impl Program {
 
             let error = create_whitespace_cstring_with_len(len as usize);
 
-            unsafe { gl::GetProgramInfoLog(id, len, std::ptr::null_mut(), error.as_ptr() as *mut gl::types::GLchar) };
+            unsafe {
+                gl::GetProgramInfoLog(id, len, std::ptr::null_mut(), error.as_ptr() as *mut gl::types::GLchar);
+                gl::DeleteProgram(id);
+            }
 
-            return Err(error.to_string_lossy().into_owned());
+            return Err(Error::ProgramLink(error.to_string_lossy().into_owned()));
         }
 
         for shader in shaders {
             unsafe { gl::DetachShader(id, shader.id()) };
         }
 
-        Ok(Program(id))
+        let uniforms = active_uniforms(id);
+
+        Ok(Program {
+            id,
+            elements: element_locations(id, &uniforms),
+            uniforms,
+        })
+    }
+
+    /// Compiles and links every stage in `sources`.
+    pub fn from_sources(sources: &ShaderSources) -> Result<Program, Error> {
+        let shaders = sources.stages().iter()
+            .map(|(stage, source)| Shader::compile(source, *stage))
+            .collect::<Result<Vec<Shader>, Error>>()?;
+
+        Program::from(&shaders)
     }
 
     pub fn id(&self) -> gl::types::GLuint {
-        self.0
+        self.id
     }
 
     pub fn activate(&self) {
-        unsafe { gl::UseProgram(self.0) };
+        unsafe { gl::UseProgram(self.id) };
+    }
+
+    /// Names of the uniforms the program uses, with arrays under their name without `[0]`.
+    pub fn uniforms(&self) -> impl Iterator<Item = &str> {
+        self.uniforms.keys().map(|x| x.as_str())
+    }
+
+    pub fn has_uniform(&self, name: &str) -> bool {
+        self.uniform(name).is_ok()
+    }
+
+    /// Finds a uniform by name. Elements of arrays can be named like `lights[2]`, which gives
+    /// the part of the array from that element onwards.
+    pub fn uniform(&self, name: &str) -> Result<ActiveUniform, Error> {
+        if let Some(x) = self.uniforms.get(name) {
+            return Ok(*x);
+        }
+
+        match split_index(name).and_then(|(base, index)| Some((self.uniforms.get(base)?, base, index))) {
+            Some((x, _, 0)) => Ok(*x),
+            Some((x, base, index)) if index < x.size => {
+                let location = self.elements.get(base).and_then(|x| x.get(index)).copied().filter(|x| *x >= 0)
+                    .ok_or_else(|| Error::Uniform(format!("program {} has no location for '{}'", self.id, name)))?;
+
+                Ok(ActiveUniform { location, kind: x.kind, size: x.size - index })
+            },
+            Some((x, _, _)) => Err(Error::Uniform(format!("'{}' is past the end of an array of {}", name, x.size))),
+            None => Err(Error::Uniform(format!("program {} has no active uniform '{}'", self.id, name))),
+        }
+    }
+
+    /// Sets a uniform, or the elements of a uniform array from the one named onwards, making
+    /// this the program in use.
+    pub fn set_uniform<V: UniformValue>(&self, name: &str, value: V) -> Result<(), Error> {
+        let uniform = self.uniform(name)?;
+        let elements = value.elements();
+
+        if !V::Element::accepts(uniform.kind) {
+            return Err(Error::Uniform(format!("'{}' is a {} but was set to a {}", name, glsl_name(uniform.kind), V::Element::GLSL)));
+        }
+
+        if elements.len() > uniform.size {
+            return Err(Error::Uniform(format!("'{}' has room for {} elements but was given {}", name, uniform.size, elements.len())));
+        }
+
+        self.activate();
+        unsafe { V::Element::upload(uniform.location, uniform.kind, elements) };
+
+        Ok(())
+    }
+
+    /// Has the uniform block `name` read from the uniform buffer bound to `binding`.
+    pub fn bind_uniform_block(&self, name: &str, binding: u32) -> Result<(), Error> {
+        let c_name = CString::new(name).map_err(|_| Error::Uniform(format!("'{}' contains a nul byte", name)))?;
+        let index = unsafe { gl::GetUniformBlockIndex(self.id, c_name.as_ptr()) };
+
+        if index == gl::INVALID_INDEX {
+            return Err(Error::Uniform(format!("program {} has no active uniform block '{}'", self.id, name)));
+        }
+
+        unsafe { gl::UniformBlockBinding(self.id, index, binding) };
+
+        Ok(())
     }
 }
 
 impl Drop for Program {
     fn drop(&mut self) {
-        unsafe { gl::DeleteProgram(self.0) };
+        unsafe { gl::DeleteProgram(self.id) };
+    }
+}
+
+/// Uniforms outside uniform blocks, by name.
+fn active_uniforms(program: gl::types::GLuint) -> HashMap<String, ActiveUniform> {
+    let mut count = 0;
+    let mut max_len = 0;
+
+    unsafe {
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_len);
+    }
+
+    let mut uniforms = HashMap::new();
+    let mut buffer = vec![0u8; max_len.max(1) as usize];
+
+    for i in 0..count as gl::types::GLuint {
+        let (mut len, mut size, mut kind) = (0, 0, 0);
+
+        let location = unsafe {
+            gl::GetActiveUniform(program, i, buffer.len() as i32, &mut len, &mut size, &mut kind, buffer.as_mut_ptr() as *mut gl::types::GLchar);
+            buffer[len as usize] = 0;
+            gl::GetUniformLocation(program, buffer.as_ptr() as *const gl::types::GLchar)
+        };
+
+        // Members of uniform blocks have no location of their own
+        if location < 0 {
+            continue;
+        }
+
+        let name = String::from_utf8_lossy(&buffer[..len as usize]);
+        let name = name.strip_suffix("[0]").unwrap_or(&name);
+        uniforms.insert(String::from(name), ActiveUniform { location, kind, size: size as usize });
+    }
+
+    uniforms
+}
+
+/// Locations of every element of the arrays among `uniforms`, by array name.
+fn element_locations(program: gl::types::GLuint, uniforms: &HashMap<String, ActiveUniform>) -> HashMap<String, Vec<gl::types::GLint>> {
+    uniforms.iter()
+        .filter(|(_, x)| x.size > 1)
+        .map(|(name, x)| {
+            let locations = (0..x.size).map(|i| {
+                let c_name = CString::new(format!("{}[{}]", name, i)).expect("active uniform names have no nul bytes");
+                unsafe { gl::GetUniformLocation(program, c_name.as_ptr()) }
+            });
+
+            (name.clone(), locations.collect())
+        })
+        .collect()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Geometry,
+    Compute,
+    Other(gl::types::GLenum),
+}
+
+impl ShaderStage {
+    /// The OpenGL version the stage became core in, for stages newer than the vertex and
+    /// fragment ones.
+    pub fn gl_version(&self) -> Option<(u32, u32)> {
+        match self {
+            ShaderStage::Geometry => Some((3, 2)),
+            ShaderStage::Compute  => Some((4, 3)),
+            _                     => None,
+        }
+    }
+}
+
+impl From<gl::types::GLenum> for ShaderStage {
+    fn from(kind: gl::types::GLenum) -> ShaderStage {
+        match kind {
+            gl::VERTEX_SHADER   => ShaderStage::Vertex,
+            gl::FRAGMENT_SHADER => ShaderStage::Fragment,
+            gl::GEOMETRY_SHADER => ShaderStage::Geometry,
+            gl::COMPUTE_SHADER  => ShaderStage::Compute,
+            _ => ShaderStage::Other(kind),
+        }
+    }
+}
+
+impl From<ShaderStage> for gl::types::GLenum {
+    fn from(stage: ShaderStage) -> gl::types::GLenum {
+        match stage {
+            ShaderStage::Vertex   => gl::VERTEX_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+            ShaderStage::Geometry => gl::GEOMETRY_SHADER,
+            ShaderStage::Compute  => gl::COMPUTE_SHADER,
+            ShaderStage::Other(x) => x,
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex   => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+            ShaderStage::Geometry => write!(f, "geometry"),
+            ShaderStage::Compute  => write!(f, "compute"),
+            ShaderStage::Other(x) => write!(f, "{:#x}", x),
+        }
     }
 }
 
 pub enum Shader {
     Vertex(gl::types::GLuint),
     Fragment(gl::types::GLuint),
+    Geometry(gl::types::GLuint),
+    Compute(gl::types::GLuint),
     Unimplemented(gl::types::GLuint),
 }
 
+/// Version of the current context.
+fn context_version() -> (u32, u32) {
+    let (mut major, mut minor) = (0, 0);
+
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+
+    (major as u32, minor as u32)
+}
+
 impl Shader {
-    fn from(source: &CStr, kind: gl::types::GLenum) -> Result<Shader, String> {
+    fn from(source: &CStr, kind: gl::types::GLenum) -> Result<Shader, Error> {
+        let stage = ShaderStage::from(kind);
+
+        if let Some(needed) = stage.gl_version() {
+            let version = context_version();
+
+            if version < needed {
+                return Err(Error::ShaderCompile {
+                    stage,
+                    log: format!("{} shaders need OpenGL {}.{} but the context is {}.{}", stage, needed.0, needed.1, version.0, version.1),
+                });
+            }
+        }
+
         let id = unsafe { gl::CreateShader(kind) };
 
         unsafe {
@@ -83,41 +331,75 @@ impl Shader {
 
                 let error = create_whitespace_cstring_with_len(len as usize);
                 gl::GetShaderInfoLog(id, len, std::ptr::null_mut(), error.as_ptr() as *mut gl::types::GLchar);
+                gl::DeleteShader(id);
 
-                return Err(error.to_string_lossy().into_owned());
+                return Err(Error::ShaderCompile {
+                    stage: ShaderStage::from(kind),
+                    log: error.to_string_lossy().into_owned(),
+                });
             }
         }
 
         match kind {
             gl::VERTEX_SHADER => Ok(Shader::Vertex(id)),
             gl::FRAGMENT_SHADER => Ok(Shader::Fragment(id)),
+            gl::GEOMETRY_SHADER => Ok(Shader::Geometry(id)),
+            gl::COMPUTE_SHADER => Ok(Shader::Compute(id)),
             _ => Ok(Shader::Unimplemented(id))
         }
    }
 
-    pub fn vertex(source: &CStr) -> Result<Shader, String> {
+    /// Compiles `source` as `stage`, such as one stage from `Preprocessor` output.
+    pub fn compile(source: &str, stage: ShaderStage) -> Result<Shader, Error> {
+        let source = CString::new(source).map_err(|_| Error::ShaderSource(format!("{} shader source contains a nul byte", stage)))?;
+        Shader::from(&source, stage.into())
+    }
+
+    pub fn vertex(source: &CStr) -> Result<Shader, Error> {
         Shader::from(source, gl::VERTEX_SHADER)
     }
 
-    pub fn fragment(source: &CStr) -> Result<Shader, String> {
+    pub fn fragment(source: &CStr) -> Result<Shader, Error> {
         Shader::from(source, gl::FRAGMENT_SHADER)
     }
 
+    /// Needs OpenGL 3.2.
+    pub fn geometry(source: &CStr) -> Result<Shader, Error> {
+        Shader::from(source, gl::GEOMETRY_SHADER)
+    }
+
+    /// Needs OpenGL 4.3.
+    pub fn compute(source: &CStr) -> Result<Shader, Error> {
+        Shader::from(source, gl::COMPUTE_SHADER)
+    }
+
     pub fn id(&self) -> gl::types::GLuint {
         match self {
             Shader::Vertex(x)        => *x,
             Shader::Fragment(x)      => *x,
+            Shader::Geometry(x)      => *x,
+            Shader::Compute(x)       => *x,
             Shader::Unimplemented(x) => *x,
         }
     }
+
+    pub fn stage(&self) -> ShaderStage {
+        match self {
+            Shader::Vertex(_)        => ShaderStage::Vertex,
+            Shader::Fragment(_)      => ShaderStage::Fragment,
+            Shader::Geometry(_)      => ShaderStage::Geometry,
+            Shader::Compute(_)       => ShaderStage::Compute,
+            Shader::Unimplemented(x) => {
+                let mut kind: gl::types::GLint = 0;
+                unsafe { gl::GetShaderiv(*x, gl::SHADER_TYPE, &mut kind) };
+                ShaderStage::from(kind as gl::types::GLenum)
+            },
+        }
+    }
 }
 
 impl Drop for Shader {
     fn drop(&mut self) {
-        unsafe { gl::DeleteShader(match self {
-            Shader::Vertex(x)        => *x,
-            Shader::Fragment(x)      => *x,
-            Shader::Unimplemented(x) => *x,
-        })}
+        unsafe { gl::DeleteShader(self.id()) }
     }
 }
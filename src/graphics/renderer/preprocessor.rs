@@ -0,0 +1,448 @@
+use std::collections::{ HashMap, HashSet };
+use std::path::{ Path, PathBuf };
+use crate::Error;
+use super::ShaderStage;
+
+/// Expands `#include`s, injects `#define`s and splits combined files into stages before GLSL
+/// is handed to the driver.
+///
+/// A file holds one stage, named by its extension (`.vert`, `.frag`, `.geom` or `.comp`), or
+/// several in sections starting with `#type vertex`, `#type fragment` and so on. Anything
+/// before the first `#type` is shared by every section.
+#[derive(Clone, Debug, Default)]
+pub struct Preprocessor {
+    defines: Vec<(String, String)>,
+    include_dirs: Vec<PathBuf>,
+    includes: HashMap<String, String>,
+}
+
+/// Preprocessed source for each stage of a program.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShaderSources {
+    stages: Vec<(ShaderStage, String)>,
+    sources: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+/// One line of a section with where it came from, for `#line` directives.
+#[derive(Copy, Clone)]
+struct Line<'a> {
+    text: &'a str,
+    source: usize,
+    number: usize,
+}
+
+/// A file or string that's been read, which lines borrow their text from.
+struct Loaded {
+    name: PathBuf,
+    text: String,
+    on_disk: bool,
+}
+
+impl Preprocessor {
+    pub fn new() -> Preprocessor {
+        Preprocessor::default()
+    }
+
+    /// Adds `#define name value` after the `#version` of every stage, to build variants of
+    /// one shader.
+    pub fn with_define(mut self, name: &str, value: &str) -> Preprocessor {
+        self.defines.retain(|(x, _)| x != name);
+        self.defines.push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// Searches `directory` for includes not found next to the including file.
+    pub fn with_include_dir<P: AsRef<Path>>(mut self, directory: P) -> Preprocessor {
+        self.include_dirs.push(directory.as_ref().to_path_buf());
+        self
+    }
+
+    /// Makes `#include "name"` resolve to `source` when there's no such file.
+    pub fn with_include(mut self, name: &str, source: &str) -> Preprocessor {
+        self.includes.insert(String::from(name), String::from(source));
+        self
+    }
+
+    pub fn defines(&self) -> &[(String, String)] {
+        &self.defines
+    }
+
+    /// Preprocesses the file at `path`.
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<ShaderSources, Error> {
+        let path = path.as_ref();
+        let text = read(path)?;
+
+        self.run(Loaded { name: path.to_path_buf(), text, on_disk: true })
+    }
+
+    /// Preprocesses several files, such as a vertex and a fragment shader, into one program.
+    pub fn load_all<P: AsRef<Path>>(&self, paths: &[P]) -> Result<ShaderSources, Error> {
+        let mut sources = ShaderSources::default();
+
+        for path in paths {
+            sources.merge(self.load(path)?)?;
+        }
+
+        Ok(sources)
+    }
+
+    /// Preprocesses `source`, which is named `name` in errors and picks its stage by the
+    /// extension of `name` unless it has `#type` sections. Includes are looked for in the
+    /// include directories.
+    pub fn process(&self, name: &str, source: &str) -> Result<ShaderSources, Error> {
+        self.run(Loaded { name: PathBuf::from(name), text: String::from(source), on_disk: false })
+    }
+
+    fn run(&self, main: Loaded) -> Result<ShaderSources, Error> {
+        let mut loaded = vec![main];
+        let mut links = HashMap::new();
+        let mut sections: Vec<(ShaderStage, Vec<Line>)> = Vec::new();
+        let mut shared = Vec::new();
+
+        // Lines borrow from `loaded`, so every include is read before any is expanded
+        self.read_includes(&mut loaded, &mut links, 0, &mut Vec::new())?;
+
+        let name = |source: usize| loaded[source].name.display().to_string();
+
+        for (i, text) in loaded[0].text.lines().enumerate() {
+            let line = Line { text, source: 0, number: i + 1 };
+
+            match directive(text) {
+                Some(("type", stage)) => {
+                    let stage = stage_named(stage).ok_or_else(|| source_error(&name(0), i + 1, &format!("unknown shader stage '{}'", stage)))?;
+
+                    if sections.iter().any(|(x, _)| *x == stage) {
+                        return Err(source_error(&name(0), i + 1, &format!("second {} section", stage)));
+                    }
+
+                    sections.push((stage, Vec::new()));
+                },
+                _ => match sections.last_mut() {
+                    Some((_, lines)) => lines.push(line),
+                    None => shared.push(line),
+                },
+            }
+        }
+
+        if sections.is_empty() {
+            let stage = stage_for_path(&loaded[0].name).ok_or_else(|| Error::ShaderSource(format!(
+                "{}: can't tell which stage this is, so give it a .vert, .frag, .geom or .comp extension or use #type sections",
+                name(0),
+            )))?;
+
+            sections.push((stage, std::mem::take(&mut shared)));
+        }
+
+        let mut stages = Vec::new();
+
+        for (stage, lines) in sections {
+            let mut expanded = Vec::new();
+
+            expand(&loaded, &links, &mut shared.iter().copied().chain(lines), &mut HashSet::new(), &mut expanded);
+            stages.push((stage, self.render(&expanded)));
+        }
+
+        Ok(ShaderSources {
+            stages,
+            files: loaded.iter().filter(|x| x.on_disk).map(|x| x.name.clone()).collect(),
+            sources: loaded.into_iter().map(|x| x.name).collect(),
+        })
+    }
+
+    /// Reads everything `loaded[index]` includes, directly or not, into `loaded`, recording
+    /// which source each `#include` line refers to in `links`.
+    fn read_includes(
+        &self,
+        loaded: &mut Vec<Loaded>,
+        links: &mut HashMap<(usize, usize), usize>,
+        index: usize,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<(), Error> {
+        stack.push(loaded[index].name.clone());
+
+        let includes: Vec<(usize, String)> = loaded[index].text.lines().enumerate()
+            .filter_map(|(i, line)| match directive(line) {
+                Some(("include", name)) => Some((i + 1, String::from(name))),
+                _ => None,
+            })
+            .collect();
+
+        for (number, include) in includes {
+            let from = loaded[index].name.display().to_string();
+            let include = include_name(&include).ok_or_else(|| source_error(&from, number, "#include needs a name in quotes or angle brackets"))?;
+            let resolved = self.resolve(&loaded[index], include).ok_or_else(|| source_error(&from, number, &format!("can't find include '{}'", include)))?;
+
+            if stack.contains(&resolved.name) {
+                return Err(source_error(&from, number, &format!("'{}' includes itself", include)));
+            }
+
+            match loaded.iter().position(|x| x.name == resolved.name) {
+                Some(source) => {
+                    links.insert((index, number), source);
+                },
+                None => {
+                    loaded.push(resolved);
+                    links.insert((index, number), loaded.len() - 1);
+                    self.read_includes(loaded, links, loaded.len() - 1, stack)?;
+                },
+            }
+        }
+
+        stack.pop();
+
+        Ok(())
+    }
+
+    fn resolve(&self, from: &Loaded, include: &str) -> Option<Loaded> {
+        let beside = match (from.on_disk, from.name.parent()) {
+            (true, Some(directory)) => Some(directory.join(include)),
+            _ => None,
+        };
+
+        for path in beside.into_iter().chain(self.include_dirs.iter().map(|x| x.join(include))) {
+            if let Ok(text) = std::fs::read_to_string(&path) {
+                return Some(Loaded { name: path, text, on_disk: true });
+            }
+        }
+
+        self.includes.get(include).map(|text| Loaded { name: PathBuf::from(include), text: text.clone(), on_disk: false })
+    }
+
+    /// Joins `lines` into source with the defines after `#version`, and `#line` directives
+    /// wherever the next line isn't the one after the last, so compile errors point at the
+    /// right file and line. Source string numbers index `ShaderSources::sources`.
+    fn render(&self, lines: &[Line]) -> String {
+        let mut source = String::new();
+        let version = lines.iter().position(|x| matches!(directive(x.text), Some(("version", _))));
+
+        if let Some(i) = version {
+            source.push_str(lines[i].text);
+            source.push('\n');
+        }
+
+        for (name, value) in &self.defines {
+            match value.as_str() {
+                "" => source.push_str(&format!("#define {}\n", name)),
+                _ => source.push_str(&format!("#define {} {}\n", name, value)),
+            }
+        }
+
+        let mut next = if version.is_some() || !self.defines.is_empty() { None } else { Some((0, 1)) };
+
+        // Shaders without `#version` are GLSL 1.10
+        let offset = match version.and_then(|i| directive(lines[i].text)) {
+            Some((_, x)) if line_is_next(x) => 0,
+            _ => 1,
+        };
+
+        for (i, line) in lines.iter().enumerate() {
+            if Some(i) == version {
+                next = None;
+                continue;
+            }
+
+            if next != Some((line.source, line.number)) {
+                source.push_str(&format!("#line {} {}\n", line.number - offset, line.source));
+            }
+
+            source.push_str(line.text);
+            source.push('\n');
+            next = Some((line.source, line.number + 1));
+        }
+
+        source
+    }
+}
+
+impl ShaderSources {
+    /// Source for each stage, in the order they appeared.
+    pub fn stages(&self) -> &[(ShaderStage, String)] {
+        &self.stages
+    }
+
+    pub fn get(&self, stage: ShaderStage) -> Option<&str> {
+        self.stages.iter().find(|(x, _)| *x == stage).map(|(_, x)| x.as_str())
+    }
+
+    /// Names of the files and strings the stages were made from, by the source string number
+    /// compile errors report.
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    /// Files read from disk, which need preprocessing again when one of them changes.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    fn merge(&mut self, other: ShaderSources) -> Result<(), Error> {
+        for (stage, source) in other.stages {
+            if self.get(stage).is_some() {
+                return Err(Error::ShaderSource(format!("more than one {} shader", stage)));
+            }
+
+            self.stages.push((stage, source));
+        }
+
+        self.sources.extend(other.sources);
+        self.files.extend(other.files.into_iter().filter(|x| !self.files.contains(x)).collect::<Vec<_>>());
+
+        Ok(())
+    }
+}
+
+/// Copies `lines` to `out` with includes replaced by their lines, skipping sources marked
+/// `#pragma once` the second time.
+fn expand<'a>(
+    loaded: &'a [Loaded],
+    links: &HashMap<(usize, usize), usize>,
+    lines: &mut dyn Iterator<Item = Line<'a>>,
+    once: &mut HashSet<usize>,
+    out: &mut Vec<Line<'a>>,
+) {
+    for line in lines {
+        match (directive(line.text), links.get(&(line.source, line.number))) {
+            (Some(("include", _)), Some(&source)) => {
+                if !once.contains(&source) {
+                    let mut included = loaded[source].text.lines().enumerate().map(|(i, text)| Line { text, source, number: i + 1 });
+                    expand(loaded, links, &mut included, once, out);
+                }
+            },
+            (Some(("pragma", "once")), _) => {
+                once.insert(line.source);
+            },
+            _ => out.push(line),
+        }
+    }
+}
+
+/// The stage a file holds, going by its extension.
+pub fn stage_for_path(path: &Path) -> Option<ShaderStage> {
+    match path.extension()?.to_str()? {
+        "vert" | "vs" => Some(ShaderStage::Vertex),
+        "frag" | "fs" => Some(ShaderStage::Fragment),
+        "geom" | "gs" => Some(ShaderStage::Geometry),
+        "comp" | "cs" => Some(ShaderStage::Compute),
+        _ => None,
+    }
+}
+
+fn stage_named(name: &str) -> Option<ShaderStage> {
+    match name {
+        "vertex"              => Some(ShaderStage::Vertex),
+        "fragment" | "pixel"  => Some(ShaderStage::Fragment),
+        "geometry"            => Some(ShaderStage::Geometry),
+        "compute"             => Some(ShaderStage::Compute),
+        _                     => None,
+    }
+}
+
+/// Splits `# include "x"` into `include` and `"x"`.
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim().strip_prefix('#')?.trim_start();
+    let end = line.find(char::is_whitespace).unwrap_or(line.len());
+
+    Some((&line[..end], line[end..].trim()))
+}
+
+fn include_name(name: &str) -> Option<&str> {
+    let inner = name.strip_prefix('"').and_then(|x| x.strip_suffix('"'))
+        .or_else(|| name.strip_prefix('<').and_then(|x| x.strip_suffix('>')))?;
+
+    if inner.is_empty() { None } else { Some(inner) }
+}
+
+fn source_error(name: &str, line: usize, message: &str) -> Error {
+    Error::ShaderSource(format!("{}:{}: {}", name, line, message))
+}
+
+/// Whether `#line n` makes the line after it line n rather than n + 1 in GLSL `version`, which
+/// changed in GLSL 3.30 and GLSL ES 3.00.
+fn line_is_next(version: &str) -> bool {
+    let mut parts = version.split_whitespace();
+
+    match (parts.next().and_then(|x| x.parse::<u32>().ok()), parts.next()) {
+        (Some(number), Some("es")) => number >= 300,
+        (Some(number), _) => number >= 330,
+        _ => false,
+    }
+}
+
+fn read(path: &Path) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|error| Error::AssetIo { path: path.to_path_buf(), error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn directives() {
+        assert_eq!(directive("  #  include \"a.glsl\" "), Some(("include", "\"a.glsl\"")));
+        assert_eq!(directive("#version 330 core"), Some(("version", "330 core")));
+        assert_eq!(directive("void main() {}"), None);
+        assert!(line_is_next("330 core") && line_is_next("300 es"));
+        assert!(!line_is_next("150") && !line_is_next("100 es"));
+        assert_eq!(include_name("<common/maths.glsl>"), Some("common/maths.glsl"));
+        assert_eq!(include_name("\"\""), None);
+    }
+
+    #[test]
+    fn combined() {
+        let source = "#version 330 core\n#include \"common\"\n#type vertex\nvoid main() {}\n#type fragment\nvoid main() {}\n";
+        let preprocessor = Preprocessor::new()
+            .with_define("SHADOWS", "")
+            .with_define("LIGHTS", "4")
+            .with_include("common", "#pragma once\nuniform mat4 transform;\n");
+
+        let test = preprocessor.process("sprite.glsl", source).unwrap();
+        let correct = "#version 330 core\n#define SHADOWS\n#define LIGHTS 4\n#line 2 1\nuniform mat4 transform;\n#line 4 0\nvoid main() {}\n";
+
+        assert_eq!(test.get(ShaderStage::Vertex), Some(correct));
+        assert_eq!(test.get(ShaderStage::Fragment), Some(&*correct.replace("#line 4", "#line 6")));
+        assert_eq!(test.sources(), &[PathBuf::from("sprite.glsl"), PathBuf::from("common")]);
+        assert!(test.files().is_empty());
+    }
+
+    #[test]
+    fn includes() {
+        let preprocessor = Preprocessor::new()
+            .with_include("a", "#pragma once\nfloat a;\n")
+            .with_include("b", "#include \"a\"\nfloat b;\n")
+            .with_include("loop", "#include \"loop\"\n");
+
+        // Without `#version` the shader is GLSL 1.10, which numbers lines from after `#line`
+        let test = preprocessor.process("x.frag", "#include \"a\"\n#include \"b\"\n").unwrap();
+        let correct = "#line 1 1\nfloat a;\n#line 1 2\nfloat b;\n";
+
+        assert_eq!(test.get(ShaderStage::Fragment), Some(correct));
+
+        let test = preprocessor.process("x.frag", "#version 300 es\n#include \"a\"\n#include \"b\"\n").unwrap();
+        let correct = "#version 300 es\n#line 2 1\nfloat a;\n#line 2 2\nfloat b;\n";
+
+        assert_eq!(test.get(ShaderStage::Fragment), Some(correct));
+        assert!(preprocessor.process("x.frag", "#include \"loop\"").is_err());
+        assert!(preprocessor.process("x.frag", "#include \"missing\"").is_err());
+        assert!(preprocessor.process("x.glsl", "void main() {}").is_err());
+        assert!(preprocessor.process("x.glsl", "#type tessellation\n").is_err());
+    }
+
+    #[test]
+    fn files() {
+        let directory = TempDir::new("shaders");
+        std::fs::create_dir(directory.join("lib")).unwrap();
+        std::fs::write(directory.join("lib/light.glsl"), "vec3 light;\n").unwrap();
+        std::fs::write(directory.join("a.vert"), "#version 330 core\n#include <light.glsl>\n").unwrap();
+        std::fs::write(directory.join("a.frag"), "#version 330 core\n").unwrap();
+
+        let preprocessor = Preprocessor::new().with_include_dir(directory.join("lib"));
+        let test = preprocessor.load_all(&[directory.join("a.vert"), directory.join("a.frag")]).unwrap();
+        let stages: Vec<ShaderStage> = test.stages().iter().map(|(x, _)| *x).collect();
+
+        assert_eq!(stages, vec![ShaderStage::Vertex, ShaderStage::Fragment]);
+        assert_eq!(test.files().len(), 3);
+        assert!(preprocessor.load_all(&[directory.join("a.vert"), directory.join("a.vert")]).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::graphics::Colour;
+use crate::maths::{ Matrix2x2, Matrix3x3, Matrix4x4, Vector2D, Vector3D, Vector4D };
+
+/// A uniform a linked program uses, as reported by the driver.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ActiveUniform {
+    pub location: gl::types::GLint,
+    /// GL type such as `gl::FLOAT_VEC3`.
+    pub kind: gl::types::GLenum,
+    /// Number of array elements from `location` onwards, 1 for plain uniforms.
+    pub size: usize,
+}
+
+/// The texture unit a sampler uniform reads from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextureUnit(pub u32);
+
+/// The GLSL name of a uniform type, for error messages.
+pub fn glsl_name(kind: gl::types::GLenum) -> String {
+    let name = match kind {
+        gl::FLOAT                   => "float",
+        gl::FLOAT_VEC2              => "vec2",
+        gl::FLOAT_VEC3              => "vec3",
+        gl::FLOAT_VEC4              => "vec4",
+        gl::INT                     => "int",
+        gl::INT_VEC2                => "ivec2",
+        gl::INT_VEC3                => "ivec3",
+        gl::INT_VEC4                => "ivec4",
+        gl::UNSIGNED_INT            => "uint",
+        gl::BOOL                    => "bool",
+        gl::FLOAT_MAT2              => "mat2",
+        gl::FLOAT_MAT3              => "mat3",
+        gl::FLOAT_MAT4              => "mat4",
+        gl::SAMPLER_2D              => "sampler2D",
+        gl::SAMPLER_3D              => "sampler3D",
+        gl::SAMPLER_CUBE            => "samplerCube",
+        gl::SAMPLER_2D_ARRAY        => "sampler2DArray",
+        gl::SAMPLER_2D_SHADOW       => "sampler2DShadow",
+        _ if is_sampler(kind)       => "sampler",
+        _                           => return format!("type {:#x}", kind),
+    };
+
+    String::from(name)
+}
+
+pub fn is_sampler(kind: gl::types::GLenum) -> bool {
+    matches!(
+        kind,
+        gl::SAMPLER_1D | gl::SAMPLER_2D | gl::SAMPLER_3D | gl::SAMPLER_CUBE | gl::SAMPLER_2D_RECT
+            | gl::SAMPLER_1D_ARRAY | gl::SAMPLER_2D_ARRAY | gl::SAMPLER_BUFFER | gl::SAMPLER_2D_MULTISAMPLE
+            | gl::SAMPLER_1D_SHADOW | gl::SAMPLER_2D_SHADOW | gl::SAMPLER_CUBE_SHADOW | gl::SAMPLER_2D_ARRAY_SHADOW
+            | gl::INT_SAMPLER_2D | gl::INT_SAMPLER_3D | gl::INT_SAMPLER_CUBE | gl::INT_SAMPLER_2D_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_2D | gl::UNSIGNED_INT_SAMPLER_3D | gl::UNSIGNED_INT_SAMPLER_CUBE | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY
+    )
+}
+
+/// Splits `lights[2]` into `lights` and 2.
+pub(crate) fn split_index(name: &str) -> Option<(&str, usize)> {
+    let open = name.rfind('[')?;
+    let index = name[open + 1..].strip_suffix(']')?.parse().ok()?;
+
+    Some((&name[..open], index))
+}
+
+/// A value one element of a uniform can hold.
+pub trait UniformElement: Copy {
+    /// GLSL type to name in errors when this is set on the wrong type of uniform.
+    const GLSL: &'static str;
+
+    /// Whether a uniform of GL type `kind` can be set to this.
+    fn accepts(kind: gl::types::GLenum) -> bool;
+
+    /// Uploads `values` to the elements from `location` of a `kind` uniform in the program in
+    /// use.
+    ///
+    /// # Safety
+    ///
+    /// Needs a current context, and `values` must fit in the uniform.
+    unsafe fn upload(location: gl::types::GLint, kind: gl::types::GLenum, values: &[Self]);
+}
+
+/// A value a uniform can be set to: a single element, or a slice or array of them for uniform
+/// arrays.
+pub trait UniformValue {
+    type Element: UniformElement;
+
+    fn elements(&self) -> &[Self::Element];
+}
+
+impl<E: UniformElement> UniformValue for &[E] {
+    type Element = E;
+
+    fn elements(&self) -> &[E] {
+        self
+    }
+}
+
+impl<E: UniformElement, const N: usize> UniformValue for [E; N] {
+    type Element = E;
+
+    fn elements(&self) -> &[E] {
+        self
+    }
+}
+
+fn flatten<T, const N: usize>(values: &[T], f: impl Fn(&T) -> [f32; N]) -> Vec<f32> {
+    values.iter().flat_map(f).collect()
+}
+
+/// Elements row by row, which GL is told to transpose to its column major order.
+fn rows<M: std::ops::Index<(usize, usize), Output = f32>, const N: usize>(matrix: &M, size: usize) -> [f32; N] {
+    let mut elements = [0.; N];
+
+    for (i, x) in elements.iter_mut().enumerate() {
+        *x = matrix[(i / size, i % size)];
+    }
+
+    elements
+}
+
+macro_rules! uniform_elements {
+    ($($element:ty => $glsl:literal, |$kind:ident| $accepts:expr, |$location:ident, $upload_kind:pat, $values:ident| $upload:expr;)*) => {
+        $(
+            impl UniformElement for $element {
+                const GLSL: &'static str = $glsl;
+
+                fn accepts($kind: gl::types::GLenum) -> bool {
+                    $accepts
+                }
+
+                unsafe fn upload($location: gl::types::GLint, $upload_kind: gl::types::GLenum, $values: &[Self]) {
+                    $upload
+                }
+            }
+
+            impl UniformValue for $element {
+                type Element = $element;
+
+                fn elements(&self) -> &[$element] {
+                    std::slice::from_ref(self)
+                }
+            }
+        )*
+    };
+}
+
+uniform_elements! {
+    f32 => "float",
+        |kind| kind == gl::FLOAT,
+        |location, _, values| gl::Uniform1fv(location, values.len() as i32, values.as_ptr());
+    i32 => "int",
+        |kind| kind == gl::INT || kind == gl::BOOL || is_sampler(kind),
+        |location, _, values| gl::Uniform1iv(location, values.len() as i32, values.as_ptr());
+    u32 => "uint",
+        |kind| kind == gl::UNSIGNED_INT,
+        |location, _, values| gl::Uniform1uiv(location, values.len() as i32, values.as_ptr());
+    bool => "bool",
+        |kind| kind == gl::BOOL,
+        |location, _, values| {
+            let values: Vec<i32> = values.iter().map(|&x| x as i32).collect();
+            gl::Uniform1iv(location, values.len() as i32, values.as_ptr())
+        };
+    TextureUnit => "sampler",
+        |kind| is_sampler(kind),
+        |location, _, values| {
+            let values: Vec<i32> = values.iter().map(|x| x.0 as i32).collect();
+            gl::Uniform1iv(location, values.len() as i32, values.as_ptr())
+        };
+    Vector2D => "vec2",
+        |kind| kind == gl::FLOAT_VEC2,
+        |location, _, values| gl::Uniform2fv(location, values.len() as i32, flatten(values, |x| [x.x(), x.y()]).as_ptr());
+    Vector3D => "vec3",
+        |kind| kind == gl::FLOAT_VEC3,
+        |location, _, values| gl::Uniform3fv(location, values.len() as i32, flatten(values, |x| [x.x(), x.y(), x.z()]).as_ptr());
+    Vector4D => "vec4",
+        |kind| kind == gl::FLOAT_VEC4,
+        |location, _, values| gl::Uniform4fv(location, values.len() as i32, flatten(values, |x| [x.x(), x.y(), x.z(), x.w()]).as_ptr());
+    Colour => "vec4",
+        |kind| kind == gl::FLOAT_VEC4 || kind == gl::FLOAT_VEC3,
+        |location, kind, values| match kind {
+            gl::FLOAT_VEC3 => gl::Uniform3fv(location, values.len() as i32, flatten(values, |x| [x.r(), x.g(), x.b()]).as_ptr()),
+            _              => gl::Uniform4fv(location, values.len() as i32, flatten(values, Colour::to_array).as_ptr()),
+        };
+    Matrix2x2 => "mat2",
+        |kind| kind == gl::FLOAT_MAT2,
+        |location, _, values| gl::UniformMatrix2fv(location, values.len() as i32, gl::TRUE, flatten(values, |x| rows::<_, 4>(x, 2)).as_ptr());
+    Matrix3x3 => "mat3",
+        |kind| kind == gl::FLOAT_MAT3,
+        |location, _, values| gl::UniformMatrix3fv(location, values.len() as i32, gl::TRUE, flatten(values, |x| rows::<_, 9>(x, 3)).as_ptr());
+    Matrix4x4 => "mat4",
+        |kind| kind == gl::FLOAT_MAT4,
+        |location, _, values| gl::UniformMatrix4fv(location, values.len() as i32, gl::TRUE, flatten(values, |x| rows::<_, 16>(x, 4)).as_ptr());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index() {
+        assert_eq!(split_index("lights[2]"), Some(("lights", 2)));
+        assert_eq!(split_index("material.lights[10]"), Some(("material.lights", 10)));
+        assert_eq!(split_index("lights"), None);
+        assert_eq!(split_index("lights[x]"), None);
+    }
+
+    #[test]
+    fn accepts() {
+        assert!(Matrix4x4::accepts(gl::FLOAT_MAT4));
+        assert!(!Matrix4x4::accepts(gl::FLOAT_MAT3));
+        assert!(Colour::accepts(gl::FLOAT_VEC3));
+        assert!(i32::accepts(gl::SAMPLER_2D));
+        assert!(TextureUnit::accepts(gl::SAMPLER_CUBE));
+        assert!(!TextureUnit::accepts(gl::INT));
+        assert_eq!(glsl_name(gl::FLOAT_VEC3), "vec3");
+        assert_eq!(glsl_name(gl::DOUBLE), "type 0x140a");
+    }
+
+    #[test]
+    fn row_major() {
+        let matrix = Matrix2x2::from([1., 2., 3., 4.]);
+
+        let test = flatten(&[matrix, Matrix2x2::identity()], |x| rows::<_, 4>(x, 2));
+        let correct = vec![1., 2., 3., 4., 1., 0., 0., 1.];
+
+        assert_eq!(test, correct);
+        assert_eq!([1f32, 2.].elements().len(), 2);
+        assert_eq!(2.5f32.elements(), &[2.5]);
+    }
+}
@@ -1,4 +1,222 @@
-#[derive(Copy, Clone)]
+use std::mem::size_of;
+
+/// Component types a vertex attribute can be made of.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeType {
+    F32,
+    I32,
+    U32,
+    I16,
+    U16,
+    I8,
+    U8,
+}
+
+impl AttributeType {
+    pub fn size(&self) -> usize {
+        match self {
+            AttributeType::F32 | AttributeType::I32 | AttributeType::U32 => 4,
+            AttributeType::I16 | AttributeType::U16                      => 2,
+            AttributeType::I8 | AttributeType::U8                        => 1,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        *self != AttributeType::F32
+    }
+}
+
+impl From<AttributeType> for gl::types::GLenum {
+    fn from(kind: AttributeType) -> gl::types::GLenum {
+        match kind {
+            AttributeType::F32 => gl::FLOAT,
+            AttributeType::I32 => gl::INT,
+            AttributeType::U32 => gl::UNSIGNED_INT,
+            AttributeType::I16 => gl::SHORT,
+            AttributeType::U16 => gl::UNSIGNED_SHORT,
+            AttributeType::I8  => gl::BYTE,
+            AttributeType::U8  => gl::UNSIGNED_BYTE,
+        }
+    }
+}
+
+/// One field of a vertex as the GPU sees it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Attribute {
+    pub name: &'static str,
+    /// Number of components, from 1 to 4.
+    pub components: u32,
+    pub kind: AttributeType,
+    /// Whether integer components are mapped to `[0, 1]`, or `[-1, 1]` if signed, and read
+    /// as floats. Integer attributes that aren't normalised are read as integers.
+    pub normalized: bool,
+    /// Bytes from the start of the vertex.
+    pub offset: usize,
+}
+
+impl Attribute {
+    /// Describes `field` of the vertex at `vertex`, which only have to be valid addresses.
+    pub fn of<V, T: AttributeFormat>(name: &'static str, vertex: *const V, field: *const T, normalized: bool) -> Attribute {
+        Attribute {
+            name,
+            components: T::COMPONENTS,
+            kind: T::KIND,
+            normalized,
+            offset: field as usize - vertex as usize,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.components as usize * self.kind.size()
+    }
+}
+
+/// Types that fit in a single vertex attribute.
+pub trait AttributeFormat {
+    const KIND: AttributeType;
+    const COMPONENTS: u32;
+}
+
+macro_rules! attribute_formats {
+    ($($scalar:ty => $kind:ident,)*) => {
+        $(
+            impl AttributeFormat for $scalar {
+                const KIND: AttributeType = AttributeType::$kind;
+                const COMPONENTS: u32 = 1;
+            }
+
+            impl AttributeFormat for [$scalar; 2] {
+                const KIND: AttributeType = AttributeType::$kind;
+                const COMPONENTS: u32 = 2;
+            }
+
+            impl AttributeFormat for [$scalar; 3] {
+                const KIND: AttributeType = AttributeType::$kind;
+                const COMPONENTS: u32 = 3;
+            }
+
+            impl AttributeFormat for [$scalar; 4] {
+                const KIND: AttributeType = AttributeType::$kind;
+                const COMPONENTS: u32 = 4;
+            }
+        )*
+    };
+}
+
+attribute_formats! {
+    f32 => F32,
+    i32 => I32,
+    u32 => U32,
+    i16 => I16,
+    u16 => U16,
+    i8 => I8,
+    u8 => U8,
+}
+
+/// A vertex type that can be uploaded to a `VertexBuffer`. Implement it with
+/// `vertex_layout!`, which measures the offsets, rather than by hand.
+pub trait VertexLayout: Copy {
+    /// Attributes in the order they're bound to shader locations.
+    fn attributes() -> Vec<Attribute>;
+
+    fn stride() -> usize {
+        size_of::<Self>()
+    }
+}
+
+/// Implements `VertexLayout` for a struct, which should be `#[repr(C)]`, listing the fields
+/// to use as attributes in location order. Component counts and types come from the field
+/// types, and fields marked `#[normalized]` have their integers read as `[0, 1]` floats.
+///
+/// ```
+/// # use divergence::vertex_layout;
+/// #[repr(C)]
+/// #[derive(Copy, Clone)]
+/// struct Particle {
+///     position: [f32; 3],
+///     colour: [u8; 4],
+/// }
+///
+/// vertex_layout!(Particle { position, #[normalized] colour });
+/// ```
+#[macro_export]
+macro_rules! vertex_layout {
+    ($name:ident { $($(#[$normalized:ident])? $field:ident),* $(,)? }) => {
+        impl $crate::graphics::renderer::vertex::VertexLayout for $name {
+            fn attributes() -> Vec<$crate::graphics::renderer::vertex::Attribute> {
+                let vertex = std::mem::MaybeUninit::<$name>::uninit();
+                let base = vertex.as_ptr();
+
+                vec![$(
+                    $crate::graphics::renderer::vertex::Attribute::of(
+                        stringify!($field),
+                        base,
+                        unsafe { std::ptr::addr_of!((*base).$field) },
+                        $crate::vertex_layout!(@normalized $($normalized)?),
+                    ),
+                )*]
+            }
+        }
+    };
+    (@normalized normalized) => { true };
+    (@normalized) => { false };
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vertex {
     pub position: [f32; 2],
+    pub uv: [f32; 2],
+    /// RGBA, multiplied with the texture colour.
+    pub colour: [f32; 4],
+}
+
+impl Vertex {
+    pub fn new(position: [f32; 2], uv: [f32; 2], colour: [f32; 4]) -> Vertex {
+        Vertex {
+            position,
+            uv,
+            colour,
+        }
+    }
+}
+
+vertex_layout!(Vertex { position, uv, colour });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct Packed {
+        position: [f32; 3],
+        colour: [u8; 4],
+        id: u16,
+    }
+
+    vertex_layout!(Packed { position, #[normalized] colour, id });
+
+    #[test]
+    fn layout() {
+        let test = Packed::attributes();
+        let correct = vec![
+            Attribute { name: "position", components: 3, kind: AttributeType::F32, normalized: false, offset: 0 },
+            Attribute { name: "colour", components: 4, kind: AttributeType::U8, normalized: true, offset: 12 },
+            Attribute { name: "id", components: 1, kind: AttributeType::U16, normalized: false, offset: 16 },
+        ];
+
+        assert_eq!(test, correct);
+        assert_eq!(Packed::stride(), 20);
+        assert_eq!(test[1].size(), 4);
+    }
+
+    #[test]
+    fn vertex() {
+        let test: Vec<(u32, usize)> = Vertex::attributes().iter().map(|x| (x.components, x.offset)).collect();
+        let correct = vec![(2, 0), (2, 8), (4, 16)];
+
+        assert_eq!(test, correct);
+        assert_eq!(Vertex::stride(), 32);
+    }
 }
@@ -0,0 +1,148 @@
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, Instant, SystemTime };
+use crate::Error;
+use super::Program;
+use super::preprocessor::Preprocessor;
+
+/// Modification times of a set of files, to tell when any of them has been saved.
+#[derive(Clone, Debug, PartialEq)]
+struct Stamps(Vec<(PathBuf, Option<SystemTime>)>);
+
+impl Stamps {
+    fn new(files: &[PathBuf]) -> Stamps {
+        Stamps(files.iter().map(|x| (x.clone(), modified(x))).collect())
+    }
+
+    /// Whether any file was modified, created or removed since the last call.
+    fn changed(&mut self) -> bool {
+        let mut changed = false;
+
+        for (path, stamp) in &mut self.0 {
+            let now = modified(path);
+
+            if now != *stamp {
+                *stamp = now;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|x| x.modified()).ok()
+}
+
+/// A `Program` loaded from shader files that relinks itself when they, or anything they
+/// include, change on disk, so shaders can be edited while the game runs.
+///
+/// Watching is on by default in debug builds only. Call `poll` once a frame; it checks the
+/// files at most every `interval`.
+pub struct WatchedProgram {
+    program: Program,
+    paths: Vec<PathBuf>,
+    preprocessor: Preprocessor,
+    stamps: Stamps,
+    watching: bool,
+    interval: Duration,
+    last_check: Instant,
+}
+
+impl WatchedProgram {
+    /// Loads a program from `paths`, either one combined file or a file per stage.
+    pub fn load<P: AsRef<Path>>(paths: &[P], preprocessor: Preprocessor) -> Result<WatchedProgram, Error> {
+        let paths: Vec<PathBuf> = paths.iter().map(|x| x.as_ref().to_path_buf()).collect();
+        let sources = preprocessor.load_all(&paths)?;
+
+        Ok(WatchedProgram {
+            program: Program::from_sources(&sources)?,
+            stamps: Stamps::new(sources.files()),
+            paths,
+            preprocessor,
+            watching: cfg!(debug_assertions),
+            interval: Duration::from_millis(250),
+            last_check: Instant::now(),
+        })
+    }
+
+    pub fn with_watching(mut self, watching: bool) -> WatchedProgram {
+        self.watching = watching;
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> WatchedProgram {
+        self.interval = interval;
+        self
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watching
+    }
+
+    pub fn set_watching(&mut self, watching: bool) {
+        self.watching = watching;
+    }
+
+    /// Relinks the program if watching is on and a source has changed, returning whether it
+    /// was replaced. If the new sources fail to build, the old program is kept and the error
+    /// is returned once, then the files are left alone until they're saved again.
+    pub fn poll(&mut self) -> Result<bool, Error> {
+        if !self.watching || self.last_check.elapsed() < self.interval {
+            return Ok(false);
+        }
+
+        self.last_check = Instant::now();
+
+        if !self.stamps.changed() {
+            return Ok(false);
+        }
+
+        self.reload().map(|_| true)
+    }
+
+    /// Preprocesses, compiles and links the sources again, replacing the program on success.
+    /// Uniform values aren't carried over, so set them again afterwards.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let sources = self.preprocessor.load_all(&self.paths)?;
+        let program = Program::from_sources(&sources)?;
+
+        // Includes may have been added or removed
+        self.stamps = Stamps::new(sources.files());
+        self.program = program;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn stamps() {
+        let directory = TempDir::new("watch");
+        let path = directory.join("a.frag");
+        std::fs::write(&path, "void main() {}").unwrap();
+
+        let mut stamps = Stamps::new(&[path.clone(), directory.join("missing.glsl")]);
+        assert!(!stamps.changed());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        assert!(stamps.changed());
+        assert!(!stamps.changed());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(stamps.changed());
+    }
+}
@@ -0,0 +1,527 @@
+use std::f32::consts::{ FRAC_PI_2, PI, TAU };
+use std::ops::Range;
+use crate::Error;
+use crate::graphics::Colour;
+use crate::graphics::renderer::backend::{ Backend, BatchBuffers, DrawCall };
+use crate::graphics::renderer::vertex::Vertex;
+use crate::maths::{ Matrix4x4, Vector2D };
+
+pub mod triangulate;
+
+pub use triangulate::triangulate;
+
+/// Miter joins longer than this many times half the thickness are bevelled instead.
+const MITER_LIMIT: f32 = 4.;
+
+/// How the open ends of a line are finished.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LineCap {
+    /// Stops square at the end point.
+    Butt,
+    /// Extends half the thickness past the end point.
+    Square,
+    Round,
+}
+
+/// How corners between the segments of a line are finished.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LineJoin {
+    /// Sharp corners, bevelled when very acute.
+    Miter,
+    Bevel,
+    Round,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stroke {
+    pub thickness: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+}
+
+impl Stroke {
+    /// Butt capped and mitered.
+    pub fn new(thickness: f32) -> Stroke {
+        Stroke {
+            thickness,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+        }
+    }
+}
+
+/// A segment count that keeps a full circle of `radius` pixels looking smooth.
+pub fn segments_for(radius: f32) -> u32 {
+    ((radius.abs().sqrt() * 4.).ceil() as u32).clamp(8, 128)
+}
+
+fn perpendicular(v: Vector2D) -> Vector2D {
+    Vector2D::from((-v.y(), v.x()))
+}
+
+fn normalise(v: Vector2D) -> Vector2D {
+    let norm = v.norm();
+
+    if norm == 0. { v } else { v / norm }
+}
+
+fn cross(a: Vector2D, b: Vector2D) -> f32 {
+    a.x() * b.y() - a.y() * b.x()
+}
+
+fn angle(v: Vector2D) -> f32 {
+    v.y().atan2(v.x())
+}
+
+/// `segments + 1` points along an ellipse, with angles clockwise from the x axis as y points
+/// down.
+fn arc_points(centre: Vector2D, radii: Vector2D, angles: Range<f32>, segments: u32) -> Vec<Vector2D> {
+    let step = (angles.end - angles.start) / segments as f32;
+
+    (0..=segments)
+        .map(|i| angles.start + step * i as f32)
+        .map(|x| centre + Vector2D::from((x.cos() * radii.x(), x.sin() * radii.y())))
+        .collect()
+}
+
+fn rect_points(position: Vector2D, size: Vector2D) -> Vec<Vector2D> {
+    let (x, y) = position.xy();
+    let (width, height) = size.xy();
+
+    vec![
+        position,
+        Vector2D::from((x + width, y)),
+        Vector2D::from((x + width, y + height)),
+        Vector2D::from((x, y + height)),
+    ]
+}
+
+fn rounded_rect_points(position: Vector2D, size: Vector2D, radius: f32) -> Vec<Vector2D> {
+    let (x, y) = position.xy();
+    let (width, height) = size.xy();
+    let radius = radius.min(width / 2.).min(height / 2.).max(0.);
+
+    if radius == 0. {
+        return rect_points(position, size);
+    }
+
+    let segments = (segments_for(radius) / 4).max(2);
+    let radii = Vector2D::from((radius, radius));
+    let corners = [
+        (x + width - radius, y + radius, -FRAC_PI_2),
+        (x + width - radius, y + height - radius, 0.),
+        (x + radius, y + height - radius, FRAC_PI_2),
+        (x + radius, y + radius, PI),
+    ];
+
+    corners.iter()
+        .flat_map(|&(x, y, start)| arc_points(Vector2D::from((x, y)), radii, start..start + FRAC_PI_2, segments))
+        .collect()
+}
+
+/// Collects shapes over a frame, tessellates them into triangles and draws them all at once.
+///
+/// Positions are in whatever space the transform given to `flush` expects, usually pixels with
+/// y pointing down. Outlines are centred on the edge of the shape. Buffers are kept between
+/// frames as `SpriteBatch` does.
+pub struct ShapeBatch {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    buffers: Option<BatchBuffers>,
+}
+
+impl ShapeBatch {
+    pub fn new() -> ShapeBatch {
+        ShapeBatch {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            buffers: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Number of triangles queued.
+    pub fn triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    pub fn fill_triangle(&mut self, a: Vector2D, b: Vector2D, c: Vector2D, colour: Colour) {
+        self.fill_convex(&[a, b, c], colour);
+    }
+
+    pub fn fill_rect(&mut self, position: Vector2D, size: Vector2D, colour: Colour) {
+        self.fill_convex(&rect_points(position, size), colour);
+    }
+
+    pub fn stroke_rect(&mut self, position: Vector2D, size: Vector2D, stroke: Stroke, colour: Colour) {
+        self.stroke_path(&rect_points(position, size), true, stroke, colour);
+    }
+
+    /// A rectangle with its corners rounded by `radius`, which is limited to half the shorter
+    /// side.
+    pub fn fill_rounded_rect(&mut self, position: Vector2D, size: Vector2D, radius: f32, colour: Colour) {
+        self.fill_convex(&rounded_rect_points(position, size, radius), colour);
+    }
+
+    pub fn stroke_rounded_rect(&mut self, position: Vector2D, size: Vector2D, radius: f32, stroke: Stroke, colour: Colour) {
+        self.stroke_path(&rounded_rect_points(position, size, radius), true, stroke, colour);
+    }
+
+    pub fn fill_circle(&mut self, centre: Vector2D, radius: f32, segments: u32, colour: Colour) {
+        self.fill_ellipse(centre, Vector2D::from((radius, radius)), segments, colour);
+    }
+
+    pub fn stroke_circle(&mut self, centre: Vector2D, radius: f32, segments: u32, stroke: Stroke, colour: Colour) {
+        self.stroke_ellipse(centre, Vector2D::from((radius, radius)), segments, stroke, colour);
+    }
+
+    pub fn fill_ellipse(&mut self, centre: Vector2D, radii: Vector2D, segments: u32, colour: Colour) {
+        self.fan(centre, radii, 0.0..TAU, segments.max(3), colour);
+    }
+
+    pub fn stroke_ellipse(&mut self, centre: Vector2D, radii: Vector2D, segments: u32, stroke: Stroke, colour: Colour) {
+        let mut points = arc_points(centre, radii, 0.0..TAU, segments.max(3));
+        points.pop();
+
+        self.stroke_path(&points, true, stroke, colour);
+    }
+
+    /// A pie slice between two angles in radians, clockwise from the x axis as y points down.
+    pub fn fill_arc(&mut self, centre: Vector2D, radius: f32, angles: Range<f32>, segments: u32, colour: Colour) {
+        self.fan(centre, Vector2D::from((radius, radius)), angles, segments.max(1), colour);
+    }
+
+    /// The curved edge of a pie slice, capped at both ends.
+    pub fn stroke_arc(&mut self, centre: Vector2D, radius: f32, angles: Range<f32>, segments: u32, stroke: Stroke, colour: Colour) {
+        let points = arc_points(centre, Vector2D::from((radius, radius)), angles, segments.max(1));
+
+        self.stroke_path(&points, false, stroke, colour);
+    }
+
+    pub fn line(&mut self, from: Vector2D, to: Vector2D, stroke: Stroke, colour: Colour) {
+        self.stroke_path(&[from, to], false, stroke, colour);
+    }
+
+    /// Connected line segments through `points`, joined and capped according to `stroke`.
+    pub fn polyline(&mut self, points: &[Vector2D], stroke: Stroke, colour: Colour) {
+        self.stroke_path(points, false, stroke, colour);
+    }
+
+    /// The outline of a polygon, closing it back to the first point.
+    pub fn stroke_polygon(&mut self, points: &[Vector2D], stroke: Stroke, colour: Colour) {
+        self.stroke_path(points, true, stroke, colour);
+    }
+
+    /// Fills a simple polygon, which may be concave. Returns `false` and draws nothing if it
+    /// can't be triangulated, such as when it crosses itself.
+    pub fn fill_polygon(&mut self, points: &[Vector2D], colour: Colour) -> bool {
+        if points.len() < 3 || triangulate::signed_area(points) == 0. {
+            return false;
+        }
+
+        if triangulate::is_convex(points) {
+            self.fill_convex(points, colour);
+            return true;
+        }
+
+        match triangulate(points) {
+            Some(indices) => {
+                let first = self.vertices.len() as u32;
+
+                for &point in points {
+                    self.vertex(point, colour);
+                }
+
+                self.indices.extend(indices.iter().map(|x| first + x));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Draws everything queued as a single draw call with `transform` taking positions to clip
+    /// space, then empties the batch.
+    pub fn flush<B: Backend + ?Sized>(&mut self, backend: &mut B, transform: Matrix4x4) -> Result<(), Error> {
+        if self.indices.is_empty() {
+            self.clear();
+            return Ok(());
+        }
+
+        let indices = self.indices.len();
+        let buffers = BatchBuffers::reserve(&mut self.buffers, backend, self.vertices.len(), indices, |_| {
+            vec![0; indices.next_power_of_two().max(256)]
+        })?;
+
+        backend.update_vertex_buffer(buffers.vertex_buffer, 0, &self.vertices)?;
+        backend.update_index_buffer(buffers.index_buffer, 0, &self.indices)?;
+
+        let mut call = DrawCall::new(buffers.pipeline, buffers.vertex_buffer, self.indices.len());
+        call.indices = Some(buffers.index_buffer);
+        call.transform = transform;
+
+        backend.draw(&call)?;
+        self.clear();
+
+        Ok(())
+    }
+
+    fn vertex(&mut self, position: Vector2D, colour: Colour) -> u32 {
+        self.vertices.push(Vertex::new([position.x(), position.y()], [0., 0.], colour.to_array()));
+        self.vertices.len() as u32 - 1
+    }
+
+    fn fill_convex(&mut self, points: &[Vector2D], colour: Colour) {
+        let first = self.vertices.len() as u32;
+
+        for &point in points {
+            self.vertex(point, colour);
+        }
+
+        for i in 1..points.len().saturating_sub(1) as u32 {
+            self.indices.extend_from_slice(&[first, first + i, first + i + 1]);
+        }
+    }
+
+    fn fan(&mut self, centre: Vector2D, radii: Vector2D, angles: Range<f32>, segments: u32, colour: Colour) {
+        let centre_index = self.vertex(centre, colour);
+        let first = self.vertices.len() as u32;
+
+        for point in arc_points(centre, radii, angles, segments) {
+            self.vertex(point, colour);
+        }
+
+        for i in 0..segments {
+            self.indices.extend_from_slice(&[centre_index, first + i, first + i + 1]);
+        }
+    }
+
+    /// A round cap or join: a fan of radius `radius` sized to how far it turns.
+    fn round(&mut self, centre: Vector2D, radius: f32, angles: Range<f32>, colour: Colour) {
+        let turn = (angles.end - angles.start).abs() / TAU;
+        let segments = ((segments_for(radius) as f32 * turn).ceil() as u32).max(1);
+
+        self.fan(centre, Vector2D::from((radius, radius)), angles, segments, colour);
+    }
+
+    fn stroke_path(&mut self, points: &[Vector2D], closed: bool, stroke: Stroke, colour: Colour) {
+        let mut points = points.to_vec();
+        points.dedup();
+
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+
+        let n = points.len();
+        let closed = closed && n >= 3;
+
+        if n < 2 || stroke.thickness <= 0. {
+            return;
+        }
+
+        let half = stroke.thickness / 2.;
+        let segments = if closed { n } else { n - 1 };
+        let directions: Vec<Vector2D> = (0..segments).map(|i| normalise(points[(i + 1) % n] - points[i])).collect();
+
+        // Left and right vertices where the segments ending and starting at each point meet it
+        let mut ends = vec![(0, 0); n];
+        let mut starts = vec![(0, 0); n];
+
+        for (i, &point) in points.iter().enumerate() {
+            if !closed && (i == 0 || i == n - 1) {
+                let direction = if i == 0 { directions[0] } else { directions[n - 2] };
+                let normal = perpendicular(direction) * half;
+                let end = match (stroke.cap, i) {
+                    (LineCap::Square, 0) => point - direction * half,
+                    (LineCap::Square, _) => point + direction * half,
+                    _                    => point,
+                };
+
+                if stroke.cap == LineCap::Round {
+                    let start = angle(normal) + if i == 0 { 0. } else { PI };
+                    self.round(point, half, start..start + PI, colour);
+                }
+
+                starts[i] = (self.vertex(end + normal, colour), self.vertex(end - normal, colour));
+                ends[i] = starts[i];
+                continue;
+            }
+
+            let incoming = directions[(i + segments - 1) % segments];
+            let outgoing = directions[i % segments];
+            let (normal_in, normal_out) = (perpendicular(incoming), perpendicular(outgoing));
+            let sum = normal_in + normal_out;
+
+            if stroke.join == LineJoin::Miter && sum.norm() > 1e-3 {
+                let miter = normalise(sum);
+                let length = half / (miter * normal_in);
+
+                if length <= MITER_LIMIT * half {
+                    starts[i] = (self.vertex(point + miter * length, colour), self.vertex(point - miter * length, colour));
+                    ends[i] = starts[i];
+                    continue;
+                }
+            }
+
+            ends[i] = (self.vertex(point + normal_in * half, colour), self.vertex(point - normal_in * half, colour));
+            starts[i] = (self.vertex(point + normal_out * half, colour), self.vertex(point - normal_out * half, colour));
+
+            // Fill the gap left on the outside of the corner
+            let turn = cross(incoming, outgoing);
+            let (outer_end, outer_start, side) = match turn > 0. {
+                true  => (ends[i].1, starts[i].1, -1.),
+                false => (ends[i].0, starts[i].0, 1.),
+            };
+
+            if stroke.join == LineJoin::Round {
+                let start = angle(normal_in * side);
+                let mut sweep = angle(normal_out * side) - start;
+
+                if sweep > PI {
+                    sweep -= TAU;
+                } else if sweep < -PI {
+                    sweep += TAU;
+                }
+
+                self.round(point, half, start..start + sweep, colour);
+            } else {
+                let centre = self.vertex(point, colour);
+                self.indices.extend_from_slice(&[centre, outer_end, outer_start]);
+            }
+        }
+
+        for segment in 0..segments {
+            let (a, b) = (starts[segment], ends[(segment + 1) % n]);
+            self.indices.extend_from_slice(&[a.0, a.1, b.1, a.0, b.1, b.0]);
+        }
+    }
+}
+
+impl Default for ShapeBatch {
+    fn default() -> ShapeBatch {
+        ShapeBatch::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::renderer::backend::{ SoftwareBackend, Target };
+    use crate::graphics::renderer::pixel_projection;
+
+    /// Draws the batch in white on black and returns each row with `#` for lit pixels. The
+    /// batch is left without buffers so it can be drawn again on a new backend.
+    fn render(width: u32, height: u32, batch: &mut ShapeBatch) -> Vec<String> {
+        let mut backend = SoftwareBackend::new(width, height);
+        batch.buffers = None;
+        backend.begin_pass(Target::Screen, Some([0., 0., 0., 1.])).unwrap();
+        batch.flush(&mut backend, pixel_projection(width as f32, height as f32)).unwrap();
+
+        (0..height)
+            .map(|y| (0..width).map(|x| if backend.screen().pixel(x, y)[0] > 127 { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    fn v(x: f32, y: f32) -> Vector2D {
+        Vector2D::from((x, y))
+    }
+
+    #[test]
+    fn rects() {
+        let mut batch = ShapeBatch::new();
+        batch.fill_rect(v(1., 1.), v(2., 1.), Colour::default());
+        batch.stroke_rect(v(5., 1.), v(2., 2.), Stroke::new(2.), Colour::default());
+
+        let test = render(8, 4, &mut batch);
+        let correct = vec!["....####", ".##.####", "....####", "....####"];
+
+        assert_eq!(test, correct);
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn caps() {
+        let mut batch = ShapeBatch::new();
+        batch.line(v(2., 1.), v(6., 1.), Stroke::new(2.), Colour::default());
+        batch.line(v(2., 4.), v(6., 4.), Stroke { cap: LineCap::Square, ..Stroke::new(2.) }, Colour::default());
+
+        let test = render(8, 5, &mut batch);
+        let correct = vec!["..####..", "..####..", "........", ".######.", ".######."];
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn joins() {
+        let corner = [v(1., 5.), v(1., 1.), v(5., 1.)];
+        let stroke = Stroke::new(2.);
+
+        let mut batch = ShapeBatch::new();
+        batch.polyline(&corner, stroke, Colour::default());
+        let miter = render(6, 6, &mut batch);
+
+        batch.polyline(&corner, Stroke { join: LineJoin::Bevel, ..stroke }, Colour::default());
+        let bevel = render(6, 6, &mut batch);
+
+        assert_eq!(miter[0], "#####.");
+        assert_eq!(bevel[0], ".####.");
+        assert_eq!(miter[1..], bevel[1..]);
+    }
+
+    #[test]
+    fn polygon() {
+        let mut batch = ShapeBatch::new();
+        let shape = [v(0., 0.), v(4., 0.), v(4., 4.), v(2., 4.), v(2., 2.), v(0., 2.)];
+
+        assert!(batch.fill_polygon(&shape, Colour::default()));
+        assert!(!batch.fill_polygon(&[v(0., 0.), v(1., 1.), v(2., 2.)], Colour::default()));
+
+        let test = render(4, 4, &mut batch);
+        let correct = vec!["####", "####", "..##", "..##"];
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn circles() {
+        let mut batch = ShapeBatch::new();
+        batch.fill_circle(v(4., 4.), 3., 32, Colour::default());
+
+        let test = render(8, 8, &mut batch);
+        let lit = test.iter().flat_map(|x| x.chars()).filter(|&x| x == '#').count();
+
+        assert!((24..=32).contains(&lit), "{} pixels lit", lit);
+        assert_eq!(&test[4][1..7], "######");
+        assert_eq!(test[0].as_bytes()[0], b'.');
+
+        batch.fill_rounded_rect(v(0., 0.), v(8., 4.), 2., Colour::default());
+        let test = render(8, 4, &mut batch);
+
+        assert_eq!(test[0], ".######.");
+        assert_eq!(test[1], "########");
+    }
+
+    #[test]
+    fn arcs() {
+        let mut batch = ShapeBatch::new();
+        batch.fill_arc(v(0., 0.), 4., 0.0..FRAC_PI_2, 8, Colour::default());
+
+        let test = render(4, 4, &mut batch);
+
+        assert_eq!(test[0], "####");
+        assert_eq!(test[3], "##..");
+
+        batch.stroke_arc(v(0., 0.), 3., 0.0..FRAC_PI_2, 8, Stroke::new(1.), Colour::default());
+        let test = render(4, 4, &mut batch);
+
+        assert_eq!(test[0], "..#.");
+        assert_eq!(test[1][..2].to_string(), "..");
+    }
+}
@@ -0,0 +1,129 @@
+use crate::maths::Vector2D;
+
+fn cross(a: Vector2D, b: Vector2D) -> f32 {
+    a.x() * b.y() - a.y() * b.x()
+}
+
+/// Twice the signed area of a polygon, positive when its points run anticlockwise with y up.
+pub fn signed_area(points: &[Vector2D]) -> f32 {
+    points.iter().zip(points.iter().cycle().skip(1)).map(|(a, b)| cross(*a, *b)).sum()
+}
+
+/// Whether the polygon is convex, which lets it be drawn as a fan. Every corner must turn
+/// the same way and the turns must add up to a single revolution, which rules out stars and
+/// other outlines that wind round more than once.
+pub fn is_convex(points: &[Vector2D]) -> bool {
+    let n = points.len();
+    let mut sign = 0.;
+    let mut turning = 0.;
+
+    for i in 0..n {
+        let (a, b) = (points[(i + 1) % n] - points[i], points[(i + 2) % n] - points[(i + 1) % n]);
+        let turn = cross(a, b);
+
+        if turn != 0. {
+            if sign * turn < 0. {
+                return false;
+            }
+
+            sign = turn;
+            turning += turn.atan2(a.x() * b.x() + a.y() * b.y());
+        }
+    }
+
+    (turning.abs() - 2. * std::f32::consts::PI).abs() < 1e-3
+}
+
+/// Splits a simple polygon, convex or not and wound either way, into triangles by ear
+/// clipping. Returns indices into `points`, three per triangle, or `None` if the polygon has
+/// no area or crosses itself so badly no ear can be found.
+pub fn triangulate(points: &[Vector2D]) -> Option<Vec<u32>> {
+    let area = signed_area(points);
+
+    if points.len() < 3 || area == 0. {
+        return None;
+    }
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+
+    if area < 0. {
+        remaining.reverse();
+    }
+
+    let mut indices = Vec::with_capacity((points.len() - 2) * 3);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+            is_ear(points, &remaining, a, b, c)
+        })?;
+
+        indices.extend_from_slice(&[remaining[(ear + n - 1) % n] as u32, remaining[ear] as u32, remaining[(ear + 1) % n] as u32]);
+        remaining.remove(ear);
+    }
+
+    indices.extend(remaining.iter().map(|&x| x as u32));
+
+    Some(indices)
+}
+
+fn is_ear(points: &[Vector2D], remaining: &[usize], a: usize, b: usize, c: usize) -> bool {
+    let (pa, pb, pc) = (points[a], points[b], points[c]);
+
+    // Reflex or flat corners aren't ears
+    if cross(pb - pa, pc - pb) <= 0. {
+        return false;
+    }
+
+    remaining.iter()
+        .filter(|&&x| x != a && x != b && x != c)
+        .map(|&x| points[x])
+        .filter(|&x| x != pa && x != pb && x != pc)
+        .all(|x| !(cross(pb - pa, x - pa) >= 0. && cross(pc - pb, x - pb) >= 0. && cross(pa - pc, x - pc) >= 0.))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(xy: &[(f32, f32)]) -> Vec<Vector2D> {
+        xy.iter().map(|&x| Vector2D::from(x)).collect()
+    }
+
+    fn area(points: &[Vector2D], indices: &[u32]) -> f32 {
+        indices.chunks(3).map(|x| signed_area(&[points[x[0] as usize], points[x[1] as usize], points[x[2] as usize]]).abs() / 2.).sum()
+    }
+
+    #[test]
+    fn convex() {
+        let square = points(&[(0., 0.), (1., 0.), (1., 1.), (0., 1.)]);
+        let arrow = points(&[(0., 0.), (2., 1.), (0., 2.), (1., 1.)]);
+
+        let pentagram: Vec<Vector2D> = (0..5).map(|i| {
+            let angle = i as f32 * 4. * std::f32::consts::PI / 5.;
+            Vector2D::from((angle.cos(), angle.sin()))
+        }).collect();
+
+        assert!(is_convex(&square));
+        assert!(!is_convex(&arrow));
+        assert!(!is_convex(&pentagram));
+        assert_eq!(signed_area(&square), 2.);
+    }
+
+    #[test]
+    fn concave() {
+        // An L shape, wound clockwise with y up
+        let shape = points(&[(0., 0.), (0., 3.), (1., 3.), (1., 1.), (3., 1.), (3., 0.)]);
+        let test = triangulate(&shape).unwrap();
+
+        assert_eq!(test.len(), 12);
+        assert_eq!(area(&shape, &test), 5.);
+    }
+
+    #[test]
+    fn degenerate() {
+        assert_eq!(triangulate(&points(&[(0., 0.), (1., 1.), (2., 2.)])), None);
+        assert_eq!(triangulate(&points(&[(0., 0.), (1., 1.)])), None);
+    }
+}
@@ -0,0 +1,546 @@
+use std::path::{ Component, Path };
+use serde::{ Deserialize, Serialize };
+use crate::Error;
+use crate::graphics::image::Image;
+use crate::graphics::renderer::backend::{ Backend, TextureId };
+use super::TextureRegion;
+
+/// Packs rectangles into a fixed area, placing each as low and then as far left as it fits on
+/// the skyline left by the ones before it.
+#[derive(Clone, Debug)]
+pub struct Packer {
+    width: u32,
+    height: u32,
+    skyline: Vec<Segment>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Segment {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+impl Packer {
+    pub fn new(width: u32, height: u32) -> Packer {
+        Packer {
+            width,
+            height,
+            skyline: vec![Segment { x: 0, y: 0, width }],
+        }
+    }
+
+    /// Finds room for a `width` by `height` rectangle, returning its top left corner.
+    pub fn pack(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let mut best: Option<(usize, u32, u32)> = None;
+
+        for i in 0..self.skyline.len() {
+            if let Some(y) = self.fits(i, width, height) {
+                let x = self.skyline[i].x;
+                let better = match best {
+                    Some((_, best_x, best_y)) => (y, x) < (best_y, best_x),
+                    None                      => true,
+                };
+
+                if better {
+                    best = Some((i, x, y));
+                }
+            }
+        }
+
+        let (i, x, y) = best?;
+        self.raise(i, Segment { x, y: y + height, width });
+
+        Some((x, y))
+    }
+
+    /// Height a rectangle starting at segment `i` would sit at, if it fits there.
+    fn fits(&self, i: usize, width: u32, height: u32) -> Option<u32> {
+        if self.skyline[i].x + width > self.width {
+            return None;
+        }
+
+        let end = self.skyline[i].x + width;
+        let mut y = 0;
+
+        for segment in self.skyline[i..].iter().take_while(|x| x.x < end) {
+            y = y.max(segment.y);
+        }
+
+        if y + height > self.height {
+            return None;
+        }
+
+        Some(y)
+    }
+
+    fn raise(&mut self, i: usize, segment: Segment) {
+        let end = segment.x + segment.width;
+        self.skyline.insert(i, segment);
+
+        // Trim or remove the segments the new one covers
+        while i + 1 < self.skyline.len() && self.skyline[i + 1].x < end {
+            let next = &mut self.skyline[i + 1];
+            let next_end = next.x + next.width;
+
+            if next_end <= end {
+                self.skyline.remove(i + 1);
+            } else {
+                next.width = next_end - end;
+                next.x = end;
+                break;
+            }
+        }
+
+        let mut j = 0;
+
+        while j + 1 < self.skyline.len() {
+            if self.skyline[j].y == self.skyline[j + 1].y {
+                self.skyline[j].width += self.skyline[j + 1].width;
+                self.skyline.remove(j + 1);
+            } else {
+                j += 1;
+            }
+        }
+    }
+}
+
+/// A page image of an atlas as stored in its descriptor.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasPage {
+    /// Path of the page's PNG, relative to the descriptor. Empty until the atlas is saved.
+    pub file: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Where a named image ended up, in pixels from the top left of its page.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasRegion {
+    pub name: String,
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasRegion {
+    /// Left, top, right and bottom in texture coordinates of `page`.
+    pub fn uv(&self, page: &AtlasPage) -> [f32; 4] {
+        let (width, height) = (page.width as f32, page.height as f32);
+
+        [
+            self.x as f32 / width,
+            self.y as f32 / height,
+            (self.x + self.width) as f32 / width,
+            (self.y + self.height) as f32 / height,
+        ]
+    }
+}
+
+/// The layout of an atlas, which is all that's needed besides the page images to use it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasLayout {
+    pub pages: Vec<AtlasPage>,
+    /// Sorted by name.
+    pub regions: Vec<AtlasRegion>,
+}
+
+impl AtlasLayout {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("atlas layouts always serialise")
+    }
+
+    /// Parses a descriptor, checking every region lies within its page.
+    pub fn from_json(json: &str) -> Result<AtlasLayout, Error> {
+        let mut layout: AtlasLayout = serde_json::from_str(json).map_err(|x| Error::Atlas(x.to_string()))?;
+        layout.regions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for region in &layout.regions {
+            let page = layout.pages.get(region.page).ok_or_else(|| Error::Atlas(format!("region '{}' is on missing page {}", region.name, region.page)))?;
+
+            let inside = match (region.x.checked_add(region.width), region.y.checked_add(region.height)) {
+                (Some(right), Some(bottom)) => right <= page.width && bottom <= page.height,
+                _ => false,
+            };
+
+            if !inside {
+                return Err(Error::Atlas(format!("region '{}' is outside its page", region.name)));
+            }
+        }
+
+        if let Some(x) = layout.regions.windows(2).find(|x| x[0].name == x[1].name) {
+            return Err(Error::Atlas(format!("region '{}' is defined twice", x[0].name)));
+        }
+
+        Ok(layout)
+    }
+}
+
+/// Collects images and packs them into atlas pages.
+#[derive(Clone, Debug)]
+pub struct AtlasBuilder {
+    max_size: u32,
+    padding: u32,
+    extrusion: u32,
+    power_of_two: bool,
+    images: Vec<(String, Image)>,
+}
+
+impl AtlasBuilder {
+    /// Builds pages up to `max_size` pixels square, with no padding or extrusion.
+    pub fn new(max_size: u32) -> AtlasBuilder {
+        AtlasBuilder {
+            max_size,
+            padding: 0,
+            extrusion: 0,
+            power_of_two: false,
+            images: Vec::new(),
+        }
+    }
+
+    /// Transparent pixels left between neighbouring images.
+    pub fn with_padding(mut self, padding: u32) -> AtlasBuilder {
+        self.padding = padding;
+        self
+    }
+
+    /// Repeats the edge pixels of every image this many times outwards, so filtering at the
+    /// edge of a region doesn't pick up its neighbours.
+    pub fn with_extrusion(mut self, extrusion: u32) -> AtlasBuilder {
+        self.extrusion = extrusion;
+        self
+    }
+
+    /// Rounds page sizes up to powers of two. The maximum size is rounded down to one.
+    pub fn with_power_of_two(mut self, power_of_two: bool) -> AtlasBuilder {
+        self.power_of_two = power_of_two;
+        self
+    }
+
+    pub fn add<S: Into<String>>(&mut self, name: S, image: Image) -> &mut AtlasBuilder {
+        self.images.push((name.into(), image));
+        self
+    }
+
+    pub fn build(&self) -> Result<Atlas, Error> {
+        let max_size = match self.power_of_two {
+            true  => 1 << (31 - self.max_size.max(1).leading_zeros()),
+            false => self.max_size,
+        };
+
+        // Tallest first packs tightest
+        let mut order: Vec<_> = (0..self.images.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.images[a], &self.images[b]);
+            (b.1.height(), b.1.width()).cmp(&(a.1.height(), a.1.width())).then_with(|| a.0.cmp(&b.0))
+        });
+
+        let border = 2 * self.extrusion;
+        // Every cell carries padding on its right and bottom, which may hang off the page
+        let mut packers: Vec<Packer> = Vec::new();
+        let mut used: Vec<(u32, u32)> = Vec::new();
+        let mut placed = Vec::with_capacity(order.len());
+
+        for i in order {
+            let (name, image) = &self.images[i];
+
+            if image.width() == 0 || image.height() == 0 {
+                return Err(Error::Atlas(format!("image '{}' is empty", name)));
+            }
+
+            let (width, height) = (image.width() + border + self.padding, image.height() + border + self.padding);
+            let found = packers.iter_mut().enumerate().find_map(|(page, x)| x.pack(width, height).map(|(x, y)| (page, x, y)));
+
+            let (page, x, y) = match found {
+                Some(x) => x,
+                None    => {
+                    let mut packer = Packer::new(max_size + self.padding, max_size + self.padding);
+                    let (x, y) = packer.pack(width, height).ok_or_else(|| {
+                        Error::Atlas(format!("{}x{} image '{}' doesn't fit on a {}x{} page", image.width(), image.height(), name, max_size, max_size))
+                    })?;
+
+                    packers.push(packer);
+                    used.push((0, 0));
+                    (packers.len() - 1, x, y)
+                },
+            };
+
+            used[page].0 = used[page].0.max(x + width - self.padding);
+            used[page].1 = used[page].1.max(y + height - self.padding);
+            placed.push((i, page, x, y));
+        }
+
+        let mut pages: Vec<Image> = used.iter().map(|&(width, height)| match self.power_of_two {
+            true  => Image::new(width.next_power_of_two(), height.next_power_of_two()),
+            false => Image::new(width, height),
+        }).collect();
+
+        let mut regions = Vec::with_capacity(placed.len());
+
+        for (i, page, x, y) in placed {
+            let (name, image) = &self.images[i];
+            blit_extruded(&mut pages[page], x, y, image, self.extrusion);
+
+            regions.push(AtlasRegion {
+                name: name.clone(),
+                page,
+                x: x + self.extrusion,
+                y: y + self.extrusion,
+                width: image.width(),
+                height: image.height(),
+            });
+        }
+
+        regions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if let Some(x) = regions.windows(2).find(|x| x[0].name == x[1].name) {
+            return Err(Error::Atlas(format!("image '{}' was added twice", x[0].name)));
+        }
+
+        let layout = AtlasLayout {
+            pages: pages.iter().map(|x| AtlasPage { file: String::new(), width: x.width(), height: x.height() }).collect(),
+            regions,
+        };
+
+        Ok(Atlas { layout, pages })
+    }
+}
+
+/// Copies `image` to `x + extrusion`, `y + extrusion` in `page`, with its edges repeated
+/// `extrusion` pixels outwards.
+fn blit_extruded(page: &mut Image, x: u32, y: u32, image: &Image, extrusion: u32) {
+    for dy in 0..image.height() + 2 * extrusion {
+        for dx in 0..image.width() + 2 * extrusion {
+            let source_x = dx.saturating_sub(extrusion).min(image.width() - 1);
+            let source_y = dy.saturating_sub(extrusion).min(image.height() - 1);
+
+            page.set_pixel(x + dx, y + dy, image.pixel(source_x, source_y));
+        }
+    }
+}
+
+/// Images packed onto pages, with the regions they were packed into found by name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Atlas {
+    layout: AtlasLayout,
+    pages: Vec<Image>,
+}
+
+impl Atlas {
+    /// Puts a layout back together with its page images, which must be the sizes it expects.
+    pub fn from_parts(layout: AtlasLayout, pages: Vec<Image>) -> Result<Atlas, Error> {
+        if layout.pages.len() != pages.len() {
+            return Err(Error::Atlas(format!("layout has {} pages but {} images were given", layout.pages.len(), pages.len())));
+        }
+
+        for (i, (page, image)) in layout.pages.iter().zip(&pages).enumerate() {
+            if (page.width, page.height) != (image.width(), image.height()) {
+                return Err(Error::Atlas(format!("page {} should be {}x{} but is {}x{}", i, page.width, page.height, image.width(), image.height())));
+            }
+        }
+
+        Ok(Atlas { layout, pages })
+    }
+
+    pub fn layout(&self) -> &AtlasLayout {
+        &self.layout
+    }
+
+    pub fn pages(&self) -> &[Image] {
+        &self.pages
+    }
+
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.layout.regions.binary_search_by(|x| x.name.as_str().cmp(name)).ok().map(|i| &self.layout.regions[i])
+    }
+
+    /// Texture coordinates of the named region on its page.
+    pub fn uv(&self, name: &str) -> Option<[f32; 4]> {
+        self.region(name).map(|x| x.uv(&self.layout.pages[x.page]))
+    }
+
+    /// Creates a texture for each page, in order.
+    pub fn upload<B: Backend + ?Sized>(&self, backend: &mut B) -> Result<Vec<TextureId>, Error> {
+        self.pages.iter().map(|x| backend.create_texture(x)).collect()
+    }
+
+    /// The named region as something a sprite can draw, given the page textures from `upload`.
+    pub fn texture_region(&self, name: &str, textures: &[TextureId]) -> Option<TextureRegion> {
+        let region = self.region(name)?;
+        let page = &self.layout.pages[region.page];
+
+        Some(TextureRegion::new(*textures.get(region.page)?, page.width, page.height).sub(region.x, region.y, region.width, region.height))
+    }
+
+    /// Writes the descriptor to `path` and each page next to it as a PNG named after it.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let stem = path.file_stem().and_then(|x| x.to_str()).unwrap_or("atlas");
+        let mut layout = self.layout.clone();
+
+        for (i, (page, image)) in layout.pages.iter_mut().zip(&self.pages).enumerate() {
+            page.file = format!("{}_{}.png", stem, i);
+            image.save_png(path.with_file_name(&page.file))?;
+        }
+
+        std::fs::write(path, layout.to_json()).map_err(|error| Error::AssetIo { path: path.to_path_buf(), error })
+    }
+
+    /// Reads a descriptor and the page images it names, which must be relative paths inside
+    /// the descriptor's directory.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Atlas, Error> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|error| Error::AssetIo { path: path.to_path_buf(), error })?;
+        let layout = AtlasLayout::from_json(&json)?;
+
+        if let Some(x) = layout.pages.iter().find(|x| !Path::new(&x.file).components().all(|x| matches!(x, Component::Normal(_)))) {
+            return Err(Error::Atlas(format!("page file '{}' isn't a relative path inside the atlas directory", x.file)));
+        }
+
+        let pages = layout.pages.iter().map(|x| Image::load(path.with_file_name(&x.file))).collect::<Result<_, _>>()?;
+
+        Atlas::from_parts(layout, pages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::renderer::backend::SoftwareBackend;
+    use crate::test_util::TempDir;
+
+    fn solid(width: u32, height: u32, rgba: [u8; 4]) -> Image {
+        let mut image = Image::new(width, height);
+        image.fill(rgba);
+        image
+    }
+
+    fn overlaps(a: &AtlasRegion, b: &AtlasRegion) -> bool {
+        a.page == b.page && a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+    }
+
+    #[test]
+    fn packer() {
+        let mut packer = Packer::new(4, 4);
+
+        let test = [packer.pack(2, 3), packer.pack(2, 2), packer.pack(2, 2), packer.pack(2, 1), packer.pack(1, 1)];
+        let correct = [Some((0, 0)), Some((2, 0)), Some((2, 2)), Some((0, 3)), None];
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn no_overlaps() {
+        let mut builder = AtlasBuilder::new(64).with_padding(1);
+
+        for i in 0..20 {
+            builder.add(format!("{:02}", i), solid(3 + i % 7, 2 + i % 5, [255; 4]));
+        }
+
+        let atlas = builder.build().unwrap();
+        let regions = &atlas.layout().regions;
+
+        assert_eq!(atlas.pages().len(), 1);
+        assert_eq!(regions.len(), 20);
+
+        for (i, a) in regions.iter().enumerate() {
+            for b in &regions[i + 1..] {
+                assert!(!overlaps(a, b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn padding_and_extrusion() {
+        let mut image = solid(2, 1, [255, 0, 0, 255]);
+        image.set_pixel(1, 0, [0, 0, 255, 255]);
+
+        let mut builder = AtlasBuilder::new(16).with_padding(1).with_extrusion(1);
+        builder.add("a", image).add("b", solid(1, 1, [0, 255, 0, 255]));
+        let atlas = builder.build().unwrap();
+        let page = &atlas.pages()[0];
+
+        let test = atlas.region("a").cloned();
+        let correct = Some(AtlasRegion { name: String::from("a"), page: 0, x: 1, y: 1, width: 2, height: 1 });
+
+        assert_eq!(test, correct);
+        assert_eq!((page.width(), page.height()), (8, 3));
+        // Extruded corners and edges, then the gap left by padding
+        assert_eq!(page.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(page.pixel(3, 2), [0, 0, 255, 255]);
+        assert_eq!(page.pixel(4, 1), [0; 4]);
+        assert_eq!(atlas.region("b").unwrap().x, 6);
+    }
+
+    #[test]
+    fn pages() {
+        let mut builder = AtlasBuilder::new(10).with_power_of_two(true);
+        builder.add("a", solid(8, 8, [255; 4])).add("b", solid(5, 3, [255; 4]));
+        let atlas = builder.build().unwrap();
+
+        let test: Vec<_> = atlas.pages().iter().map(|x| (x.width(), x.height())).collect();
+        let correct = vec![(8, 8), (8, 4)];
+
+        assert_eq!(test, correct);
+        assert_eq!(atlas.uv("b"), Some([0., 0., 0.625, 0.75]));
+        assert!(matches!(AtlasBuilder::new(4).add("c", solid(5, 1, [0; 4])).build(), Err(Error::Atlas(_))));
+        assert!(matches!(AtlasBuilder::new(4).add("d", solid(1, 1, [0; 4])).add("d", solid(1, 1, [0; 4])).build(), Err(Error::Atlas(_))));
+    }
+
+    #[test]
+    fn texture_region() {
+        let mut builder = AtlasBuilder::new(8);
+        builder.add("a", solid(4, 2, [255; 4])).add("b", solid(2, 2, [255; 4]));
+        let atlas = builder.build().unwrap();
+
+        let mut backend = SoftwareBackend::new(1, 1);
+        let textures = atlas.upload(&mut backend).unwrap();
+        let region = atlas.texture_region("b", &textures).unwrap();
+
+        assert_eq!(region.texture(), textures[0]);
+        assert_eq!(region.uv(), [4. / 6., 0., 1., 1.]);
+        assert_eq!(region.size().xy(), (2., 2.));
+        assert_eq!(atlas.texture_region("c", &textures), None);
+    }
+
+    #[test]
+    fn json() {
+        let mut builder = AtlasBuilder::new(8);
+        builder.add("b", solid(2, 2, [255; 4])).add("a", solid(1, 3, [255; 4]));
+        let layout = builder.build().unwrap().layout().clone();
+
+        assert_eq!(AtlasLayout::from_json(&layout.to_json()).unwrap(), layout);
+        assert!(AtlasLayout::from_json("{ \"pages\": [], \"regions\": [{ \"name\": \"a\", \"page\": 0, \"x\": 0, \"y\": 0, \"width\": 1, \"height\": 1 }] }").is_err());
+        assert!(AtlasLayout::from_json("not json").is_err());
+
+        let mut overflowing = layout.clone();
+        overflowing.regions[0].x = u32::MAX;
+        assert!(matches!(AtlasLayout::from_json(&overflowing.to_json()), Err(Error::Atlas(_))));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let directory = TempDir::new("atlas");
+
+        let mut builder = AtlasBuilder::new(8).with_extrusion(1);
+        builder.add("a", solid(2, 2, [10, 20, 30, 255])).add("b", solid(3, 1, [40, 50, 60, 128]));
+        let atlas = builder.build().unwrap();
+        atlas.save(directory.join("sprites.json")).unwrap();
+
+        let test = Atlas::load(directory.join("sprites.json")).unwrap();
+
+        assert_eq!(test.pages(), atlas.pages());
+        assert_eq!(test.layout().regions, atlas.layout().regions);
+        assert_eq!(test.layout().pages[0].file, "sprites_0.png");
+
+        for file in ["../sprites_0.png", "/sprites_0.png"] {
+            let mut layout = atlas.layout().clone();
+            layout.pages[0].file = String::from(file);
+            std::fs::write(directory.join("escape.json"), layout.to_json()).unwrap();
+
+            assert!(matches!(Atlas::load(directory.join("escape.json")), Err(Error::Atlas(_))));
+        }
+    }
+}
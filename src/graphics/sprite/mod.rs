@@ -0,0 +1,321 @@
+use std::cmp::Ordering;
+use crate::Error;
+use crate::graphics::Colour;
+use crate::graphics::renderer::backend::{ Backend, BatchBuffers, DrawCall, TextureId };
+use crate::graphics::renderer::vertex::Vertex;
+use crate::maths::{ Matrix4x4, Vector2D };
+
+pub mod atlas;
+
+pub use atlas::{ Atlas, AtlasBuilder };
+
+/// Part of a texture to draw, with its size in pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureRegion {
+    texture: TextureId,
+    /// Left, top, right and bottom in texture coordinates.
+    uv: [f32; 4],
+    width: f32,
+    height: f32,
+}
+
+impl TextureRegion {
+    /// The whole of a `width` by `height` texture.
+    pub fn new(texture: TextureId, width: u32, height: u32) -> TextureRegion {
+        TextureRegion {
+            texture,
+            uv: [0., 0., 1., 1.],
+            width: width as f32,
+            height: height as f32,
+        }
+    }
+
+    /// The part of this region `width` by `height` pixels from `x`, `y` in its top left corner.
+    pub fn sub(&self, x: u32, y: u32, width: u32, height: u32) -> TextureRegion {
+        let scale_u = (self.uv[2] - self.uv[0]) / self.width;
+        let scale_v = (self.uv[3] - self.uv[1]) / self.height;
+        let left = self.uv[0] + x as f32 * scale_u;
+        let top = self.uv[1] + y as f32 * scale_v;
+
+        TextureRegion {
+            texture: self.texture,
+            uv: [left, top, left + width as f32 * scale_u, top + height as f32 * scale_v],
+            width: width as f32,
+            height: height as f32,
+        }
+    }
+
+    pub fn texture(&self) -> TextureId {
+        self.texture
+    }
+
+    pub fn uv(&self) -> [f32; 4] {
+        self.uv
+    }
+
+    pub fn size(&self) -> Vector2D {
+        Vector2D::from((self.width, self.height))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+    pub region: TextureRegion,
+    pub position: Vector2D,
+    /// Clockwise rotation in radians around the origin, with y pointing down.
+    pub rotation: f32,
+    pub scale: Vector2D,
+    /// Point the sprite is positioned, rotated and scaled around, from `(0, 0)` at the top
+    /// left of the region to `(1, 1)` at the bottom right.
+    pub origin: Vector2D,
+    pub colour: Colour,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Sprites on higher layers are drawn on top.
+    pub layer: i32,
+}
+
+impl Sprite {
+    pub fn new(region: TextureRegion, position: Vector2D) -> Sprite {
+        Sprite {
+            region,
+            position,
+            rotation: 0.,
+            scale: Vector2D::from((1., 1.)),
+            origin: Vector2D::from((0., 0.)),
+            colour: Colour::default(),
+            flip_x: false,
+            flip_y: false,
+            layer: 0,
+        }
+    }
+
+    /// Corners clockwise from the top left, after scaling, rotation and translation.
+    pub fn corners(&self) -> [Vector2D; 4] {
+        let (width, height) = self.region.size().xy();
+        let (scale_x, scale_y) = self.scale.xy();
+        let (origin_x, origin_y) = (self.origin.x() * width, self.origin.y() * height);
+        let (sin, cos) = self.rotation.sin_cos();
+
+        let corner = |x: f32, y: f32| {
+            let (x, y) = ((x - origin_x) * scale_x, (y - origin_y) * scale_y);
+            self.position + Vector2D::from((x * cos - y * sin, x * sin + y * cos))
+        };
+
+        [corner(0., 0.), corner(width, 0.), corner(width, height), corner(0., height)]
+    }
+
+    fn vertices(&self) -> [Vertex; 4] {
+        let [mut left, mut top, mut right, mut bottom] = self.region.uv;
+
+        if self.flip_x {
+            std::mem::swap(&mut left, &mut right);
+        }
+
+        if self.flip_y {
+            std::mem::swap(&mut top, &mut bottom);
+        }
+
+        let corners = self.corners();
+        let colour = self.colour.to_array();
+        let vertex = |i: usize, uv: [f32; 2]| Vertex::new([corners[i].x(), corners[i].y()], uv, colour);
+
+        [vertex(0, [left, top]), vertex(1, [right, top]), vertex(2, [right, bottom]), vertex(3, [left, bottom])]
+    }
+}
+
+/// Collects sprites over a frame and draws them in as few draw calls as possible.
+///
+/// Sprites are sorted by layer and then by texture, keeping submission order otherwise, and
+/// every run of sprites sharing a texture becomes one draw call. The batch keeps its buffers
+/// between frames, so use each batch with a single backend.
+pub struct SpriteBatch {
+    sprites: Vec<Sprite>,
+    vertices: Vec<Vertex>,
+    buffers: Option<BatchBuffers>,
+}
+
+impl SpriteBatch {
+    pub fn new() -> SpriteBatch {
+        SpriteBatch {
+            sprites: Vec::new(),
+            vertices: Vec::new(),
+            buffers: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    pub fn draw(&mut self, sprite: &Sprite) {
+        self.sprites.push(*sprite);
+    }
+
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    /// Draws every queued sprite with `transform` taking positions to clip space, then empties
+    /// the batch. Returns the number of draw calls made.
+    pub fn flush<B: Backend + ?Sized>(&mut self, backend: &mut B, transform: Matrix4x4) -> Result<usize, Error> {
+        if self.sprites.is_empty() {
+            return Ok(0);
+        }
+
+        // Stable, so sprites on the same layer and texture keep their order
+        self.sprites.sort_by(|a, b| match a.layer.cmp(&b.layer) {
+            Ordering::Equal => a.region.texture.0.cmp(&b.region.texture.0),
+            x => x,
+        });
+
+        self.vertices.clear();
+
+        for sprite in &self.sprites {
+            self.vertices.extend_from_slice(&sprite.vertices());
+        }
+
+        let count = self.sprites.len();
+
+        // Every sprite is a quad of two triangles, so the indices never change
+        let buffers = BatchBuffers::reserve(&mut self.buffers, backend, count * 4, count * 6, |vertices| {
+            (0..(vertices / 4) as u32).flat_map(|x| [0, 1, 2, 0, 2, 3].iter().map(move |i| x * 4 + i)).collect()
+        })?;
+
+        backend.update_vertex_buffer(buffers.vertex_buffer, 0, &self.vertices)?;
+
+        let mut draw_calls = 0;
+        let mut start = 0;
+
+        while start < self.sprites.len() {
+            let texture = self.sprites[start].region.texture;
+            let end = self.sprites[start..].iter().position(|x| x.region.texture != texture).map_or(self.sprites.len(), |x| start + x);
+
+            let mut call = DrawCall::new(buffers.pipeline, buffers.vertex_buffer, (end - start) * 6);
+            call.indices = Some(buffers.index_buffer);
+            call.first = start * 6;
+            call.texture = Some(texture);
+            call.transform = transform;
+
+            backend.draw(&call)?;
+            draw_calls += 1;
+            start = end;
+        }
+
+        self.sprites.clear();
+
+        Ok(draw_calls)
+    }
+}
+
+impl Default for SpriteBatch {
+    fn default() -> SpriteBatch {
+        SpriteBatch::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::image::Image;
+    use crate::graphics::renderer::backend::{ SoftwareBackend, Target };
+    use crate::graphics::renderer::pixel_projection;
+
+    fn texture(backend: &mut SoftwareBackend, pixels: &[[u8; 4]], width: u32) -> TextureRegion {
+        let height = pixels.len() as u32 / width;
+        let image = Image::from_rgba(width, height, pixels.concat()).unwrap();
+
+        TextureRegion::new(backend.create_texture(&image).unwrap(), width, height)
+    }
+
+    #[test]
+    fn sub_region() {
+        let region = TextureRegion::new(TextureId(1), 8, 4);
+        let test = region.sub(2, 1, 4, 2).sub(2, 0, 2, 1);
+
+        assert_eq!(test.uv(), [0.5, 0.25, 0.75, 0.5]);
+        assert_eq!(test.size(), Vector2D::from((2., 1.)));
+    }
+
+    #[test]
+    fn corners() {
+        let mut sprite = Sprite::new(TextureRegion::new(TextureId(1), 4, 2), Vector2D::from((10., 10.)));
+        sprite.origin = Vector2D::from((0.5, 0.5));
+        sprite.scale = Vector2D::from((2., 1.));
+        sprite.rotation = std::f32::consts::FRAC_PI_2;
+
+        let test: Vec<(i32, i32)> = sprite.corners().iter().map(|x| (x.x().round() as i32, x.y().round() as i32)).collect();
+        let correct = vec![(11, 6), (11, 14), (9, 14), (9, 6)];
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn batches_by_layer_and_texture() {
+        let mut backend = SoftwareBackend::new(4, 1);
+        let red = texture(&mut backend, &[[255, 0, 0, 255]], 1);
+        let blue = texture(&mut backend, &[[0, 0, 255, 255]], 1);
+        let mut batch = SpriteBatch::new();
+
+        for x in 0..4 {
+            let mut sprite = Sprite::new(if x % 2 == 0 { red } else { blue }, Vector2D::from((x as f32, 0.)));
+            sprite.layer = 1;
+            batch.draw(&sprite);
+        }
+
+        // Underneath the others despite coming last, and covered by the second red sprite
+        let mut background = Sprite::new(blue, Vector2D::from((2., 0.)));
+        background.layer = -1;
+        batch.draw(&background);
+
+        backend.begin_pass(Target::Screen, Some([0., 0., 0., 1.])).unwrap();
+        let test = batch.flush(&mut backend, pixel_projection(4., 1.)).unwrap();
+
+        assert_eq!(test, 3);
+        assert!(batch.is_empty());
+
+        let image = backend.read_pixels(Target::Screen).unwrap();
+        let pixels: Vec<[u8; 4]> = (0..4).map(|x| image.pixel(x, 0)).collect();
+
+        assert_eq!(pixels, vec![[255, 0, 0, 255], [0, 0, 255, 255], [255, 0, 0, 255], [0, 0, 255, 255]]);
+    }
+
+    #[test]
+    fn tint_and_flip() {
+        let mut backend = SoftwareBackend::new(2, 1);
+        let region = texture(&mut backend, &[[255, 255, 255, 255], [0, 255, 0, 255]], 2);
+        let mut sprite = Sprite::new(region, Vector2D::from((0., 0.)));
+        sprite.flip_x = true;
+        sprite.colour = Colour::new(1., 0.5, 1., 1.);
+
+        let mut batch = SpriteBatch::new();
+        batch.draw(&sprite);
+
+        backend.begin_pass(Target::Screen, Some([0., 0., 0., 1.])).unwrap();
+        batch.flush(&mut backend, pixel_projection(2., 1.)).unwrap();
+
+        assert_eq!(backend.screen().pixel(0, 0), [0, 128, 0, 255]);
+        assert_eq!(backend.screen().pixel(1, 0), [255, 128, 255, 255]);
+    }
+
+    #[test]
+    fn grows() {
+        let mut backend = SoftwareBackend::new(100, 100);
+        let region = texture(&mut backend, &[[255; 4]], 1);
+        let mut batch = SpriteBatch::new();
+
+        for i in 0..1000 {
+            batch.draw(&Sprite::new(region, Vector2D::from(((i % 100) as f32, (i / 100) as f32))));
+        }
+
+        backend.begin_pass(Target::Screen, Some([0., 0., 0., 1.])).unwrap();
+
+        assert_eq!(batch.flush(&mut backend, pixel_projection(100., 100.)).unwrap(), 1);
+        assert_eq!(backend.screen().pixel(99, 9), [255; 4]);
+        assert_eq!(backend.screen().pixel(0, 10), [0, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,282 @@
+use std::path::Path;
+use crate::Error;
+use crate::graphics::image::Image;
+
+/// How texels are sampled when a texture is drawn smaller or larger than it is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+/// What happens to texture coordinates outside 0 to 1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Wrap {
+    ClampToEdge,
+    Repeat,
+    MirroredRepeat,
+}
+
+impl From<Wrap> for gl::types::GLenum {
+    fn from(wrap: Wrap) -> gl::types::GLenum {
+        match wrap {
+            Wrap::ClampToEdge    => gl::CLAMP_TO_EDGE,
+            Wrap::Repeat         => gl::REPEAT,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+        }
+    }
+}
+
+/// Sampling settings for a texture. The default suits pixel art: nearest filtering, clamped
+/// edges and no mipmaps.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextureOptions {
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    pub wrap_s: Wrap,
+    pub wrap_t: Wrap,
+    /// Generate mipmaps on upload and sample between them when minifying.
+    pub mipmaps: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> TextureOptions {
+        TextureOptions {
+            min_filter: Filter::Nearest,
+            mag_filter: Filter::Nearest,
+            wrap_s: Wrap::ClampToEdge,
+            wrap_t: Wrap::ClampToEdge,
+            mipmaps: false,
+        }
+    }
+}
+
+impl TextureOptions {
+    /// Linear filtering with mipmaps, for art that gets scaled.
+    pub fn smooth() -> TextureOptions {
+        TextureOptions {
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            mipmaps: true,
+            ..TextureOptions::default()
+        }
+    }
+
+    fn gl_min_filter(&self) -> gl::types::GLenum {
+        match (self.min_filter, self.mipmaps) {
+            (Filter::Nearest, false) => gl::NEAREST,
+            (Filter::Linear, false)  => gl::LINEAR,
+            (Filter::Nearest, true)  => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Linear, true)   => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    fn gl_mag_filter(&self) -> gl::types::GLenum {
+        match self.mag_filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear  => gl::LINEAR,
+        }
+    }
+}
+
+/// Number of levels in a full mipmap chain for a texture of the given size.
+pub fn mip_levels(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// An RGBA8 OpenGL texture, deleted when dropped. Needs a current context.
+///
+/// Pixels are uploaded top row first, so texture coordinate (0, 0) is the top left of the
+/// image it was made from.
+#[derive(Debug)]
+pub struct Texture {
+    id: gl::types::GLuint,
+    width: u32,
+    height: u32,
+    options: TextureOptions,
+}
+
+impl Texture {
+    pub fn from_image(image: &Image, options: TextureOptions) -> Result<Texture, Error> {
+        let mut id = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                gl::TEXTURE_2D, 0, gl::RGBA8 as gl::types::GLint,
+                image.width() as i32, image.height() as i32, 0,
+                gl::RGBA, gl::UNSIGNED_BYTE, image.pixels().as_ptr() as *const _,
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        if id == 0 {
+            return Err(Error::Render(String::from("failed to create texture")));
+        }
+
+        let mut texture = Texture {
+            id,
+            width: image.width(),
+            height: image.height(),
+            options,
+        };
+        texture.set_options(options);
+
+        Ok(texture)
+    }
+
+    /// Uploads raw RGBA8 pixels, top row first.
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>, options: TextureOptions) -> Result<Texture, Error> {
+        Texture::from_image(&Image::from_rgba(width, height, pixels)?, options)
+    }
+
+    /// Decodes a PNG or BMP and uploads it.
+    pub fn from_bytes(bytes: &[u8], options: TextureOptions) -> Result<Texture, Error> {
+        Texture::from_image(&Image::decode(bytes)?, options)
+    }
+
+    /// Loads a PNG, BMP or TGA file and uploads it.
+    pub fn load<P: AsRef<Path>>(path: P, options: TextureOptions) -> Result<Texture, Error> {
+        Texture::from_image(&Image::load(path)?, options)
+    }
+
+    pub fn id(&self) -> gl::types::GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn options(&self) -> TextureOptions {
+        self.options
+    }
+
+    /// Changes filtering, wrapping and mipmapping. Turning mipmaps on generates them.
+    pub fn set_options(&mut self, options: TextureOptions) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, options.gl_min_filter() as gl::types::GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, options.gl_mag_filter() as gl::types::GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::types::GLenum::from(options.wrap_s) as gl::types::GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::types::GLenum::from(options.wrap_t) as gl::types::GLint);
+
+            if options.mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        self.options = options;
+    }
+
+    pub fn set_filter(&mut self, min: Filter, mag: Filter) {
+        self.set_options(TextureOptions { min_filter: min, mag_filter: mag, ..self.options });
+    }
+
+    pub fn set_wrap(&mut self, s: Wrap, t: Wrap) {
+        self.set_options(TextureOptions { wrap_s: s, wrap_t: t, ..self.options });
+    }
+
+    /// Rebuilds the mipmap chain from the base level, switching to mipmapped sampling if it
+    /// wasn't already.
+    pub fn generate_mipmaps(&mut self) {
+        self.set_options(TextureOptions { mipmaps: true, ..self.options });
+    }
+
+    /// Replaces the pixels under `image`, with its top left corner at `x`, `y`. Mipmaps are
+    /// regenerated if the texture has them.
+    pub fn update(&mut self, x: u32, y: u32, image: &Image) -> Result<(), Error> {
+        let inside = match (x.checked_add(image.width()), y.checked_add(image.height())) {
+            (Some(right), Some(bottom)) => right <= self.width && bottom <= self.height,
+            _ => false,
+        };
+
+        if !inside {
+            return Err(Error::Render(format!("{}x{} update at ({}, {}) is outside the texture", image.width(), image.height(), x, y)));
+        }
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D, 0, x as i32, y as i32,
+                image.width() as i32, image.height() as i32,
+                gl::RGBA, gl::UNSIGNED_BYTE, image.pixels().as_ptr() as *const _,
+            );
+
+            if self.options.mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Ok(())
+    }
+
+    /// Reads the base level back from the GPU.
+    pub fn read(&self) -> Image {
+        let mut image = Image::new(self.width, self.height);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::GetTexImage(gl::TEXTURE_2D, 0, gl::RGBA, gl::UNSIGNED_BYTE, image.pixels_mut().as_mut_ptr() as *mut _);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        image
+    }
+
+    /// Binds the texture to texture unit `unit`.
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteTextures(1, &self.id) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        let test = [
+            TextureOptions::default().gl_min_filter(),
+            TextureOptions::smooth().gl_min_filter(),
+            TextureOptions { mipmaps: true, ..TextureOptions::default() }.gl_min_filter(),
+            TextureOptions::smooth().gl_mag_filter(),
+        ];
+        let correct = [gl::NEAREST, gl::LINEAR_MIPMAP_LINEAR, gl::NEAREST_MIPMAP_NEAREST, gl::LINEAR];
+
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn levels() {
+        let test = [mip_levels(1, 1), mip_levels(256, 256), mip_levels(300, 20), mip_levels(0, 0)];
+        let correct = [1, 9, 9, 1];
+
+        assert_eq!(test, correct);
+    }
+}
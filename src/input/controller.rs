@@ -1,28 +1,100 @@
-#[derive(Copy, Clone)]
-pub enum Axis {
-    LeftX,
-    LeftY,
-    RightX,
-    RightY,
-    TriggerLeft,
-    TriggerRight,
+use serde::{ Deserialize, Serialize };
+
+named_enum! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum Axis {
+        LeftX,
+        LeftY,
+        RightX,
+        RightY,
+        TriggerLeft,
+        TriggerRight,
+    }
+}
+
+named_enum! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum Button {
+        A,
+        B,
+        X,
+        Y,
+        Back,
+        Guide,
+        Start,
+        LeftStick,
+        RightStick,
+        LeftShoulder,
+        RightShoulder,
+        DPadUp,
+        DPadDown,
+        DPadLeft,
+        DPadRight,
+    }
 }
 
-#[derive(Copy, Clone)]
-pub enum Button {
-    A,
-    B,
-    X,
-    Y,
-    Back,
-    Guide,
-    Start,
-    LeftStick,
-    RightStick,
-    LeftShoulder,
-    RightShoulder,
-    DPadUp,
-    DPadDown,
-    DPadLeft,
-    DPadRight,
+/// Dead zone used for sticks and axis bindings unless one is given.
+pub const DEFAULT_DEAD_ZONE: f32 = 0.2;
+
+/// Maps a raw axis value to `[-1, 1]`.
+pub fn normalise_axis(value: i16) -> f32 {
+    (value as f32 / i16::MAX as f32).max(-1.)
+}
+
+/// Zeroes values within `dead_zone` of the centre and rescales the rest so the output still
+/// covers the full range.
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        0.
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1. - dead_zone)
+    }
+}
+
+impl From<sdl2::controller::Axis> for Axis {
+    fn from(axis: sdl2::controller::Axis) -> Axis {
+        match axis {
+            sdl2::controller::Axis::LeftX        => Axis::LeftX,
+            sdl2::controller::Axis::LeftY        => Axis::LeftY,
+            sdl2::controller::Axis::RightX       => Axis::RightX,
+            sdl2::controller::Axis::RightY       => Axis::RightY,
+            sdl2::controller::Axis::TriggerLeft  => Axis::TriggerLeft,
+            sdl2::controller::Axis::TriggerRight => Axis::TriggerRight,
+        }
+    }
+}
+
+impl From<sdl2::controller::Button> for Button {
+    fn from(button: sdl2::controller::Button) -> Button {
+        match button {
+            sdl2::controller::Button::A             => Button::A,
+            sdl2::controller::Button::B             => Button::B,
+            sdl2::controller::Button::X             => Button::X,
+            sdl2::controller::Button::Y             => Button::Y,
+            sdl2::controller::Button::Back          => Button::Back,
+            sdl2::controller::Button::Guide         => Button::Guide,
+            sdl2::controller::Button::Start         => Button::Start,
+            sdl2::controller::Button::LeftStick     => Button::LeftStick,
+            sdl2::controller::Button::RightStick    => Button::RightStick,
+            sdl2::controller::Button::LeftShoulder  => Button::LeftShoulder,
+            sdl2::controller::Button::RightShoulder => Button::RightShoulder,
+            sdl2::controller::Button::DPadUp        => Button::DPadUp,
+            sdl2::controller::Button::DPadDown      => Button::DPadDown,
+            sdl2::controller::Button::DPadLeft      => Button::DPadLeft,
+            sdl2::controller::Button::DPadRight     => Button::DPadRight,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(Button::DPadUp.to_string(), "DPadUp");
+        assert_eq!("dpadup".parse(), Ok(Button::DPadUp));
+        assert_eq!("TriggerLeft".parse(), Ok(Axis::TriggerLeft));
+        assert!("Z".parse::<Button>().is_err());
+    }
 }
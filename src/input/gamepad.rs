@@ -0,0 +1,352 @@
+use std::path::Path;
+use std::time::Duration;
+use sdl2::GameControllerSubsystem;
+use sdl2::controller::GameController;
+use crate::Error;
+use crate::core::event::Event;
+use crate::input::controller::{ self, apply_dead_zone, normalise_axis, DEFAULT_DEAD_ZONE };
+
+const BUTTON_COUNT: usize = controller::Button::ALL.len();
+const AXIS_COUNT: usize = controller::Axis::ALL.len();
+
+/// How small stick deflections are filtered out.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DeadZone {
+    None,
+    /// Each axis of the stick is cut off on its own, which snaps diagonals towards the axes.
+    Axial(f32),
+    /// The stick's distance from the centre is cut off, keeping its direction intact.
+    Radial(f32),
+}
+
+impl DeadZone {
+    /// Applies the dead zone to a stick position in `[-1, 1]`, rescaling what's left
+    /// so the output still covers the full range.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        match self {
+            DeadZone::None => (x, y),
+            DeadZone::Axial(dead_zone) => (apply_dead_zone(x, *dead_zone), apply_dead_zone(y, *dead_zone)),
+            DeadZone::Radial(dead_zone) => {
+                let magnitude = (x * x + y * y).sqrt();
+
+                if magnitude <= *dead_zone {
+                    return (0., 0.);
+                }
+
+                let scale = apply_dead_zone(magnitude.min(1.), *dead_zone) / magnitude;
+
+                (x * scale, y * scale)
+            },
+        }
+    }
+}
+
+impl Default for DeadZone {
+    fn default() -> DeadZone {
+        DeadZone::Radial(DEFAULT_DEAD_ZONE)
+    }
+}
+
+/// A connected game controller occupying a player slot.
+pub struct Gamepad {
+    instance_id: u32,
+    controller: Option<GameController>,
+    axes: [i16; AXIS_COUNT],
+    buttons: [bool; BUTTON_COUNT],
+    stick_dead_zone: DeadZone,
+    trigger_dead_zone: f32,
+}
+
+impl Gamepad {
+    fn new(instance_id: u32, controller: Option<GameController>) -> Gamepad {
+        Gamepad {
+            instance_id,
+            controller,
+            axes: [0; AXIS_COUNT],
+            buttons: [false; BUTTON_COUNT],
+            stick_dead_zone: DeadZone::default(),
+            trigger_dead_zone: 0.,
+        }
+    }
+
+    pub fn instance_id(&self) -> u32 {
+        self.instance_id
+    }
+
+    pub fn name(&self) -> String {
+        self.controller.as_ref().map(GameController::name).unwrap_or_default()
+    }
+
+    pub fn is_button_held(&self, button: controller::Button) -> bool {
+        self.buttons[button as usize]
+    }
+
+    pub fn raw_axis(&self, axis: controller::Axis) -> i16 {
+        self.axes[axis as usize]
+    }
+
+    /// Axis value with dead zones applied, in `[-1, 1]` for sticks and `[0, 1]` for triggers.
+    pub fn axis(&self, axis: controller::Axis) -> f32 {
+        match axis {
+            controller::Axis::LeftX  => self.left_stick().0,
+            controller::Axis::LeftY  => self.left_stick().1,
+            controller::Axis::RightX => self.right_stick().0,
+            controller::Axis::RightY => self.right_stick().1,
+            controller::Axis::TriggerLeft | controller::Axis::TriggerRight => {
+                apply_dead_zone(normalise_axis(self.raw_axis(axis)).max(0.), self.trigger_dead_zone)
+            },
+        }
+    }
+
+    pub fn left_stick(&self) -> (f32, f32) {
+        self.stick(controller::Axis::LeftX, controller::Axis::LeftY)
+    }
+
+    pub fn right_stick(&self) -> (f32, f32) {
+        self.stick(controller::Axis::RightX, controller::Axis::RightY)
+    }
+
+    fn stick(&self, x: controller::Axis, y: controller::Axis) -> (f32, f32) {
+        self.stick_dead_zone.apply(normalise_axis(self.raw_axis(x)), normalise_axis(self.raw_axis(y)))
+    }
+
+    pub fn set_dead_zones(&mut self, stick: DeadZone, trigger: f32) {
+        self.stick_dead_zone = stick;
+        self.trigger_dead_zone = trigger;
+    }
+
+    /// Starts rumbling with motor strengths in `[0, 1]`, replacing any rumble already playing.
+    pub fn rumble(&mut self, low_frequency: f32, high_frequency: f32, duration: Duration) -> Result<(), Error> {
+        let controller = match self.controller.as_mut() {
+            Some(x) => x,
+            None => return Err(Error::Controller(String::from("gamepad is not open"))),
+        };
+
+        let strength = |x: f32| (x.clamp(0., 1.) * u16::MAX as f32) as u16;
+
+        controller
+            .set_rumble(strength(low_frequency), strength(high_frequency), duration.as_millis() as u32)
+            .map_err(|e| Error::Controller(e.to_string()))
+    }
+
+    pub fn stop_rumble(&mut self) -> Result<(), Error> {
+        self.rumble(0., 0., Duration::from_millis(0))
+    }
+}
+
+/// Opens game controllers as they're plugged in and keeps them in stable per-player slots.
+///
+/// `Game::run` attaches the SDL controller subsystem and feeds events to the manager returned
+/// from `Game::gamepads`. Without a subsystem, devices are tracked from events alone and can't
+/// rumble.
+pub struct Gamepads {
+    subsystem: Option<GameControllerSubsystem>,
+    slots: Vec<Option<Gamepad>>,
+    stick_dead_zone: DeadZone,
+    trigger_dead_zone: f32,
+}
+
+impl Gamepads {
+    pub fn new(max_players: usize) -> Gamepads {
+        Gamepads {
+            subsystem: None,
+            slots: (0..max_players).map(|_| None).collect(),
+            stick_dead_zone: DeadZone::default(),
+            trigger_dead_zone: 0.,
+        }
+    }
+
+    pub fn attach(&mut self, subsystem: GameControllerSubsystem) {
+        self.subsystem = Some(subsystem);
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.subsystem.is_some()
+    }
+
+    /// Sets the dead zones used by every current and future gamepad.
+    pub fn set_dead_zones(&mut self, stick: DeadZone, trigger: f32) {
+        self.stick_dead_zone = stick;
+        self.trigger_dead_zone = trigger;
+
+        for gamepad in self.slots.iter_mut().flatten() {
+            gamepad.set_dead_zones(stick, trigger);
+        }
+    }
+
+    /// Adds an SDL game controller mapping string, as found in `gamecontrollerdb.txt`.
+    pub fn add_mapping(&self, mapping: &str) -> Result<(), Error> {
+        match &self.subsystem {
+            Some(x) => x.add_mapping(mapping).map(|_| ()).map_err(|e| Error::Controller(e.to_string())),
+            None => Err(Error::Controller(String::from("controller subsystem is not attached"))),
+        }
+    }
+
+    /// Adds every mapping in a `gamecontrollerdb.txt` style file, returning how many were added.
+    pub fn load_mappings<P: AsRef<Path>>(&self, path: P) -> Result<usize, Error> {
+        let text = std::fs::read_to_string(path.as_ref()).map_err(|error| Error::AssetIo {
+            path: path.as_ref().to_path_buf(),
+            error,
+        })?;
+
+        let mut count = 0;
+
+        for line in text.lines().map(str::trim).filter(|x| !x.is_empty() && !x.starts_with('#')) {
+            self.add_mapping(line)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::ControllerDeviceAdded { which } => self.open(*which),
+            Event::ControllerDeviceRemoved { which } => {
+                if let Some(slot) = self.slot_of(*which) {
+                    self.slots[slot] = None;
+                }
+            },
+            Event::ControllerButtonDown { which, button } => {
+                if let Some(x) = self.by_instance_id(*which) {
+                    x.buttons[*button as usize] = true;
+                }
+            },
+            Event::ControllerButtonUp { which, button } => {
+                if let Some(x) = self.by_instance_id(*which) {
+                    x.buttons[*button as usize] = false;
+                }
+            },
+            Event::ControllerAxisMotion { which, axis, value } => {
+                if let Some(x) = self.by_instance_id(*which) {
+                    x.axes[*axis as usize] = *value;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// `which` is a device index for added devices, but an instance id for every other controller event.
+    fn open(&mut self, device_index: u32) {
+        let (instance_id, controller) = match &self.subsystem {
+            Some(subsystem) => match subsystem.open(device_index) {
+                Ok(x) => (x.instance_id(), Some(x)),
+                Err(_) => return,
+            },
+            None => (device_index, None),
+        };
+
+        if self.slot_of(instance_id).is_some() {
+            return;
+        }
+
+        if let Some(slot) = self.slots.iter_mut().find(|x| x.is_none()) {
+            let mut gamepad = Gamepad::new(instance_id, controller);
+            gamepad.set_dead_zones(self.stick_dead_zone, self.trigger_dead_zone);
+            *slot = Some(gamepad);
+        }
+    }
+
+    fn by_instance_id(&mut self, instance_id: u32) -> Option<&mut Gamepad> {
+        self.slots.iter_mut().flatten().find(|x| x.instance_id == instance_id)
+    }
+
+    /// Player slot the controller with `instance_id` occupies.
+    pub fn slot_of(&self, instance_id: u32) -> Option<usize> {
+        self.slots.iter().position(|x| x.as_ref().map(|x| x.instance_id) == Some(instance_id))
+    }
+
+    pub fn player(&self, slot: usize) -> Option<&Gamepad> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    pub fn player_mut(&mut self, slot: usize) -> Option<&mut Gamepad> {
+        self.slots.get_mut(slot).and_then(Option::as_mut)
+    }
+
+    pub fn connected(&self) -> impl Iterator<Item = (usize, &Gamepad)> {
+        self.slots.iter().enumerate().filter_map(|(i, x)| x.as_ref().map(|x| (i, x)))
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Gamepads {
+        Gamepads::new(4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-5 && (a.1 - b.1).abs() < 1e-5
+    }
+
+    #[test]
+    fn axial_dead_zone() {
+        let test = DeadZone::Axial(0.2).apply(0.1, 0.6);
+
+        assert!(close(test, (0., 0.5)));
+    }
+
+    #[test]
+    fn radial_dead_zone() {
+        assert_eq!(DeadZone::Radial(DEFAULT_DEAD_ZONE).apply(0.1, 0.1), (0., 0.));
+
+        let test = DeadZone::Radial(DEFAULT_DEAD_ZONE).apply(0.36, 0.48);
+
+        assert!(close(test, (0.3, 0.4)));
+    }
+
+    #[test]
+    fn slots() {
+        let mut gamepads = Gamepads::new(2);
+
+        gamepads.handle_event(&Event::ControllerDeviceAdded { which: 7 });
+        gamepads.handle_event(&Event::ControllerDeviceAdded { which: 8 });
+        gamepads.handle_event(&Event::ControllerDeviceAdded { which: 9 });
+
+        assert_eq!(gamepads.slot_of(7), Some(0));
+        assert_eq!(gamepads.slot_of(8), Some(1));
+        assert_eq!(gamepads.slot_of(9), None);
+
+        gamepads.handle_event(&Event::ControllerDeviceRemoved { which: 7 });
+        gamepads.handle_event(&Event::ControllerDeviceAdded { which: 10 });
+
+        assert_eq!(gamepads.slot_of(8), Some(1));
+        assert_eq!(gamepads.slot_of(10), Some(0));
+        assert_eq!(gamepads.connected().count(), 2);
+    }
+
+    #[test]
+    fn axes_and_buttons() {
+        let mut gamepads = Gamepads::new(1);
+        gamepads.set_dead_zones(DeadZone::Radial(0.5), 0.5);
+
+        gamepads.handle_event(&Event::ControllerDeviceAdded { which: 0 });
+        gamepads.handle_event(&Event::ControllerAxisMotion { which: 0, axis: controller::Axis::LeftX, value: i16::MIN });
+        gamepads.handle_event(&Event::ControllerAxisMotion { which: 0, axis: controller::Axis::RightY, value: 8000 });
+        gamepads.handle_event(&Event::ControllerAxisMotion { which: 0, axis: controller::Axis::TriggerLeft, value: i16::MAX });
+        gamepads.handle_event(&Event::ControllerAxisMotion { which: 0, axis: controller::Axis::TriggerRight, value: 8000 });
+        gamepads.handle_event(&Event::ControllerButtonDown { which: 0, button: controller::Button::Start });
+
+        let gamepad = gamepads.player(0).unwrap();
+
+        assert_eq!(gamepad.left_stick(), (-1., 0.));
+        assert_eq!(gamepad.axis(controller::Axis::RightY), 0.);
+        assert_eq!(gamepad.axis(controller::Axis::TriggerLeft), 1.);
+        assert_eq!(gamepad.axis(controller::Axis::TriggerRight), 0.);
+        assert_eq!(gamepad.raw_axis(controller::Axis::RightY), 8000);
+        assert!(gamepad.is_button_held(controller::Button::Start));
+    }
+
+    #[test]
+    fn detached() {
+        let mut gamepads = Gamepads::default();
+        gamepads.handle_event(&Event::ControllerDeviceAdded { which: 0 });
+
+        assert!(gamepads.add_mapping("").is_err());
+        assert!(gamepads.player_mut(0).unwrap().rumble(1., 1., Duration::from_millis(100)).is_err());
+    }
+}
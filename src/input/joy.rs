@@ -1,12 +1,33 @@
-#[derive(Copy, Clone)]
-pub enum HatState {
-    Centered,
-    Up,
-    Right,
-    Down,
-    Left,
-    RightUp,
-    RightDown,
-    LeftUp,
-    LeftDown,
+use sdl2::joystick;
+use serde::{ Deserialize, Serialize };
+
+named_enum! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum HatState {
+        Centered,
+        Up,
+        Right,
+        Down,
+        Left,
+        RightUp,
+        RightDown,
+        LeftUp,
+        LeftDown,
+    }
+}
+
+impl From<joystick::HatState> for HatState {
+    fn from(state: joystick::HatState) -> HatState {
+        match state {
+            joystick::HatState::Centered  => HatState::Centered,
+            joystick::HatState::Up        => HatState::Up,
+            joystick::HatState::Right     => HatState::Right,
+            joystick::HatState::Down      => HatState::Down,
+            joystick::HatState::Left      => HatState::Left,
+            joystick::HatState::RightUp   => HatState::RightUp,
+            joystick::HatState::RightDown => HatState::RightDown,
+            joystick::HatState::LeftUp    => HatState::LeftUp,
+            joystick::HatState::LeftDown  => HatState::LeftDown,
+        }
+    }
 }
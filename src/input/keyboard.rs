@@ -1,500 +1,1190 @@
-#[derive(Copy, Clone)]
-pub enum KeyCode {
-    Backspace,
-    Tab,
-    Return,
-    Escape,
-    Space,
-    Exclaim,
-    Quotedbl,
-    Hash,
-    Dollar,
-    Percent,
-    Ampersand,
-    Quote,
-    LeftParenthesis,
-    RightParenthesis,
-    Asterisk,
-    Plus,
-    Comma,
-    Minus,
-    Period,
-    Slash,
-    Number0,
-    Number1,
-    Number2,
-    Number3,
-    Number4,
-    Number5,
-    Number6,
-    Number7,
-    Number8,
-    Number9,
-    Colon,
-    Semicolon,
-    Less,
-    Equals,
-    Greater,
-    Question,
-    At,
-    LeftBracket,
-    Backslash,
-    RightBracket,
-    Caret,
-    Underscore,
-    Backquote,
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
-    G,
-    H,
-    I,
-    J,
-    K,
-    L,
-    M,
-    N,
-    O,
-    P,
-    Q,
-    R,
-    S,
-    T,
-    U,
-    V,
-    W,
-    X,
-    Y,
-    Z,
-    Delete,
-    CapsLock,
-    F1,
-    F2,
-    F3,
-    F4,
-    F5,
-    F6,
-    F7,
-    F8,
-    F9,
-    F10,
-    F11,
-    F12,
-    PrintScreen,
-    ScrollLock,
-    Pause,
-    Insert,
-    Home,
-    PageUp,
-    End,
-    PageDown,
-    Right,
-    Left,
-    Down,
-    Up,
-    NumLockClear,
-    KeypadDivide,
-    KeypadMultiply,
-    KeypadMinus,
-    KeypadPlus,
-    KeypadEnter,
-    Keypad1,
-    Keypad2,
-    Keypad3,
-    Keypad4,
-    Keypad5,
-    Keypad6,
-    Keypad7,
-    Keypad8,
-    Keypad9,
-    Keypad0,
-    KeypadPeriod,
-    Application,
-    Power,
-    KeypadEquals,
-    F13,
-    F14,
-    F15,
-    F16,
-    F17,
-    F18,
-    F19,
-    F20,
-    F21,
-    F22,
-    F23,
-    F24,
-    Execute,
-    Help,
-    Menu,
-    Select,
-    Stop,
-    Again,
-    Undo,
-    Cut,
-    Copy,
-    Paste,
-    Find,
-    Mute,
-    VolumeUp,
-    VolumeDown,
-    KeypadComma,
-    KeypadEqualsAS400,
-    AltErase,
-    Sysreq,
-    Cancel,
-    Clear,
-    Prior,
-    Return2,
-    Separator,
-    Out,
-    Oper,
-    ClearAgain,
-    CrSel,
-    ExSel,
-    Keypad00,
-    Keypad000,
-    ThousandsSeparator,
-    DecimalSeparator,
-    CurrencyUnit,
-    CurrencySubUnit,
-    KeypadLeftParenthesis,
-    KeypadRightParenthesis,
-    KeypadLeftBrace,
-    KeypadRightBrace,
-    KeypadTab,
-    KeypadBackspace,
-    KeypadA,
-    KeypadB,
-    KeypadC,
-    KeypadD,
-    KeypadE,
-    KeypadF,
-    KeypadXor,
-    KeypadPower,
-    KeypadPercent,
-    KeypadLess,
-    KeypadGreater,
-    KeypadAmpersand,
-    KeypadDblAmpersand,
-    KeypadVerticalBar,
-    KeypadDblVerticalBar,
-    KeypadColon,
-    KeypadHash,
-    KeypadSpace,
-    KeypadAt,
-    KeypadExclam,
-    KeypadMemStore,
-    KeypadMemRecall,
-    KeypadMemClear,
-    KeypadMemAdd,
-    KeypadMemSubtract,
-    KeypadMemMultiply,
-    KeypadMemDivide,
-    KeypadPlusMinus,
-    KeypadClear,
-    KeypadClearEntry,
-    KeypadBinary,
-    KeypadOctal,
-    KeypadDecimal,
-    KeypadHexadecimal,
-    LControl,
-    LShift,
-    LAlt,
-    LGui,
-    RControl,
-    RShift,
-    RAlt,
-    RGui,
-    Mode,
-    AudioNext,
-    AudioPrev,
-    AudioStop,
-    AudioPlay,
-    AudioMute,
-    MediaSelect,
-    Www,
-    Mail,
-    Calculator,
-    Computer,
-    AcSearch,
-    AcHome,
-    AcBack,
-    AcForward,
-    AcStop,
-    AcRefresh,
-    AcBookmarks,
-    BrightnessDown,
-    BrightnessUp,
-    DisplaySwitch,
-    KeyboardIlluminationToggle,
-    KeyboardIlluminationDown,
-    KeyboardIlluminationUp,
-    Eject,
-    Sleep,
+use std::fmt;
+use std::ops::{ BitAnd, BitOr, BitOrAssign, Not };
+use std::str::FromStr;
+use sdl2::keyboard::{ Keycode, Scancode };
+use crate::input::ParseNameError;
+use serde::{ Deserialize, Serialize };
+
+named_enum! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum KeyCode {
+        Backspace,
+        Tab,
+        Return,
+        Escape,
+        Space,
+        Exclaim,
+        Quotedbl,
+        Hash,
+        Dollar,
+        Percent,
+        Ampersand,
+        Quote,
+        LeftParenthesis,
+        RightParenthesis,
+        Asterisk,
+        Plus,
+        Comma,
+        Minus,
+        Period,
+        Slash,
+        Number0,
+        Number1,
+        Number2,
+        Number3,
+        Number4,
+        Number5,
+        Number6,
+        Number7,
+        Number8,
+        Number9,
+        Colon,
+        Semicolon,
+        Less,
+        Equals,
+        Greater,
+        Question,
+        At,
+        LeftBracket,
+        Backslash,
+        RightBracket,
+        Caret,
+        Underscore,
+        Backquote,
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
+        Delete,
+        CapsLock,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        PrintScreen,
+        ScrollLock,
+        Pause,
+        Insert,
+        Home,
+        PageUp,
+        End,
+        PageDown,
+        Right,
+        Left,
+        Down,
+        Up,
+        NumLockClear,
+        KeypadDivide,
+        KeypadMultiply,
+        KeypadMinus,
+        KeypadPlus,
+        KeypadEnter,
+        Keypad1,
+        Keypad2,
+        Keypad3,
+        Keypad4,
+        Keypad5,
+        Keypad6,
+        Keypad7,
+        Keypad8,
+        Keypad9,
+        Keypad0,
+        KeypadPeriod,
+        Application,
+        Power,
+        KeypadEquals,
+        F13,
+        F14,
+        F15,
+        F16,
+        F17,
+        F18,
+        F19,
+        F20,
+        F21,
+        F22,
+        F23,
+        F24,
+        Execute,
+        Help,
+        Menu,
+        Select,
+        Stop,
+        Again,
+        Undo,
+        Cut,
+        Copy,
+        Paste,
+        Find,
+        Mute,
+        VolumeUp,
+        VolumeDown,
+        KeypadComma,
+        KeypadEqualsAS400,
+        AltErase,
+        Sysreq,
+        Cancel,
+        Clear,
+        Prior,
+        Return2,
+        Separator,
+        Out,
+        Oper,
+        ClearAgain,
+        CrSel,
+        ExSel,
+        Keypad00,
+        Keypad000,
+        ThousandsSeparator,
+        DecimalSeparator,
+        CurrencyUnit,
+        CurrencySubUnit,
+        KeypadLeftParenthesis,
+        KeypadRightParenthesis,
+        KeypadLeftBrace,
+        KeypadRightBrace,
+        KeypadTab,
+        KeypadBackspace,
+        KeypadA,
+        KeypadB,
+        KeypadC,
+        KeypadD,
+        KeypadE,
+        KeypadF,
+        KeypadXor,
+        KeypadPower,
+        KeypadPercent,
+        KeypadLess,
+        KeypadGreater,
+        KeypadAmpersand,
+        KeypadDblAmpersand,
+        KeypadVerticalBar,
+        KeypadDblVerticalBar,
+        KeypadColon,
+        KeypadHash,
+        KeypadSpace,
+        KeypadAt,
+        KeypadExclam,
+        KeypadMemStore,
+        KeypadMemRecall,
+        KeypadMemClear,
+        KeypadMemAdd,
+        KeypadMemSubtract,
+        KeypadMemMultiply,
+        KeypadMemDivide,
+        KeypadPlusMinus,
+        KeypadClear,
+        KeypadClearEntry,
+        KeypadBinary,
+        KeypadOctal,
+        KeypadDecimal,
+        KeypadHexadecimal,
+        LControl,
+        LShift,
+        LAlt,
+        LGui,
+        RControl,
+        RShift,
+        RAlt,
+        RGui,
+        Mode,
+        AudioNext,
+        AudioPrev,
+        AudioStop,
+        AudioPlay,
+        AudioMute,
+        MediaSelect,
+        Www,
+        Mail,
+        Calculator,
+        Computer,
+        AcSearch,
+        AcHome,
+        AcBack,
+        AcForward,
+        AcStop,
+        AcRefresh,
+        AcBookmarks,
+        BrightnessDown,
+        BrightnessUp,
+        DisplaySwitch,
+        KeyboardIlluminationToggle,
+        KeyboardIlluminationDown,
+        KeyboardIlluminationUp,
+        Eject,
+        Sleep,
+    }
+}
+
+named_enum! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum ScanCode {
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
+        Number1,
+        Number2,
+        Number3,
+        Number4,
+        Number5,
+        Number6,
+        Number7,
+        Number8,
+        Number9,
+        Number0,
+        Return,
+        Escape,
+        Backspace,
+        Tab,
+        Space,
+        Minus,
+        Equals,
+        LeftBracket,
+        RightBracket,
+        Backslash,
+        NonUsHash,
+        Semicolon,
+        Apostrophe,
+        Grave,
+        Comma,
+        Period,
+        Slash,
+        CapsLock,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        PrintScreen,
+        ScrollLock,
+        Pause,
+        Insert,
+        Home,
+        PageUp,
+        Delete,
+        End,
+        PageDown,
+        Right,
+        Left,
+        Down,
+        Up,
+        NumLockClear,
+        KeypadDivide,
+        KeypadMultiply,
+        KeypadMinus,
+        KeypadPlus,
+        KeypadEnter,
+        Keypad1,
+        Keypad2,
+        Keypad3,
+        Keypad4,
+        Keypad5,
+        Keypad6,
+        Keypad7,
+        Keypad8,
+        Keypad9,
+        Keypad0,
+        KeypadPeriod,
+        NonUsBackslash,
+        Application,
+        Power,
+        KeypadEquals,
+        F13,
+        F14,
+        F15,
+        F16,
+        F17,
+        F18,
+        F19,
+        F20,
+        F21,
+        F22,
+        F23,
+        F24,
+        Execute,
+        Help,
+        Menu,
+        Select,
+        Stop,
+        Again,
+        Undo,
+        Cut,
+        Copy,
+        Paste,
+        Find,
+        Mute,
+        VolumeUp,
+        VolumeDown,
+        KeypadComma,
+        KeypadEqualsAS400,
+        International1,
+        International2,
+        International3,
+        International4,
+        International5,
+        International6,
+        International7,
+        International8,
+        International9,
+        Language1,
+        Language2,
+        Language3,
+        Language4,
+        Language5,
+        Language6,
+        Language7,
+        Language8,
+        Language9,
+        AltErase,
+        SysReq,
+        Cancel,
+        Clear,
+        Prior,
+        Return2,
+        Separator,
+        Out,
+        Oper,
+        ClearAgain,
+        CrSel,
+        ExSel,
+        Keypad00,
+        Keypad000,
+        ThousandsSeparator,
+        DecimalSeparator,
+        CurrencyUnit,
+        CurrencySubUnit,
+        KeypadLeftParen,
+        KeypadRightParen,
+        KeypadLeftBrace,
+        KeypadRightBrace,
+        KeypadTab,
+        KeypadBackspace,
+        KeypadA,
+        KeypadB,
+        KeypadC,
+        KeypadD,
+        KeypadE,
+        KeypadF,
+        KeypadXor,
+        KeypadPower,
+        KeypadPercent,
+        KeypadLess,
+        KeypadGreater,
+        KeypadAmpersand,
+        KeypadDblAmpersand,
+        KeypadVerticalBar,
+        KeypadDblVerticalBar,
+        KeypadColon,
+        KeypadHash,
+        KeypadSpace,
+        KeypadAt,
+        KeypadExclam,
+        KeypadMemStore,
+        KeypadMemRecall,
+        KeypadMemClear,
+        KeypadMemAdd,
+        KeypadMemSubtract,
+        KeypadMemMultiply,
+        KeypadMemDivide,
+        KeypadPlusMinus,
+        KeypadClear,
+        KeypadClearEntry,
+        KeypadBinary,
+        KeypadOctal,
+        KeypadDecimal,
+        KeypadHexadecimal,
+        LControl,
+        LShift,
+        LAlt,
+        LGui,
+        RControl,
+        RShift,
+        RAlt,
+        RGui,
+        Mode,
+        AudioNext,
+        AudioPrev,
+        AudioStop,
+        AudioPlay,
+        AudioMute,
+        MediaSelect,
+        Www,
+        Mail,
+        Calculator,
+        Computer,
+        AcSearch,
+        AcHome,
+        AcBack,
+        AcForward,
+        AcStop,
+        AcRefresh,
+        AcBookmarks,
+        BrightnessDown,
+        BrightnessUp,
+        DisplaySwitch,
+        KeyboardIlluminationToggle,
+        KeyboardIlluminationDown,
+        KeyboardIlluminationUp,
+        Eject,
+        Sleep,
+        App1,
+        App2,
+        Num,
+    }
+}
+
+/// Set of held modifier keys. Bit values match SDL's.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Mod(u16);
+
+impl Mod {
+    pub const NONE: Mod = Mod(0x0000);
+    pub const LSHIFT: Mod = Mod(0x0001);
+    pub const RSHIFT: Mod = Mod(0x0002);
+    pub const LCONTROL: Mod = Mod(0x0040);
+    pub const RCONTROL: Mod = Mod(0x0080);
+    pub const LALT: Mod = Mod(0x0100);
+    pub const RALT: Mod = Mod(0x0200);
+    pub const LGUI: Mod = Mod(0x0400);
+    pub const RGUI: Mod = Mod(0x0800);
+    pub const NUM: Mod = Mod(0x1000);
+    pub const CAPS: Mod = Mod(0x2000);
+    pub const MODE: Mod = Mod(0x4000);
+    pub const RESERVED: Mod = Mod(0x8000);
+
+    pub const SHIFT: Mod = Mod(Mod::LSHIFT.0 | Mod::RSHIFT.0);
+    pub const CONTROL: Mod = Mod(Mod::LCONTROL.0 | Mod::RCONTROL.0);
+    pub const ALT: Mod = Mod(Mod::LALT.0 | Mod::RALT.0);
+    pub const GUI: Mod = Mod(Mod::LGUI.0 | Mod::RGUI.0);
+
+    const NAMES: [(Mod, &'static str); 12] = [
+        (Mod::LSHIFT, "LShift"),
+        (Mod::RSHIFT, "RShift"),
+        (Mod::LCONTROL, "LControl"),
+        (Mod::RCONTROL, "RControl"),
+        (Mod::LALT, "LAlt"),
+        (Mod::RALT, "RAlt"),
+        (Mod::LGUI, "LGui"),
+        (Mod::RGUI, "RGui"),
+        (Mod::NUM, "Num"),
+        (Mod::CAPS, "Caps"),
+        (Mod::MODE, "Mode"),
+        (Mod::RESERVED, "Reserved"),
+    ];
+
+    pub fn from_bits(bits: u16) -> Mod {
+        Mod(bits)
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether every modifier in `other` is held.
+    pub fn contains(&self, other: Mod) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether any modifier in `other` is held, e.g. `key_mod.intersects(Mod::SHIFT)` for either shift.
+    pub fn intersects(&self, other: Mod) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn insert(&mut self, other: Mod) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Mod) {
+        self.0 &= !other.0;
+    }
 }
 
-#[derive(Copy, Clone)]
-pub enum ScanCode {
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
-    G,
-    H,
-    I,
-    J,
-    K,
-    L,
-    M,
-    N,
-    O,
-    P,
-    Q,
-    R,
-    S,
-    T,
-    U,
-    V,
-    W,
-    X,
-    Y,
-    Z,
-    Number1,
-    Number2,
-    Number3,
-    Number4,
-    Number5,
-    Number6,
-    Number7,
-    Number8,
-    Number9,
-    Number0,
-    Return,
-    Escape,
-    Backspace,
-    Tab,
-    Space,
-    Minus,
-    Equals,
-    LeftBracket,
-    RightBracket,
-    Backslash,
-    NonUsHash,
-    Semicolon,
-    Apostrophe,
-    Grave,
-    Comma,
-    Period,
-    Slash,
-    CapsLock,
-    F1,
-    F2,
-    F3,
-    F4,
-    F5,
-    F6,
-    F7,
-    F8,
-    F9,
-    F10,
-    F11,
-    F12,
-    PrintScreen,
-    ScrollLock,
-    Pause,
-    Insert,
-    Home,
-    PageUp,
-    Delete,
-    End,
-    PageDown,
-    Right,
-    Left,
-    Down,
-    Up,
-    NumLockClear,
-    KeypadDivide,
-    KeypadMultiply,
-    KeypadMinus,
-    KeypadPlus,
-    KeypadEnter,
-    Keypad1,
-    Keypad2,
-    Keypad3,
-    Keypad4,
-    Keypad5,
-    Keypad6,
-    Keypad7,
-    Keypad8,
-    Keypad9,
-    Keypad0,
-    KeypadPeriod,
-    NonUsBackslash,
-    Application,
-    Power,
-    KeypadEquals,
-    F13,
-    F14,
-    F15,
-    F16,
-    F17,
-    F18,
-    F19,
-    F20,
-    F21,
-    F22,
-    F23,
-    F24,
-    Execute,
-    Help,
-    Menu,
-    Select,
-    Stop,
-    Again,
-    Undo,
-    Cut,
-    Copy,
-    Paste,
-    Find,
-    Mute,
-    VolumeUp,
-    VolumeDown,
-    KeypadComma,
-    KeypadEqualsAS400,
-    International1,
-    International2,
-    International3,
-    International4,
-    International5,
-    International6,
-    International7,
-    International8,
-    International9,
-    Language1,
-    Language2,
-    Language3,
-    Language4,
-    Language5,
-    Language6,
-    Language7,
-    Language8,
-    Language9,
-    AltErase,
-    SysReq,
-    Cancel,
-    Clear,
-    Prior,
-    Return2,
-    Separator,
-    Out,
-    Oper,
-    ClearAgain,
-    CrSel,
-    ExSel,
-    Keypad00,
-    Keypad000,
-    ThousandsSeparator,
-    DecimalSeparator,
-    CurrencyUnit,
-    CurrencySubUnit,
-    KeypadLeftParen,
-    KeypadRightParen,
-    KeypadLeftBrace,
-    KeypadRightBrace,
-    KeypadTab,
-    KeypadBackspace,
-    KeypadA,
-    KeypadB,
-    KeypadC,
-    KeypadD,
-    KeypadE,
-    KeypadF,
-    KeypadXor,
-    KeypadPower,
-    KeypadPercent,
-    KeypadLess,
-    KeypadGreater,
-    KeypadAmpersand,
-    KeypadDblAmpersand,
-    KeypadVerticalBar,
-    KeypadDblVerticalBar,
-    KeypadColon,
-    KeypadHash,
-    KeypadSpace,
-    KeypadAt,
-    KeypadExclam,
-    KeypadMemStore,
-    KeypadMemRecall,
-    KeypadMemClear,
-    KeypadMemAdd,
-    KeypadMemSubtract,
-    KeypadMemMultiply,
-    KeypadMemDivide,
-    KeypadPlusMinus,
-    KeypadClear,
-    KeypadClearEntry,
-    KeypadBinary,
-    KeypadOctal,
-    KeypadDecimal,
-    KeypadHexadecimal,
-    LControl,
-    LShift,
-    LAlt,
-    LGui,
-    RControl,
-    RShift,
-    RAlt,
-    RGui,
-    Mode,
-    AudioNext,
-    AudioPrev,
-    AudioStop,
-    AudioPlay,
-    AudioMute,
-    MediaSelect,
-    Www,
-    Mail,
-    Calculator,
-    Computer,
-    AcSearch,
-    AcHome,
-    AcBack,
-    AcForward,
-    AcStop,
-    AcRefresh,
-    AcBookmarks,
-    BrightnessDown,
-    BrightnessUp,
-    DisplaySwitch,
-    KeyboardIlluminationToggle,
-    KeyboardIlluminationDown,
-    KeyboardIlluminationUp,
-    Eject,
-    Sleep,
-    App1,
-    App2,
-    Num,
+impl BitOr for Mod {
+    type Output = Mod;
+
+    fn bitor(self, rhs: Mod) -> Mod {
+        Mod(self.0 | rhs.0)
+    }
 }
 
-#[derive(Copy, Clone)]
-pub enum Mod {
-    NoMod,
-    LShift,
-    RShift,
-    LControl,
-    RControl,
-    LAlt,
-    RAlt,
-    LGui,
-    RGui,
-    Num,
-    Caps,
-    Mode,
-    Reserved,
+impl BitOrAssign for Mod {
+    fn bitor_assign(&mut self, rhs: Mod) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Mod {
+    type Output = Mod;
+
+    fn bitand(self, rhs: Mod) -> Mod {
+        Mod(self.0 & rhs.0)
+    }
+}
+
+impl Not for Mod {
+    type Output = Mod;
+
+    fn not(self) -> Mod {
+        Mod(!self.0)
+    }
+}
+
+/// Writes held modifiers separated by `|`, or `NoMod` when none are held.
+impl fmt::Display for Mod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("NoMod");
+        }
+
+        let names: Vec<&str> = Mod::NAMES.iter().filter(|(x, _)| self.contains(*x)).map(|(_, name)| *name).collect();
+
+        f.write_str(&names.join("|"))
+    }
+}
+
+impl FromStr for Mod {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Mod, ParseNameError> {
+        let mut result = Mod::NONE;
+
+        for name in s.split(&['|', '+'][..]).map(str::trim) {
+            if name.eq_ignore_ascii_case("NoMod") {
+                continue;
+            }
+
+            match Mod::NAMES.iter().find(|(_, x)| x.eq_ignore_ascii_case(name)) {
+                Some((x, _)) => result |= *x,
+                None => return Err(ParseNameError::new("Mod", name)),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl From<Keycode> for KeyCode {
+    fn from(code: Keycode) -> KeyCode {
+        match code {
+            Keycode::Backspace          => KeyCode::Backspace,
+            Keycode::Tab                => KeyCode::Tab,
+            Keycode::Return             => KeyCode::Return,
+            Keycode::Escape             => KeyCode::Escape,
+            Keycode::Space              => KeyCode::Space,
+            Keycode::Exclaim            => KeyCode::Exclaim,
+            Keycode::Quotedbl           => KeyCode::Quotedbl,
+            Keycode::Hash               => KeyCode::Hash,
+            Keycode::Dollar             => KeyCode::Dollar,
+            Keycode::Percent            => KeyCode::Percent,
+            Keycode::Ampersand          => KeyCode::Ampersand,
+            Keycode::Quote              => KeyCode::Quote,
+            Keycode::LeftParen          => KeyCode::LeftParenthesis,
+            Keycode::RightParen         => KeyCode::RightParenthesis,
+            Keycode::Asterisk           => KeyCode::Asterisk,
+            Keycode::Plus               => KeyCode::Plus,
+            Keycode::Comma              => KeyCode::Comma,
+            Keycode::Minus              => KeyCode::Minus,
+            Keycode::Period             => KeyCode::Period,
+            Keycode::Slash              => KeyCode::Slash,
+            Keycode::Num0               => KeyCode::Number0,
+            Keycode::Num1               => KeyCode::Number1,
+            Keycode::Num2               => KeyCode::Number2,
+            Keycode::Num3               => KeyCode::Number3,
+            Keycode::Num4               => KeyCode::Number4,
+            Keycode::Num5               => KeyCode::Number5,
+            Keycode::Num6               => KeyCode::Number6,
+            Keycode::Num7               => KeyCode::Number7,
+            Keycode::Num8               => KeyCode::Number8,
+            Keycode::Num9               => KeyCode::Number9,
+            Keycode::Colon              => KeyCode::Colon,
+            Keycode::Semicolon          => KeyCode::Semicolon,
+            Keycode::Less               => KeyCode::Less,
+            Keycode::Equals             => KeyCode::Equals,
+            Keycode::Greater            => KeyCode::Greater,
+            Keycode::Question           => KeyCode::Question,
+            Keycode::At                 => KeyCode::At,
+            Keycode::LeftBracket        => KeyCode::LeftBracket,
+            Keycode::Backslash          => KeyCode::Backslash,
+            Keycode::RightBracket       => KeyCode::RightBracket,
+            Keycode::Caret              => KeyCode::Caret,
+            Keycode::Underscore         => KeyCode::Underscore,
+            Keycode::Backquote          => KeyCode::Backquote,
+            Keycode::A                  => KeyCode::A,
+            Keycode::B                  => KeyCode::B,
+            Keycode::C                  => KeyCode::C,
+            Keycode::D                  => KeyCode::D,
+            Keycode::E                  => KeyCode::E,
+            Keycode::F                  => KeyCode::F,
+            Keycode::G                  => KeyCode::G,
+            Keycode::H                  => KeyCode::H,
+            Keycode::I                  => KeyCode::I,
+            Keycode::J                  => KeyCode::J,
+            Keycode::K                  => KeyCode::K,
+            Keycode::L                  => KeyCode::L,
+            Keycode::M                  => KeyCode::M,
+            Keycode::N                  => KeyCode::N,
+            Keycode::O                  => KeyCode::O,
+            Keycode::P                  => KeyCode::P,
+            Keycode::Q                  => KeyCode::Q,
+            Keycode::R                  => KeyCode::R,
+            Keycode::S                  => KeyCode::S,
+            Keycode::T                  => KeyCode::T,
+            Keycode::U                  => KeyCode::U,
+            Keycode::V                  => KeyCode::V,
+            Keycode::W                  => KeyCode::W,
+            Keycode::X                  => KeyCode::X,
+            Keycode::Y                  => KeyCode::Y,
+            Keycode::Z                  => KeyCode::Z,
+            Keycode::Delete             => KeyCode::Delete,
+            Keycode::CapsLock           => KeyCode::CapsLock,
+            Keycode::F1                 => KeyCode::F1,
+            Keycode::F2                 => KeyCode::F2,
+            Keycode::F3                 => KeyCode::F3,
+            Keycode::F4                 => KeyCode::F4,
+            Keycode::F5                 => KeyCode::F5,
+            Keycode::F6                 => KeyCode::F6,
+            Keycode::F7                 => KeyCode::F7,
+            Keycode::F8                 => KeyCode::F8,
+            Keycode::F9                 => KeyCode::F9,
+            Keycode::F10                => KeyCode::F10,
+            Keycode::F11                => KeyCode::F11,
+            Keycode::F12                => KeyCode::F12,
+            Keycode::PrintScreen        => KeyCode::PrintScreen,
+            Keycode::ScrollLock         => KeyCode::ScrollLock,
+            Keycode::Pause              => KeyCode::Pause,
+            Keycode::Insert             => KeyCode::Insert,
+            Keycode::Home               => KeyCode::Home,
+            Keycode::PageUp             => KeyCode::PageUp,
+            Keycode::End                => KeyCode::End,
+            Keycode::PageDown           => KeyCode::PageDown,
+            Keycode::Right              => KeyCode::Right,
+            Keycode::Left               => KeyCode::Left,
+            Keycode::Down               => KeyCode::Down,
+            Keycode::Up                 => KeyCode::Up,
+            Keycode::NumLockClear       => KeyCode::NumLockClear,
+            Keycode::KpDivide           => KeyCode::KeypadDivide,
+            Keycode::KpMultiply         => KeyCode::KeypadMultiply,
+            Keycode::KpMinus            => KeyCode::KeypadMinus,
+            Keycode::KpPlus             => KeyCode::KeypadPlus,
+            Keycode::KpEnter            => KeyCode::KeypadEnter,
+            Keycode::Kp1                => KeyCode::Keypad1,
+            Keycode::Kp2                => KeyCode::Keypad2,
+            Keycode::Kp3                => KeyCode::Keypad3,
+            Keycode::Kp4                => KeyCode::Keypad4,
+            Keycode::Kp5                => KeyCode::Keypad5,
+            Keycode::Kp6                => KeyCode::Keypad6,
+            Keycode::Kp7                => KeyCode::Keypad7,
+            Keycode::Kp8                => KeyCode::Keypad8,
+            Keycode::Kp9                => KeyCode::Keypad9,
+            Keycode::Kp0                => KeyCode::Keypad0,
+            Keycode::KpPeriod           => KeyCode::KeypadPeriod,
+            Keycode::Application        => KeyCode::Application,
+            Keycode::Power              => KeyCode::Power,
+            Keycode::KpEquals           => KeyCode::KeypadEquals,
+            Keycode::F13                => KeyCode::F13,
+            Keycode::F14                => KeyCode::F14,
+            Keycode::F15                => KeyCode::F15,
+            Keycode::F16                => KeyCode::F16,
+            Keycode::F17                => KeyCode::F17,
+            Keycode::F18                => KeyCode::F18,
+            Keycode::F19                => KeyCode::F19,
+            Keycode::F20                => KeyCode::F20,
+            Keycode::F21                => KeyCode::F21,
+            Keycode::F22                => KeyCode::F22,
+            Keycode::F23                => KeyCode::F23,
+            Keycode::F24                => KeyCode::F24,
+            Keycode::Execute            => KeyCode::Execute,
+            Keycode::Help               => KeyCode::Help,
+            Keycode::Menu               => KeyCode::Menu,
+            Keycode::Select             => KeyCode::Select,
+            Keycode::Stop               => KeyCode::Stop,
+            Keycode::Again              => KeyCode::Again,
+            Keycode::Undo               => KeyCode::Undo,
+            Keycode::Cut                => KeyCode::Cut,
+            Keycode::Copy               => KeyCode::Copy,
+            Keycode::Paste              => KeyCode::Paste,
+            Keycode::Find               => KeyCode::Find,
+            Keycode::Mute               => KeyCode::Mute,
+            Keycode::VolumeUp           => KeyCode::VolumeUp,
+            Keycode::VolumeDown         => KeyCode::VolumeDown,
+            Keycode::KpComma            => KeyCode::KeypadComma,
+            Keycode::KpEqualsAS400      => KeyCode::KeypadEqualsAS400,
+            Keycode::AltErase           => KeyCode::AltErase,
+            Keycode::Sysreq             => KeyCode::Sysreq,
+            Keycode::Cancel             => KeyCode::Cancel,
+            Keycode::Clear              => KeyCode::Clear,
+            Keycode::Prior              => KeyCode::Prior,
+            Keycode::Return2            => KeyCode::Return2,
+            Keycode::Separator          => KeyCode::Separator,
+            Keycode::Out                => KeyCode::Out,
+            Keycode::Oper               => KeyCode::Oper,
+            Keycode::ClearAgain         => KeyCode::ClearAgain,
+            Keycode::CrSel              => KeyCode::CrSel,
+            Keycode::ExSel              => KeyCode::ExSel,
+            Keycode::Kp00               => KeyCode::Keypad00,
+            Keycode::Kp000              => KeyCode::Keypad000,
+            Keycode::ThousandsSeparator => KeyCode::ThousandsSeparator,
+            Keycode::DecimalSeparator   => KeyCode::DecimalSeparator,
+            Keycode::CurrencyUnit       => KeyCode::CurrencyUnit,
+            Keycode::CurrencySubUnit    => KeyCode::CurrencySubUnit,
+            Keycode::KpLeftParen        => KeyCode::KeypadLeftParenthesis,
+            Keycode::KpRightParen       => KeyCode::KeypadRightParenthesis,
+            Keycode::KpLeftBrace        => KeyCode::KeypadLeftBrace,
+            Keycode::KpRightBrace       => KeyCode::KeypadRightBrace,
+            Keycode::KpTab              => KeyCode::KeypadTab,
+            Keycode::KpBackspace        => KeyCode::KeypadBackspace,
+            Keycode::KpA                => KeyCode::KeypadA,
+            Keycode::KpB                => KeyCode::KeypadB,
+            Keycode::KpC                => KeyCode::KeypadC,
+            Keycode::KpD                => KeyCode::KeypadD,
+            Keycode::KpE                => KeyCode::KeypadE,
+            Keycode::KpF                => KeyCode::KeypadF,
+            Keycode::KpXor              => KeyCode::KeypadXor,
+            Keycode::KpPower            => KeyCode::KeypadPower,
+            Keycode::KpPercent          => KeyCode::KeypadPercent,
+            Keycode::KpLess             => KeyCode::KeypadLess,
+            Keycode::KpGreater          => KeyCode::KeypadGreater,
+            Keycode::KpAmpersand        => KeyCode::KeypadAmpersand,
+            Keycode::KpDblAmpersand     => KeyCode::KeypadDblAmpersand,
+            Keycode::KpVerticalBar      => KeyCode::KeypadVerticalBar,
+            Keycode::KpDblVerticalBar   => KeyCode::KeypadDblVerticalBar,
+            Keycode::KpColon            => KeyCode::KeypadColon,
+            Keycode::KpHash             => KeyCode::KeypadHash,
+            Keycode::KpSpace            => KeyCode::KeypadSpace,
+            Keycode::KpAt               => KeyCode::KeypadAt,
+            Keycode::KpExclam           => KeyCode::KeypadExclam,
+            Keycode::KpMemStore         => KeyCode::KeypadMemStore,
+            Keycode::KpMemRecall        => KeyCode::KeypadMemRecall,
+            Keycode::KpMemClear         => KeyCode::KeypadMemClear,
+            Keycode::KpMemAdd           => KeyCode::KeypadMemAdd,
+            Keycode::KpMemSubtract      => KeyCode::KeypadMemSubtract,
+            Keycode::KpMemMultiply      => KeyCode::KeypadMemMultiply,
+            Keycode::KpMemDivide        => KeyCode::KeypadMemDivide,
+            Keycode::KpPlusMinus        => KeyCode::KeypadPlusMinus,
+            Keycode::KpClear            => KeyCode::KeypadClear,
+            Keycode::KpClearEntry       => KeyCode::KeypadClearEntry,
+            Keycode::KpBinary           => KeyCode::KeypadBinary,
+            Keycode::KpOctal            => KeyCode::KeypadOctal,
+            Keycode::KpDecimal          => KeyCode::KeypadDecimal,
+            Keycode::KpHexadecimal      => KeyCode::KeypadHexadecimal,
+            Keycode::LCtrl              => KeyCode::LControl,
+            Keycode::LShift             => KeyCode::LShift,
+            Keycode::LAlt               => KeyCode::LAlt,
+            Keycode::LGui               => KeyCode::LGui,
+            Keycode::RCtrl              => KeyCode::RControl,
+            Keycode::RShift             => KeyCode::RShift,
+            Keycode::RAlt               => KeyCode::RAlt,
+            Keycode::RGui               => KeyCode::RGui,
+            Keycode::Mode               => KeyCode::Mode,
+            Keycode::AudioNext          => KeyCode::AudioNext,
+            Keycode::AudioPrev          => KeyCode::AudioPrev,
+            Keycode::AudioStop          => KeyCode::AudioStop,
+            Keycode::AudioPlay          => KeyCode::AudioPlay,
+            Keycode::AudioMute          => KeyCode::AudioMute,
+            Keycode::MediaSelect        => KeyCode::MediaSelect,
+            Keycode::Www                => KeyCode::Www,
+            Keycode::Mail               => KeyCode::Mail,
+            Keycode::Calculator         => KeyCode::Calculator,
+            Keycode::Computer           => KeyCode::Computer,
+            Keycode::AcSearch           => KeyCode::AcSearch,
+            Keycode::AcHome             => KeyCode::AcHome,
+            Keycode::AcBack             => KeyCode::AcBack,
+            Keycode::AcForward          => KeyCode::AcForward,
+            Keycode::AcStop             => KeyCode::AcStop,
+            Keycode::AcRefresh          => KeyCode::AcRefresh,
+            Keycode::AcBookmarks        => KeyCode::AcBookmarks,
+            Keycode::BrightnessDown     => KeyCode::BrightnessDown,
+            Keycode::BrightnessUp       => KeyCode::BrightnessUp,
+            Keycode::DisplaySwitch      => KeyCode::DisplaySwitch,
+            Keycode::KbdIllumToggle     => KeyCode::KeyboardIlluminationToggle,
+            Keycode::KbdIllumDown       => KeyCode::KeyboardIlluminationDown,
+            Keycode::KbdIllumUp         => KeyCode::KeyboardIlluminationUp,
+            Keycode::Eject              => KeyCode::Eject,
+            Keycode::Sleep              => KeyCode::Sleep,
+        }
+    }
+}
+
+impl From<Scancode> for ScanCode {
+    fn from(code: Scancode) -> ScanCode {
+        match code {
+            Scancode::A                  => ScanCode::A,
+            Scancode::B                  => ScanCode::B,
+            Scancode::C                  => ScanCode::C,
+            Scancode::D                  => ScanCode::D,
+            Scancode::E                  => ScanCode::E,
+            Scancode::F                  => ScanCode::F,
+            Scancode::G                  => ScanCode::G,
+            Scancode::H                  => ScanCode::H,
+            Scancode::I                  => ScanCode::I,
+            Scancode::J                  => ScanCode::J,
+            Scancode::K                  => ScanCode::K,
+            Scancode::L                  => ScanCode::L,
+            Scancode::M                  => ScanCode::M,
+            Scancode::N                  => ScanCode::N,
+            Scancode::O                  => ScanCode::O,
+            Scancode::P                  => ScanCode::P,
+            Scancode::Q                  => ScanCode::Q,
+            Scancode::R                  => ScanCode::R,
+            Scancode::S                  => ScanCode::S,
+            Scancode::T                  => ScanCode::T,
+            Scancode::U                  => ScanCode::U,
+            Scancode::V                  => ScanCode::V,
+            Scancode::W                  => ScanCode::W,
+            Scancode::X                  => ScanCode::X,
+            Scancode::Y                  => ScanCode::Y,
+            Scancode::Z                  => ScanCode::Z,
+            Scancode::Num1               => ScanCode::Number1,
+            Scancode::Num2               => ScanCode::Number2,
+            Scancode::Num3               => ScanCode::Number3,
+            Scancode::Num4               => ScanCode::Number4,
+            Scancode::Num5               => ScanCode::Number5,
+            Scancode::Num6               => ScanCode::Number6,
+            Scancode::Num7               => ScanCode::Number7,
+            Scancode::Num8               => ScanCode::Number8,
+            Scancode::Num9               => ScanCode::Number9,
+            Scancode::Num0               => ScanCode::Number0,
+            Scancode::Return             => ScanCode::Return,
+            Scancode::Escape             => ScanCode::Escape,
+            Scancode::Backspace          => ScanCode::Backspace,
+            Scancode::Tab                => ScanCode::Tab,
+            Scancode::Space              => ScanCode::Space,
+            Scancode::Minus              => ScanCode::Minus,
+            Scancode::Equals             => ScanCode::Equals,
+            Scancode::LeftBracket        => ScanCode::LeftBracket,
+            Scancode::RightBracket       => ScanCode::RightBracket,
+            Scancode::Backslash          => ScanCode::Backslash,
+            Scancode::NonUsHash          => ScanCode::NonUsHash,
+            Scancode::Semicolon          => ScanCode::Semicolon,
+            Scancode::Apostrophe         => ScanCode::Apostrophe,
+            Scancode::Grave              => ScanCode::Grave,
+            Scancode::Comma              => ScanCode::Comma,
+            Scancode::Period             => ScanCode::Period,
+            Scancode::Slash              => ScanCode::Slash,
+            Scancode::CapsLock           => ScanCode::CapsLock,
+            Scancode::F1                 => ScanCode::F1,
+            Scancode::F2                 => ScanCode::F2,
+            Scancode::F3                 => ScanCode::F3,
+            Scancode::F4                 => ScanCode::F4,
+            Scancode::F5                 => ScanCode::F5,
+            Scancode::F6                 => ScanCode::F6,
+            Scancode::F7                 => ScanCode::F7,
+            Scancode::F8                 => ScanCode::F8,
+            Scancode::F9                 => ScanCode::F9,
+            Scancode::F10                => ScanCode::F10,
+            Scancode::F11                => ScanCode::F11,
+            Scancode::F12                => ScanCode::F12,
+            Scancode::PrintScreen        => ScanCode::PrintScreen,
+            Scancode::ScrollLock         => ScanCode::ScrollLock,
+            Scancode::Pause              => ScanCode::Pause,
+            Scancode::Insert             => ScanCode::Insert,
+            Scancode::Home               => ScanCode::Home,
+            Scancode::PageUp             => ScanCode::PageUp,
+            Scancode::Delete             => ScanCode::Delete,
+            Scancode::End                => ScanCode::End,
+            Scancode::PageDown           => ScanCode::PageDown,
+            Scancode::Right              => ScanCode::Right,
+            Scancode::Left               => ScanCode::Left,
+            Scancode::Down               => ScanCode::Down,
+            Scancode::Up                 => ScanCode::Up,
+            Scancode::NumLockClear       => ScanCode::NumLockClear,
+            Scancode::KpDivide           => ScanCode::KeypadDivide,
+            Scancode::KpMultiply         => ScanCode::KeypadMultiply,
+            Scancode::KpMinus            => ScanCode::KeypadMinus,
+            Scancode::KpPlus             => ScanCode::KeypadPlus,
+            Scancode::KpEnter            => ScanCode::KeypadEnter,
+            Scancode::Kp1                => ScanCode::Keypad1,
+            Scancode::Kp2                => ScanCode::Keypad2,
+            Scancode::Kp3                => ScanCode::Keypad3,
+            Scancode::Kp4                => ScanCode::Keypad4,
+            Scancode::Kp5                => ScanCode::Keypad5,
+            Scancode::Kp6                => ScanCode::Keypad6,
+            Scancode::Kp7                => ScanCode::Keypad7,
+            Scancode::Kp8                => ScanCode::Keypad8,
+            Scancode::Kp9                => ScanCode::Keypad9,
+            Scancode::Kp0                => ScanCode::Keypad0,
+            Scancode::KpPeriod           => ScanCode::KeypadPeriod,
+            Scancode::NonUsBackslash     => ScanCode::NonUsBackslash,
+            Scancode::Application        => ScanCode::Application,
+            Scancode::Power              => ScanCode::Power,
+            Scancode::KpEquals           => ScanCode::KeypadEquals,
+            Scancode::F13                => ScanCode::F13,
+            Scancode::F14                => ScanCode::F14,
+            Scancode::F15                => ScanCode::F15,
+            Scancode::F16                => ScanCode::F16,
+            Scancode::F17                => ScanCode::F17,
+            Scancode::F18                => ScanCode::F18,
+            Scancode::F19                => ScanCode::F19,
+            Scancode::F20                => ScanCode::F20,
+            Scancode::F21                => ScanCode::F21,
+            Scancode::F22                => ScanCode::F22,
+            Scancode::F23                => ScanCode::F23,
+            Scancode::F24                => ScanCode::F24,
+            Scancode::Execute            => ScanCode::Execute,
+            Scancode::Help               => ScanCode::Help,
+            Scancode::Menu               => ScanCode::Menu,
+            Scancode::Select             => ScanCode::Select,
+            Scancode::Stop               => ScanCode::Stop,
+            Scancode::Again              => ScanCode::Again,
+            Scancode::Undo               => ScanCode::Undo,
+            Scancode::Cut                => ScanCode::Cut,
+            Scancode::Copy               => ScanCode::Copy,
+            Scancode::Paste              => ScanCode::Paste,
+            Scancode::Find               => ScanCode::Find,
+            Scancode::Mute               => ScanCode::Mute,
+            Scancode::VolumeUp           => ScanCode::VolumeUp,
+            Scancode::VolumeDown         => ScanCode::VolumeDown,
+            Scancode::KpComma            => ScanCode::KeypadComma,
+            Scancode::KpEqualsAS400      => ScanCode::KeypadEqualsAS400,
+            Scancode::International1     => ScanCode::International1,
+            Scancode::International2     => ScanCode::International2,
+            Scancode::International3     => ScanCode::International3,
+            Scancode::International4     => ScanCode::International4,
+            Scancode::International5     => ScanCode::International5,
+            Scancode::International6     => ScanCode::International6,
+            Scancode::International7     => ScanCode::International7,
+            Scancode::International8     => ScanCode::International8,
+            Scancode::International9     => ScanCode::International9,
+            Scancode::Lang1              => ScanCode::Language1,
+            Scancode::Lang2              => ScanCode::Language2,
+            Scancode::Lang3              => ScanCode::Language3,
+            Scancode::Lang4              => ScanCode::Language4,
+            Scancode::Lang5              => ScanCode::Language5,
+            Scancode::Lang6              => ScanCode::Language6,
+            Scancode::Lang7              => ScanCode::Language7,
+            Scancode::Lang8              => ScanCode::Language8,
+            Scancode::Lang9              => ScanCode::Language9,
+            Scancode::AltErase           => ScanCode::AltErase,
+            Scancode::SysReq             => ScanCode::SysReq,
+            Scancode::Cancel             => ScanCode::Cancel,
+            Scancode::Clear              => ScanCode::Clear,
+            Scancode::Prior              => ScanCode::Prior,
+            Scancode::Return2            => ScanCode::Return2,
+            Scancode::Separator          => ScanCode::Separator,
+            Scancode::Out                => ScanCode::Out,
+            Scancode::Oper               => ScanCode::Oper,
+            Scancode::ClearAgain         => ScanCode::ClearAgain,
+            Scancode::CrSel              => ScanCode::CrSel,
+            Scancode::ExSel              => ScanCode::ExSel,
+            Scancode::Kp00               => ScanCode::Keypad00,
+            Scancode::Kp000              => ScanCode::Keypad000,
+            Scancode::ThousandsSeparator => ScanCode::ThousandsSeparator,
+            Scancode::DecimalSeparator   => ScanCode::DecimalSeparator,
+            Scancode::CurrencyUnit       => ScanCode::CurrencyUnit,
+            Scancode::CurrencySubUnit    => ScanCode::CurrencySubUnit,
+            Scancode::KpLeftParen        => ScanCode::KeypadLeftParen,
+            Scancode::KpRightParen       => ScanCode::KeypadRightParen,
+            Scancode::KpLeftBrace        => ScanCode::KeypadLeftBrace,
+            Scancode::KpRightBrace       => ScanCode::KeypadRightBrace,
+            Scancode::KpTab              => ScanCode::KeypadTab,
+            Scancode::KpBackspace        => ScanCode::KeypadBackspace,
+            Scancode::KpA                => ScanCode::KeypadA,
+            Scancode::KpB                => ScanCode::KeypadB,
+            Scancode::KpC                => ScanCode::KeypadC,
+            Scancode::KpD                => ScanCode::KeypadD,
+            Scancode::KpE                => ScanCode::KeypadE,
+            Scancode::KpF                => ScanCode::KeypadF,
+            Scancode::KpXor              => ScanCode::KeypadXor,
+            Scancode::KpPower            => ScanCode::KeypadPower,
+            Scancode::KpPercent          => ScanCode::KeypadPercent,
+            Scancode::KpLess             => ScanCode::KeypadLess,
+            Scancode::KpGreater          => ScanCode::KeypadGreater,
+            Scancode::KpAmpersand        => ScanCode::KeypadAmpersand,
+            Scancode::KpDblAmpersand     => ScanCode::KeypadDblAmpersand,
+            Scancode::KpVerticalBar      => ScanCode::KeypadVerticalBar,
+            Scancode::KpDblVerticalBar   => ScanCode::KeypadDblVerticalBar,
+            Scancode::KpColon            => ScanCode::KeypadColon,
+            Scancode::KpHash             => ScanCode::KeypadHash,
+            Scancode::KpSpace            => ScanCode::KeypadSpace,
+            Scancode::KpAt               => ScanCode::KeypadAt,
+            Scancode::KpExclam           => ScanCode::KeypadExclam,
+            Scancode::KpMemStore         => ScanCode::KeypadMemStore,
+            Scancode::KpMemRecall        => ScanCode::KeypadMemRecall,
+            Scancode::KpMemClear         => ScanCode::KeypadMemClear,
+            Scancode::KpMemAdd           => ScanCode::KeypadMemAdd,
+            Scancode::KpMemSubtract      => ScanCode::KeypadMemSubtract,
+            Scancode::KpMemMultiply      => ScanCode::KeypadMemMultiply,
+            Scancode::KpMemDivide        => ScanCode::KeypadMemDivide,
+            Scancode::KpPlusMinus        => ScanCode::KeypadPlusMinus,
+            Scancode::KpClear            => ScanCode::KeypadClear,
+            Scancode::KpClearEntry       => ScanCode::KeypadClearEntry,
+            Scancode::KpBinary           => ScanCode::KeypadBinary,
+            Scancode::KpOctal            => ScanCode::KeypadOctal,
+            Scancode::KpDecimal          => ScanCode::KeypadDecimal,
+            Scancode::KpHexadecimal      => ScanCode::KeypadHexadecimal,
+            Scancode::LCtrl              => ScanCode::LControl,
+            Scancode::LShift             => ScanCode::LShift,
+            Scancode::LAlt               => ScanCode::LAlt,
+            Scancode::LGui               => ScanCode::LGui,
+            Scancode::RCtrl              => ScanCode::RControl,
+            Scancode::RShift             => ScanCode::RShift,
+            Scancode::RAlt               => ScanCode::RAlt,
+            Scancode::RGui               => ScanCode::RGui,
+            Scancode::Mode               => ScanCode::Mode,
+            Scancode::AudioNext          => ScanCode::AudioNext,
+            Scancode::AudioPrev          => ScanCode::AudioPrev,
+            Scancode::AudioStop          => ScanCode::AudioStop,
+            Scancode::AudioPlay          => ScanCode::AudioPlay,
+            Scancode::AudioMute          => ScanCode::AudioMute,
+            Scancode::MediaSelect        => ScanCode::MediaSelect,
+            Scancode::Www                => ScanCode::Www,
+            Scancode::Mail               => ScanCode::Mail,
+            Scancode::Calculator         => ScanCode::Calculator,
+            Scancode::Computer           => ScanCode::Computer,
+            Scancode::AcSearch           => ScanCode::AcSearch,
+            Scancode::AcHome             => ScanCode::AcHome,
+            Scancode::AcBack             => ScanCode::AcBack,
+            Scancode::AcForward          => ScanCode::AcForward,
+            Scancode::AcStop             => ScanCode::AcStop,
+            Scancode::AcRefresh          => ScanCode::AcRefresh,
+            Scancode::AcBookmarks        => ScanCode::AcBookmarks,
+            Scancode::BrightnessDown     => ScanCode::BrightnessDown,
+            Scancode::BrightnessUp       => ScanCode::BrightnessUp,
+            Scancode::DisplaySwitch      => ScanCode::DisplaySwitch,
+            Scancode::KbdIllumToggle     => ScanCode::KeyboardIlluminationToggle,
+            Scancode::KbdIllumDown       => ScanCode::KeyboardIlluminationDown,
+            Scancode::KbdIllumUp         => ScanCode::KeyboardIlluminationUp,
+            Scancode::Eject              => ScanCode::Eject,
+            Scancode::Sleep              => ScanCode::Sleep,
+            Scancode::App1               => ScanCode::App1,
+            Scancode::App2               => ScanCode::App2,
+            Scancode::Num                => ScanCode::Num,
+        }
+    }
+}
+
+impl From<sdl2::keyboard::Mod> for Mod {
+    fn from(key_mod: sdl2::keyboard::Mod) -> Mod {
+        Mod(key_mod.bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_keycode() {
+        assert_eq!(KeyCode::from(Keycode::Num5), KeyCode::Number5);
+        assert_eq!(KeyCode::from(Keycode::KpEnter), KeyCode::KeypadEnter);
+        assert_eq!(KeyCode::from(Keycode::KbdIllumUp), KeyCode::KeyboardIlluminationUp);
+    }
+
+    #[test]
+    fn from_scancode() {
+        assert_eq!(ScanCode::from(Scancode::Lang3), ScanCode::Language3);
+        assert_eq!(ScanCode::from(Scancode::RCtrl), ScanCode::RControl);
+        assert_eq!(ScanCode::from(Scancode::Num), ScanCode::Num);
+    }
+
+    #[test]
+    fn from_mod() {
+        assert_eq!(Mod::from(sdl2::keyboard::Mod::NOMOD), Mod::NONE);
+        assert_eq!(Mod::from(sdl2::keyboard::Mod::CAPSMOD), Mod::CAPS);
+        assert_eq!(Mod::from(sdl2::keyboard::Mod::RALTMOD | sdl2::keyboard::Mod::LSHIFTMOD), Mod::RALT | Mod::LSHIFT);
+    }
+
+    #[test]
+    fn mod_set() {
+        let mut test = Mod::LSHIFT | Mod::RCONTROL;
+
+        assert!(test.contains(Mod::LSHIFT));
+        assert!(!test.contains(Mod::SHIFT));
+        assert!(test.intersects(Mod::SHIFT));
+        assert!(!test.intersects(Mod::ALT));
+
+        test.remove(Mod::LSHIFT);
+
+        assert_eq!(test, Mod::RCONTROL);
+    }
+
+    #[test]
+    fn mod_names() {
+        assert_eq!((Mod::LSHIFT | Mod::RALT).to_string(), "LShift|RAlt");
+        assert_eq!(Mod::NONE.to_string(), "NoMod");
+        assert_eq!("LShift|RAlt".parse(), Ok(Mod::LSHIFT | Mod::RALT));
+        assert_eq!("lcontrol + caps".parse(), Ok(Mod::LCONTROL | Mod::CAPS));
+        assert_eq!("NoMod".parse(), Ok(Mod::NONE));
+        assert!("Hyper".parse::<Mod>().is_err());
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(KeyCode::LShift.to_string(), "LShift");
+        assert_eq!("LShift".parse(), Ok(KeyCode::LShift));
+        assert_eq!("keypad0".parse(), Ok(ScanCode::Keypad0));
+        assert!("Nope".parse::<KeyCode>().is_err());
+
+        for code in KeyCode::ALL {
+            assert_eq!(code.to_string().parse(), Ok(*code));
+        }
+
+        for code in ScanCode::ALL {
+            assert_eq!(code.to_string().parse(), Ok(*code));
+        }
+    }
 }
@@ -0,0 +1,593 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use crate::core::event::Event;
+use crate::input::{ controller, keyboard, mouse, InputState, ParseNameError };
+use crate::input::controller::{ apply_dead_zone, normalise_axis };
+
+pub use crate::input::controller::DEFAULT_DEAD_ZONE;
+
+/// Magnitude an action's value has to reach to count as held.
+const PRESS_THRESHOLD: f32 = 0.5;
+
+/// A physical input an action can be bound to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Binding {
+    Key(keyboard::KeyCode),
+    ScanCode(keyboard::ScanCode),
+    MouseButton(mouse::Button),
+    ControllerButton(controller::Button),
+    ControllerAxis {
+        axis: controller::Axis,
+        dead_zone: f32,
+        inverted: bool,
+    },
+    /// Two keys acting as one axis, `negative` giving -1 and `positive` giving 1.
+    KeyAxis {
+        negative: keyboard::KeyCode,
+        positive: keyboard::KeyCode,
+    },
+}
+
+impl Binding {
+    /// Turns the first meaningful input in `event` into a binding, for "press a key" rebinding screens.
+    pub fn from_event(event: &Event) -> Option<Binding> {
+        match event {
+            Event::KeyDown { keycode: Some(keycode), repeat: false, .. } => Some(Binding::Key(*keycode)),
+            Event::MouseButtonDown { mouse_button, .. } => Some(Binding::MouseButton(*mouse_button)),
+            Event::ControllerButtonDown { button, .. } => Some(Binding::ControllerButton(*button)),
+            Event::ControllerAxisMotion { axis, value, .. } if normalise_axis(*value).abs() >= PRESS_THRESHOLD => Some(Binding::ControllerAxis {
+                axis: *axis,
+                dead_zone: DEFAULT_DEAD_ZONE,
+                inverted: *value < 0,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Current value of the binding in `[-1, 1]`.
+    pub fn value(&self, input: &InputState) -> f32 {
+        let key = |keycode: keyboard::KeyCode| input.is_key_held(keycode) || input.is_key_pressed(keycode);
+
+        match self {
+            Binding::Key(keycode) => digital(key(*keycode)),
+            Binding::ScanCode(scancode) => digital(input.is_scancode_held(*scancode) || input.is_scancode_pressed(*scancode)),
+            Binding::MouseButton(button) => digital(input.is_mouse_button_held(*button) || input.is_mouse_button_pressed(*button)),
+            Binding::ControllerButton(button) => digital(input.controllers().any(|(_, x)| x.is_button_held(*button) || x.is_button_pressed(*button))),
+            Binding::ControllerAxis { axis, dead_zone, inverted } => {
+                let value = input.controllers()
+                    .map(|(_, x)| apply_dead_zone(normalise_axis(x.axis(*axis)), *dead_zone))
+                    .fold(0., |acc: f32, x| if x.abs() > acc.abs() { x } else { acc });
+
+                if *inverted { -value } else { value }
+            },
+            Binding::KeyAxis { negative, positive } => digital(key(*positive)) - digital(key(*negative)),
+        }
+    }
+}
+
+fn digital(held: bool) -> f32 {
+    if held { 1. } else { 0. }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(x)              => write!(f, "key:{}", x),
+            Binding::ScanCode(x)         => write!(f, "scancode:{}", x),
+            Binding::MouseButton(x)      => write!(f, "mouse:{}", x),
+            Binding::ControllerButton(x) => write!(f, "button:{}", x),
+            Binding::ControllerAxis { axis, dead_zone, inverted } => {
+                write!(f, "axis:{}:{}", axis, dead_zone)?;
+
+                if *inverted {
+                    write!(f, ":inverted")?;
+                }
+
+                Ok(())
+            },
+            Binding::KeyAxis { negative, positive } => write!(f, "keys:{}:{}", negative, positive),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = ParseBindingError;
+
+    fn from_str(s: &str) -> Result<Binding, ParseBindingError> {
+        let parts: Vec<&str> = s.trim().split(':').map(str::trim).collect();
+
+        let binding = match parts.as_slice() {
+            ["key", x] => Binding::Key(x.parse()?),
+            ["scancode", x] => Binding::ScanCode(x.parse()?),
+            ["mouse", x] => Binding::MouseButton(x.parse()?),
+            ["button", x] => Binding::ControllerButton(x.parse()?),
+            ["axis", axis, rest @ ..] if rest.len() <= 2 => {
+                let dead_zone = match rest.first() {
+                    Some(x) => match x.parse::<f32>() {
+                        Ok(dead_zone) if (0. ..1.).contains(&dead_zone) => dead_zone,
+                        _ => return Err(ParseBindingError::DeadZone(String::from(*x))),
+                    },
+                    None => DEFAULT_DEAD_ZONE,
+                };
+
+                let inverted = match rest.get(1) {
+                    Some(&"inverted") => true,
+                    Some(x) => return Err(ParseBindingError::Unexpected(String::from(*x))),
+                    None => false,
+                };
+
+                Binding::ControllerAxis {
+                    axis: axis.parse()?,
+                    dead_zone,
+                    inverted,
+                }
+            },
+            ["keys", negative, positive] => Binding::KeyAxis {
+                negative: negative.parse()?,
+                positive: positive.parse()?,
+            },
+            _ => return Err(ParseBindingError::Invalid(String::from(s.trim()))),
+        };
+
+        Ok(binding)
+    }
+}
+
+/// Why a binding like `key:Space` couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBindingError {
+    /// Not one of the `kind:name` forms `Binding` is written in.
+    Invalid(String),
+    /// A key, button or axis name that doesn't exist.
+    Name(ParseNameError),
+    /// A dead zone that isn't a number in `[0, 1)`.
+    DeadZone(String),
+    /// Something other than `inverted` after an axis dead zone.
+    Unexpected(String),
+}
+
+impl From<ParseNameError> for ParseBindingError {
+    fn from(error: ParseNameError) -> ParseBindingError {
+        ParseBindingError::Name(error)
+    }
+}
+
+impl fmt::Display for ParseBindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBindingError::Invalid(x)    => write!(f, "invalid binding \"{}\"", x),
+            ParseBindingError::Name(x)       => write!(f, "{}", x),
+            ParseBindingError::DeadZone(x)   => write!(f, "invalid dead zone \"{}\"", x),
+            ParseBindingError::Unexpected(x) => write!(f, "unexpected \"{}\"", x),
+        }
+    }
+}
+
+impl std::error::Error for ParseBindingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseBindingError::Name(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMappingError {
+    line: usize,
+    message: String,
+}
+
+impl ParseMappingError {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for ParseMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseMappingError {}
+
+#[derive(Clone, Default)]
+struct Action {
+    bindings: Vec<Binding>,
+    value: f32,
+    previous: f32,
+}
+
+/// Named actions bound to any number of physical inputs.
+///
+/// Call `update` once per frame after the `InputState` has seen the frame's events. Bindings
+/// are saved as one action per line:
+///
+/// ```text
+/// jump = key:Space, button:A
+/// move_x = keys:A:D, axis:LeftX:0.2
+/// "aim, alt" = mouse:Right
+/// ```
+///
+/// Action names that wouldn't read back the same as they are are written in quotes, with `\`
+/// escaping quotes, backslashes and line breaks.
+#[derive(Clone, Default)]
+pub struct InputMap {
+    actions: BTreeMap<String, Action>,
+}
+
+impl InputMap {
+    pub fn new() -> InputMap {
+        InputMap {
+            actions: BTreeMap::new(),
+        }
+    }
+
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        if let Binding::ControllerAxis { dead_zone, .. } = binding {
+            debug_assert!((0. ..1.).contains(&dead_zone), "dead zone {} is outside [0, 1)", dead_zone);
+        }
+
+        let bindings = &mut self.actions.entry(String::from(action)).or_default().bindings;
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes `binding` from `action`, returning whether it was bound.
+    pub fn unbind(&mut self, action: &str, binding: &Binding) -> bool {
+        match self.actions.get_mut(action) {
+            Some(x) => {
+                let len = x.bindings.len();
+                x.bindings.retain(|b| b != binding);
+                x.bindings.len() != len
+            },
+            None => false,
+        }
+    }
+
+    /// Replaces `old` with `new` in place, keeping the binding order. Returns whether `old` was bound.
+    pub fn rebind(&mut self, action: &str, old: &Binding, new: Binding) -> bool {
+        let bindings = match self.actions.get_mut(action) {
+            Some(x) => &mut x.bindings,
+            None => return false,
+        };
+
+        match bindings.iter().position(|b| b == old) {
+            Some(i) => {
+                bindings[i] = new;
+
+                let mut j = 0;
+                bindings.retain(|b| {
+                    let keep = j == i || *b != new;
+                    j += 1;
+                    keep
+                });
+
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self, action: &str) {
+        if let Some(x) = self.actions.get_mut(action) {
+            x.bindings.clear();
+        }
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map(|x| x.bindings.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    pub fn update(&mut self, input: &InputState) {
+        for action in self.actions.values_mut() {
+            action.previous = action.value;
+            action.value = action.bindings
+                .iter()
+                .map(|x| x.value(input))
+                .fold(0., |acc: f32, x| if x.abs() > acc.abs() { x } else { acc });
+        }
+    }
+
+    /// Strongest value of any of the action's bindings, in `[-1, 1]`.
+    pub fn value(&self, action: &str) -> f32 {
+        self.actions.get(action).map(|x| x.value).unwrap_or(0.)
+    }
+
+    pub fn is_held(&self, action: &str) -> bool {
+        self.actions.get(action).map(|x| x.value.abs() >= PRESS_THRESHOLD).unwrap_or(false)
+    }
+
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.actions.get(action).map(|x| x.value.abs() >= PRESS_THRESHOLD && x.previous.abs() < PRESS_THRESHOLD).unwrap_or(false)
+    }
+
+    pub fn is_released(&self, action: &str) -> bool {
+        self.actions.get(action).map(|x| x.value.abs() < PRESS_THRESHOLD && x.previous.abs() >= PRESS_THRESHOLD).unwrap_or(false)
+    }
+
+    pub fn save(&self) -> String {
+        let mut out = String::new();
+
+        for (name, action) in self.actions.iter() {
+            let bindings: Vec<String> = action.bindings.iter().map(Binding::to_string).collect();
+            out.push_str(&format!("{} = {}\n", quote_name(name), bindings.join(", ")));
+        }
+
+        out
+    }
+
+    /// Parses bindings written by `save`. Blank lines and lines starting with `#` are skipped.
+    pub fn load(text: &str) -> Result<InputMap, ParseMappingError> {
+        let mut map = InputMap::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| ParseMappingError { line: i + 1, message };
+
+            let (name, rest) = match line.strip_prefix('"') {
+                Some(x) => unquote_name(x).ok_or_else(|| error(String::from("unterminated quoted action name")))?,
+                None => match line.find('=') {
+                    Some(0) => return Err(error(String::from("missing action name"))),
+                    Some(x) => (String::from(line[..x].trim()), &line[x..]),
+                    None => (String::from(line), ""),
+                },
+            };
+
+            let bindings = match rest.trim_start().strip_prefix('=') {
+                Some(x) => x.trim(),
+                None => return Err(error(String::from("expected \"action = bindings\""))),
+            };
+
+            map.actions.entry(name.clone()).or_default();
+
+            for binding in bindings.split(',').filter(|x| !x.trim().is_empty()) {
+                map.bind(&name, binding.parse().map_err(|x: ParseBindingError| error(x.to_string()))?);
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// `name` as `InputMap::save` writes it, quoted if it would otherwise load back differently.
+fn quote_name(name: &str) -> String {
+    let plain = !name.is_empty()
+        && name.trim() == name
+        && !name.starts_with('#')
+        && !name.starts_with('"')
+        && !name.contains(['=', ',', '\n', '\r']);
+
+    if plain {
+        return String::from(name);
+    }
+
+    let mut quoted = String::from("\"");
+
+    for x in name.chars() {
+        match x {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(x);
+            },
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(x),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Reads a quoted action name from just after its opening quote, returning it and the rest of
+/// the line, or `None` if the quote is never closed.
+fn unquote_name(text: &str) -> Option<(String, &str)> {
+    let mut name = String::new();
+    let mut chars = text.char_indices();
+
+    while let Some((i, x)) = chars.next() {
+        match x {
+            '"' => return Some((name, &text[i + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => name.push('\n'),
+                'r' => name.push('\r'),
+                x => name.push(x),
+            },
+            _ => name.push(x),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_down(keycode: keyboard::KeyCode) -> Event {
+        Event::KeyDown {
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            key_mod: keyboard::Mod::NONE,
+            repeat: false,
+        }
+    }
+
+    fn key_up(keycode: keyboard::KeyCode) -> Event {
+        Event::KeyUp {
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            key_mod: keyboard::Mod::NONE,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn action_edges() {
+        let mut input = InputState::new();
+        let mut map = InputMap::new();
+        map.bind("jump", Binding::Key(keyboard::KeyCode::Space));
+        map.bind("jump", Binding::ControllerButton(controller::Button::A));
+
+        input.handle_event(&key_down(keyboard::KeyCode::Space));
+        map.update(&input);
+
+        assert!(map.is_pressed("jump"));
+        assert!(map.is_held("jump"));
+
+        input.end_frame();
+        map.update(&input);
+
+        assert!(!map.is_pressed("jump"));
+        assert!(map.is_held("jump"));
+
+        input.end_frame();
+        input.handle_event(&key_up(keyboard::KeyCode::Space));
+        map.update(&input);
+
+        assert!(map.is_released("jump"));
+        assert!(!map.is_held("jump"));
+        assert!(!map.is_held("missing"));
+    }
+
+    #[test]
+    fn key_axis() {
+        let mut input = InputState::new();
+        let mut map = InputMap::new();
+        map.bind("move_x", Binding::KeyAxis { negative: keyboard::KeyCode::A, positive: keyboard::KeyCode::D });
+
+        input.handle_event(&key_down(keyboard::KeyCode::A));
+        map.update(&input);
+
+        assert_eq!(map.value("move_x"), -1.);
+
+        input.handle_event(&key_down(keyboard::KeyCode::D));
+        map.update(&input);
+
+        assert_eq!(map.value("move_x"), 0.);
+    }
+
+    #[test]
+    fn controller_axis() {
+        let mut input = InputState::new();
+        let mut map = InputMap::new();
+        map.bind("move_y", Binding::ControllerAxis { axis: controller::Axis::LeftY, dead_zone: 0.5, inverted: true });
+
+        input.handle_event(&Event::ControllerAxisMotion { which: 0, axis: controller::Axis::LeftY, value: 8000 });
+        map.update(&input);
+
+        assert_eq!(map.value("move_y"), 0.);
+
+        input.handle_event(&Event::ControllerAxisMotion { which: 0, axis: controller::Axis::LeftY, value: i16::MAX });
+        map.update(&input);
+
+        assert_eq!(map.value("move_y"), -1.);
+    }
+
+    #[test]
+    fn rebind() {
+        let mut map = InputMap::new();
+        map.bind("fire", Binding::Key(keyboard::KeyCode::F));
+        map.bind("fire", Binding::MouseButton(mouse::Button::Left));
+
+        assert!(map.rebind("fire", &Binding::Key(keyboard::KeyCode::F), Binding::Key(keyboard::KeyCode::J)));
+        assert!(!map.rebind("fire", &Binding::Key(keyboard::KeyCode::F), Binding::Key(keyboard::KeyCode::K)));
+        assert_eq!(map.bindings("fire"), &[Binding::Key(keyboard::KeyCode::J), Binding::MouseButton(mouse::Button::Left)]);
+
+        assert!(map.unbind("fire", &Binding::MouseButton(mouse::Button::Left)));
+        assert_eq!(map.bindings("fire"), &[Binding::Key(keyboard::KeyCode::J)]);
+    }
+
+    #[test]
+    fn from_event() {
+        let test = Binding::from_event(&Event::ControllerAxisMotion { which: 0, axis: controller::Axis::RightX, value: -30000 });
+        let correct = Binding::ControllerAxis { axis: controller::Axis::RightX, dead_zone: DEFAULT_DEAD_ZONE, inverted: true };
+
+        assert_eq!(test, Some(correct));
+        assert_eq!(Binding::from_event(&Event::ControllerAxisMotion { which: 0, axis: controller::Axis::RightX, value: 100 }), None);
+        assert_eq!(Binding::from_event(&key_down(keyboard::KeyCode::Q)), Some(Binding::Key(keyboard::KeyCode::Q)));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut map = InputMap::new();
+        map.bind("jump", Binding::Key(keyboard::KeyCode::Space));
+        map.bind("jump", Binding::ControllerButton(controller::Button::A));
+        map.bind("move_x", Binding::KeyAxis { negative: keyboard::KeyCode::Left, positive: keyboard::KeyCode::Right });
+        map.bind("move_x", Binding::ControllerAxis { axis: controller::Axis::LeftX, dead_zone: 0.25, inverted: true });
+        map.bind("pause", Binding::ScanCode(keyboard::ScanCode::Escape));
+        map.bind("shoot", Binding::MouseButton(mouse::Button::Left));
+
+        let text = map.save();
+        let correct = "jump = key:Space, button:A\n\
+                       move_x = keys:Left:Right, axis:LeftX:0.25:inverted\n\
+                       pause = scancode:Escape\n\
+                       shoot = mouse:Left\n";
+
+        assert_eq!(text, correct);
+
+        let test = InputMap::load(&text).unwrap();
+
+        for action in map.actions() {
+            assert_eq!(test.bindings(action), map.bindings(action));
+        }
+    }
+
+    #[test]
+    fn awkward_names() {
+        let names = [" aim, alt ", "#1", "a = b", "two\nlines", "\"quoted\" \\", ""];
+        let mut map = InputMap::new();
+
+        for name in names {
+            map.bind(name, Binding::Key(keyboard::KeyCode::Space));
+        }
+
+        let test = InputMap::load(&map.save()).unwrap();
+
+        assert_eq!(test.actions().collect::<Vec<_>>(), map.actions().collect::<Vec<_>>());
+
+        for name in names {
+            assert_eq!(test.bindings(name), map.bindings(name));
+        }
+
+        assert_eq!(quote_name("a = b"), "\"a = b\"");
+        assert_eq!(quote_name("move_x"), "move_x");
+        assert!(InputMap::load("\"jump = key:Space").is_err());
+    }
+
+    #[test]
+    fn load_errors() {
+        let test = InputMap::load("# comment\n\njump = key:Space\nfire = key:NotAKey\n");
+
+        assert_eq!(test.err().map(|x| x.line()), Some(4));
+        assert!(InputMap::load("jump key:Space").is_err());
+        assert!(InputMap::load("move = axis:LeftX:0.2:sideways").is_err());
+        assert_eq!("axis:LeftX:fast".parse::<Binding>(), Err(ParseBindingError::DeadZone(String::from("fast"))));
+
+        for x in ["-0.5", "1", "1.5", "NaN"] {
+            assert_eq!(format!("axis:LeftX:{}", x).parse::<Binding>(), Err(ParseBindingError::DeadZone(String::from(x))));
+        }
+        assert!(matches!("key:NotAKey".parse::<Binding>(), Err(ParseBindingError::Name(_))));
+        assert_eq!(InputMap::load("move = axis:leftx").unwrap().bindings("move"), &[Binding::ControllerAxis {
+            axis: controller::Axis::LeftX,
+            dead_zone: DEFAULT_DEAD_ZONE,
+            inverted: false,
+        }]);
+    }
+}
@@ -1,4 +1,79 @@
+use std::fmt;
+
+/// Declares a fieldless enum together with `Display` and `FromStr` impls that use the
+/// variant names, so values can round-trip through config files.
+macro_rules! named_enum {
+    ($(#[$meta:meta])* pub enum $name:ident { $($variant:ident,)* }) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant),)*
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = crate::input::ParseNameError;
+
+            fn from_str(name: &str) -> Result<$name, Self::Err> {
+                $name::ALL
+                    .iter()
+                    .find(|x| x.name().eq_ignore_ascii_case(name))
+                    .copied()
+                    .ok_or_else(|| crate::input::ParseNameError::new(stringify!($name), name))
+            }
+        }
+    };
+}
+
 pub mod controller;
+pub mod gamepad;
 pub mod joy;
 pub mod keyboard;
+pub mod mapping;
 pub mod mouse;
+pub mod state;
+pub mod text;
+pub mod touch;
+
+pub use gamepad::{ DeadZone, Gamepad, Gamepads };
+pub use mapping::{ Binding, InputMap, ParseBindingError };
+pub use state::InputState;
+pub use text::{ TextBuffer, TextInput };
+pub use touch::{ Gesture, TouchTracker };
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNameError {
+    kind: &'static str,
+    name: String,
+}
+
+impl ParseNameError {
+    pub(crate) fn new(kind: &'static str, name: &str) -> ParseNameError {
+        ParseNameError {
+            kind,
+            name: String::from(name),
+        }
+    }
+}
+
+impl fmt::Display for ParseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown {} name \"{}\"", self.kind, self.name)
+    }
+}
+
+impl std::error::Error for ParseNameError {}
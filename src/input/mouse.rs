@@ -1,16 +1,110 @@
-#[derive(Copy, Clone)]
-pub enum Button {
-    Unknown,
-    Left,
-    Middle,
-    Right,
-    X1,
-    X2,
+use sdl2::mouse::{ MouseButton, MouseState, MouseWheelDirection };
+use serde::{ Deserialize, Serialize };
+
+named_enum! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum Button {
+        Unknown,
+        Left,
+        Middle,
+        Right,
+        X1,
+        X2,
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum WheelDirection {
     Normal,
     Flipped,
     Unknown(u32),
 }
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct State {
+    left: bool,
+    middle: bool,
+    right: bool,
+    x1: bool,
+    x2: bool,
+}
+
+impl State {
+    pub fn from_buttons(buttons: &[Button]) -> State {
+        let held = |button: Button| buttons.contains(&button);
+
+        State {
+            left: held(Button::Left),
+            middle: held(Button::Middle),
+            right: held(Button::Right),
+            x1: held(Button::X1),
+            x2: held(Button::X2),
+        }
+    }
+
+    pub fn left(&self) -> bool {
+        self.left
+    }
+
+    pub fn middle(&self) -> bool {
+        self.middle
+    }
+
+    pub fn right(&self) -> bool {
+        self.right
+    }
+
+    pub fn x1(&self) -> bool {
+        self.x1
+    }
+
+    pub fn x2(&self) -> bool {
+        self.x2
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        match button {
+            Button::Unknown => false,
+            Button::Left    => self.left,
+            Button::Middle  => self.middle,
+            Button::Right   => self.right,
+            Button::X1      => self.x1,
+            Button::X2      => self.x2,
+        }
+    }
+}
+
+impl From<MouseButton> for Button {
+    fn from(button: MouseButton) -> Button {
+        match button {
+            MouseButton::Unknown => Button::Unknown,
+            MouseButton::Left    => Button::Left,
+            MouseButton::Middle  => Button::Middle,
+            MouseButton::Right   => Button::Right,
+            MouseButton::X1      => Button::X1,
+            MouseButton::X2      => Button::X2,
+        }
+    }
+}
+
+impl From<MouseWheelDirection> for WheelDirection {
+    fn from(direction: MouseWheelDirection) -> WheelDirection {
+        match direction {
+            MouseWheelDirection::Normal     => WheelDirection::Normal,
+            MouseWheelDirection::Flipped    => WheelDirection::Flipped,
+            MouseWheelDirection::Unknown(x) => WheelDirection::Unknown(x),
+        }
+    }
+}
+
+impl From<MouseState> for State {
+    fn from(state: MouseState) -> State {
+        State {
+            left: state.left(),
+            middle: state.middle(),
+            right: state.right(),
+            x1: state.x1(),
+            x2: state.x2(),
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use crate::core::event::{ Event, WindowEvent };
+use crate::input::{ controller, keyboard, mouse };
+
+const KEY_CODE_COUNT: usize = keyboard::KeyCode::ALL.len();
+const SCAN_CODE_COUNT: usize = keyboard::ScanCode::ALL.len();
+const MOUSE_BUTTON_COUNT: usize = mouse::Button::ALL.len();
+const CONTROLLER_BUTTON_COUNT: usize = controller::Button::ALL.len();
+const CONTROLLER_AXIS_COUNT: usize = controller::Axis::ALL.len();
+
+/// Held, pressed and released flags for a set of buttons indexed by discriminant.
+#[derive(Clone)]
+struct Buttons {
+    held: Vec<bool>,
+    pressed: Vec<bool>,
+    released: Vec<bool>,
+}
+
+impl Buttons {
+    fn new(count: usize) -> Buttons {
+        Buttons {
+            held: vec![false; count],
+            pressed: vec![false; count],
+            released: vec![false; count],
+        }
+    }
+
+    fn press(&mut self, idx: usize) {
+        if !self.held[idx] {
+            self.pressed[idx] = true;
+        }
+
+        self.held[idx] = true;
+    }
+
+    fn release(&mut self, idx: usize) {
+        if self.held[idx] {
+            self.released[idx] = true;
+        }
+
+        self.held[idx] = false;
+    }
+
+    fn release_all(&mut self) {
+        for idx in 0..self.held.len() {
+            self.release(idx);
+        }
+    }
+
+    fn end_frame(&mut self) {
+        self.pressed.iter_mut().for_each(|x| *x = false);
+        self.released.iter_mut().for_each(|x| *x = false);
+    }
+}
+
+/// Button and axis state of a single game controller.
+#[derive(Clone)]
+pub struct ControllerState {
+    buttons: Buttons,
+    axes: [i16; CONTROLLER_AXIS_COUNT],
+}
+
+impl ControllerState {
+    fn new() -> ControllerState {
+        ControllerState {
+            buttons: Buttons::new(CONTROLLER_BUTTON_COUNT),
+            axes: [0; CONTROLLER_AXIS_COUNT],
+        }
+    }
+
+    pub fn is_button_held(&self, button: controller::Button) -> bool {
+        self.buttons.held[button as usize]
+    }
+
+    pub fn is_button_pressed(&self, button: controller::Button) -> bool {
+        self.buttons.pressed[button as usize]
+    }
+
+    pub fn is_button_released(&self, button: controller::Button) -> bool {
+        self.buttons.released[button as usize]
+    }
+
+    pub fn axis(&self, axis: controller::Axis) -> i16 {
+        self.axes[axis as usize]
+    }
+}
+
+/// Polled view of keyboard, mouse and controller input.
+///
+/// Feed it every event with `handle_event` and call `end_frame` once all of a frame's
+/// updates have run; "pressed" and "released" then mean "since the previous `end_frame`".
+#[derive(Clone)]
+pub struct InputState {
+    keys: Buttons,
+    scancodes: Buttons,
+    mouse_buttons: Buttons,
+    mouse_position: (i32, i32),
+    mouse_delta: (i32, i32),
+    wheel_delta: (i32, i32),
+    controllers: HashMap<u32, ControllerState>,
+}
+
+impl InputState {
+    pub fn new() -> InputState {
+        InputState {
+            keys: Buttons::new(KEY_CODE_COUNT),
+            scancodes: Buttons::new(SCAN_CODE_COUNT),
+            mouse_buttons: Buttons::new(MOUSE_BUTTON_COUNT),
+            mouse_position: (0, 0),
+            mouse_delta: (0, 0),
+            wheel_delta: (0, 0),
+            controllers: HashMap::new(),
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::KeyDown { keycode, scancode, .. } => {
+                if let Some(keycode) = keycode {
+                    self.keys.press(*keycode as usize);
+                }
+
+                if let Some(scancode) = scancode {
+                    self.scancodes.press(*scancode as usize);
+                }
+            },
+            Event::KeyUp { keycode, scancode, .. } => {
+                if let Some(keycode) = keycode {
+                    self.keys.release(*keycode as usize);
+                }
+
+                if let Some(scancode) = scancode {
+                    self.scancodes.release(*scancode as usize);
+                }
+            },
+            Event::MouseMotion { x, y, xrel, yrel, .. } => {
+                self.mouse_position = (*x, *y);
+                self.mouse_delta = (self.mouse_delta.0 + xrel, self.mouse_delta.1 + yrel);
+            },
+            Event::MouseButtonDown { mouse_button, x, y, .. } => {
+                self.mouse_position = (*x, *y);
+                self.mouse_buttons.press(*mouse_button as usize);
+            },
+            Event::MouseButtonUp { mouse_button, x, y, .. } => {
+                self.mouse_position = (*x, *y);
+                self.mouse_buttons.release(*mouse_button as usize);
+            },
+            Event::MouseWheel { x, y, direction, .. } => {
+                let sign = if let mouse::WheelDirection::Flipped = direction { -1 } else { 1 };
+                self.wheel_delta = (self.wheel_delta.0 + x * sign, self.wheel_delta.1 + y * sign);
+            },
+            Event::ControllerDeviceRemoved { which } => {
+                self.controllers.remove(which);
+            },
+            Event::ControllerButtonDown { which, button } => {
+                self.controllers.entry(*which).or_insert_with(ControllerState::new).buttons.press(*button as usize);
+            },
+            Event::ControllerButtonUp { which, button } => {
+                self.controllers.entry(*which).or_insert_with(ControllerState::new).buttons.release(*button as usize);
+            },
+            Event::ControllerAxisMotion { which, axis, value } => {
+                self.controllers.entry(*which).or_insert_with(ControllerState::new).axes[*axis as usize] = *value;
+            },
+            Event::Window { window_event: WindowEvent::FocusLost, .. } => {
+                // Key up events for keys held while focus is lost never arrive
+                self.keys.release_all();
+                self.scancodes.release_all();
+                self.mouse_buttons.release_all();
+            },
+            _ => {}
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.keys.end_frame();
+        self.scancodes.end_frame();
+        self.mouse_buttons.end_frame();
+        self.mouse_delta = (0, 0);
+        self.wheel_delta = (0, 0);
+
+        for controller in self.controllers.values_mut() {
+            controller.buttons.end_frame();
+        }
+    }
+
+    pub fn is_key_held(&self, keycode: keyboard::KeyCode) -> bool {
+        self.keys.held[keycode as usize]
+    }
+
+    pub fn is_key_pressed(&self, keycode: keyboard::KeyCode) -> bool {
+        self.keys.pressed[keycode as usize]
+    }
+
+    pub fn is_key_released(&self, keycode: keyboard::KeyCode) -> bool {
+        self.keys.released[keycode as usize]
+    }
+
+    pub fn is_scancode_held(&self, scancode: keyboard::ScanCode) -> bool {
+        self.scancodes.held[scancode as usize]
+    }
+
+    pub fn is_scancode_pressed(&self, scancode: keyboard::ScanCode) -> bool {
+        self.scancodes.pressed[scancode as usize]
+    }
+
+    pub fn is_scancode_released(&self, scancode: keyboard::ScanCode) -> bool {
+        self.scancodes.released[scancode as usize]
+    }
+
+    pub fn is_mouse_button_held(&self, button: mouse::Button) -> bool {
+        self.mouse_buttons.held[button as usize]
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: mouse::Button) -> bool {
+        self.mouse_buttons.pressed[button as usize]
+    }
+
+    pub fn is_mouse_button_released(&self, button: mouse::Button) -> bool {
+        self.mouse_buttons.released[button as usize]
+    }
+
+    pub fn mouse_position(&self) -> (i32, i32) {
+        self.mouse_position
+    }
+
+    pub fn mouse_delta(&self) -> (i32, i32) {
+        self.mouse_delta
+    }
+
+    pub fn wheel_delta(&self) -> (i32, i32) {
+        self.wheel_delta
+    }
+
+    /// State of the controller with joystick instance id `which`. A controller shows up once
+    /// it sends its first button or axis event; `ControllerDeviceAdded` carries a device index
+    /// rather than an instance id, so it can't be used to track controllers here.
+    pub fn controller(&self, which: u32) -> Option<&ControllerState> {
+        self.controllers.get(&which)
+    }
+
+    pub fn controllers(&self) -> impl Iterator<Item = (&u32, &ControllerState)> {
+        self.controllers.iter()
+    }
+}
+
+impl Default for InputState {
+    fn default() -> InputState {
+        InputState::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_down(keycode: keyboard::KeyCode) -> Event {
+        Event::KeyDown {
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            key_mod: keyboard::Mod::NONE,
+            repeat: false,
+        }
+    }
+
+    fn key_up(keycode: keyboard::KeyCode) -> Event {
+        Event::KeyUp {
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            key_mod: keyboard::Mod::NONE,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn key_edges() {
+        let mut input = InputState::new();
+
+        input.handle_event(&key_down(keyboard::KeyCode::Space));
+
+        assert!(input.is_key_held(keyboard::KeyCode::Space));
+        assert!(input.is_key_pressed(keyboard::KeyCode::Space));
+        assert!(!input.is_key_held(keyboard::KeyCode::Return));
+
+        input.end_frame();
+        input.handle_event(&key_down(keyboard::KeyCode::Space));
+
+        assert!(input.is_key_held(keyboard::KeyCode::Space));
+        assert!(!input.is_key_pressed(keyboard::KeyCode::Space));
+
+        input.end_frame();
+        input.handle_event(&key_up(keyboard::KeyCode::Space));
+
+        assert!(!input.is_key_held(keyboard::KeyCode::Space));
+        assert!(input.is_key_released(keyboard::KeyCode::Space));
+
+        input.end_frame();
+
+        assert!(!input.is_key_released(keyboard::KeyCode::Space));
+    }
+
+    #[test]
+    fn press_and_release_in_one_frame() {
+        let mut input = InputState::new();
+
+        input.handle_event(&key_down(keyboard::KeyCode::Sleep));
+        input.handle_event(&key_up(keyboard::KeyCode::Sleep));
+
+        assert!(!input.is_key_held(keyboard::KeyCode::Sleep));
+        assert!(input.is_key_pressed(keyboard::KeyCode::Sleep));
+        assert!(input.is_key_released(keyboard::KeyCode::Sleep));
+    }
+
+    #[test]
+    fn scancode() {
+        let mut input = InputState::new();
+
+        input.handle_event(&Event::KeyDown {
+            window_id: 0,
+            keycode: None,
+            scancode: Some(keyboard::ScanCode::Num),
+            key_mod: keyboard::Mod::NONE,
+            repeat: false,
+        });
+
+        assert!(input.is_scancode_held(keyboard::ScanCode::Num));
+        assert!(input.is_scancode_pressed(keyboard::ScanCode::Num));
+    }
+
+    #[test]
+    fn mouse() {
+        let mut input = InputState::new();
+        let mouse_state = mouse::State::from(sdl2::mouse::MouseState::from_sdl_state(0));
+
+        input.handle_event(&Event::MouseMotion { window_id: 0, which: 0, mouse_state, x: 10, y: 10, xrel: 2, yrel: 3 });
+        input.handle_event(&Event::MouseMotion { window_id: 0, which: 0, mouse_state, x: 12, y: 11, xrel: 2, yrel: 1 });
+        input.handle_event(&Event::MouseButtonDown { window_id: 0, which: 0, mouse_button: mouse::Button::Right, clicks: 1, x: 12, y: 11 });
+        input.handle_event(&Event::MouseWheel { window_id: 0, which: 0, x: 0, y: 1, direction: mouse::WheelDirection::Flipped });
+
+        assert_eq!(input.mouse_position(), (12, 11));
+        assert_eq!(input.mouse_delta(), (4, 4));
+        assert_eq!(input.wheel_delta(), (0, -1));
+        assert!(input.is_mouse_button_pressed(mouse::Button::Right));
+
+        input.end_frame();
+
+        assert_eq!(input.mouse_position(), (12, 11));
+        assert_eq!(input.mouse_delta(), (0, 0));
+        assert_eq!(input.wheel_delta(), (0, 0));
+        assert!(input.is_mouse_button_held(mouse::Button::Right));
+    }
+
+    #[test]
+    fn controllers() {
+        let mut input = InputState::new();
+
+        // Device index 0, which SDL gives instance id 1
+        input.handle_event(&Event::ControllerDeviceAdded { which: 0 });
+        input.handle_event(&Event::ControllerButtonDown { which: 1, button: controller::Button::A });
+        input.handle_event(&Event::ControllerAxisMotion { which: 2, axis: controller::Axis::LeftY, value: -300 });
+
+        assert!(input.controller(1).unwrap().is_button_pressed(controller::Button::A));
+        assert!(!input.controller(1).unwrap().is_button_held(controller::Button::B));
+        assert_eq!(input.controller(2).unwrap().axis(controller::Axis::LeftY), -300);
+
+        input.handle_event(&Event::ControllerDeviceRemoved { which: 1 });
+
+        assert!(input.controller(1).is_none());
+        assert!(input.controller(0).is_none());
+        assert_eq!(input.controllers().count(), 1);
+    }
+
+    #[test]
+    fn focus_lost() {
+        let mut input = InputState::new();
+
+        input.handle_event(&key_down(keyboard::KeyCode::W));
+        input.end_frame();
+        input.handle_event(&Event::Window { window_id: 0, window_event: WindowEvent::FocusLost });
+
+        assert!(!input.is_key_held(keyboard::KeyCode::W));
+        assert!(input.is_key_released(keyboard::KeyCode::W));
+    }
+}
@@ -0,0 +1,474 @@
+use sdl2::clipboard::ClipboardUtil;
+use sdl2::keyboard::TextInputUtil;
+use sdl2::rect::Rect;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::core::event::Event;
+use crate::input::keyboard::{ KeyCode, Mod };
+
+/// Text the IME is still composing, before it's committed as `Event::TextInput`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Composition {
+    pub text: String,
+    /// Cursor position within `text`, in characters.
+    pub cursor: usize,
+    /// Number of characters selected from `cursor`.
+    pub selection: usize,
+}
+
+/// Switches SDL text input on and off and tracks IME composition and the clipboard.
+///
+/// `Game::run` attaches SDL to the service returned from `Game::text_input` and keeps it fed
+/// with events. Without SDL it still tracks composition, and the clipboard is kept in memory.
+pub struct TextInput {
+    util: Option<TextInputUtil>,
+    clipboard: Option<ClipboardUtil>,
+    active: bool,
+    composition: Option<Composition>,
+    local_clipboard: String,
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        TextInput {
+            util: None,
+            clipboard: None,
+            active: false,
+            composition: None,
+            local_clipboard: String::new(),
+        }
+    }
+
+    pub fn attach(&mut self, util: TextInputUtil, clipboard: ClipboardUtil) {
+        // SDL starts with text input enabled; keep it in line with what the game asked for
+        if self.active { util.start() } else { util.stop() }
+
+        self.util = Some(util);
+        self.clipboard = Some(clipboard);
+    }
+
+    pub fn start(&mut self) {
+        self.active = true;
+
+        if let Some(x) = &self.util {
+            x.start();
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+        self.composition = None;
+
+        if let Some(x) = &self.util {
+            x.stop();
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Tells the IME where the text being edited is, in window coordinates, so its candidate
+    /// list can be shown next to it.
+    pub fn set_candidate_rect(&self, x: i32, y: i32, width: u32, height: u32) {
+        if let Some(util) = &self.util {
+            util.set_rect(Rect::new(x, y, width, height));
+        }
+    }
+
+    pub fn composition(&self) -> Option<&Composition> {
+        self.composition.as_ref()
+    }
+
+    pub fn clipboard_text(&self) -> String {
+        match &self.clipboard {
+            Some(x) => x.clipboard_text().unwrap_or_default(),
+            None => self.local_clipboard.clone(),
+        }
+    }
+
+    pub fn set_clipboard_text(&mut self, text: &str) {
+        match &self.clipboard {
+            Some(x) => {
+                let _ = x.set_clipboard_text(text);
+            },
+            None => self.local_clipboard = String::from(text),
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::TextEditing { text, start, length, .. } => {
+                self.composition = if text.is_empty() {
+                    None
+                } else {
+                    Some(Composition {
+                        text: text.clone(),
+                        cursor: (*start).max(0) as usize,
+                        selection: (*length).max(0) as usize,
+                    })
+                };
+            },
+            Event::TextInput { .. } => self.composition = None,
+            _ => {}
+        }
+    }
+}
+
+impl Default for TextInput {
+    fn default() -> TextInput {
+        TextInput::new()
+    }
+}
+
+/// Editable single-line text with a caret and selection, moved and deleted by grapheme
+/// so combined characters and emoji stay intact.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextBuffer {
+    text: String,
+    /// Byte offset of the caret, always on a grapheme boundary.
+    caret: usize,
+    /// Byte offset of the other end of the selection, if any.
+    anchor: Option<usize>,
+}
+
+impl TextBuffer {
+    pub fn new() -> TextBuffer {
+        TextBuffer::default()
+    }
+
+    pub fn from(text: &str) -> TextBuffer {
+        TextBuffer {
+            text: String::from(text),
+            caret: text.len(),
+            anchor: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Selected byte range, ordered.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        match self.anchor {
+            Some(x) if x != self.caret => Some((x.min(self.caret), x.max(self.caret))),
+            _ => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> &str {
+        match self.selection() {
+            Some((start, end)) => &self.text[start..end],
+            None => "",
+        }
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = String::from(text);
+        self.caret = self.text.len();
+        self.anchor = None;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.caret = self.text.len();
+    }
+
+    /// Replaces the selection, if any, with `text` and moves the caret after it.
+    pub fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        self.text.insert_str(self.caret, text);
+        self.caret += text.len();
+    }
+
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() {
+            let start = self.previous_grapheme();
+            self.text.replace_range(start..self.caret, "");
+            self.caret = start;
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if !self.delete_selection() {
+            let end = self.next_grapheme();
+            self.text.replace_range(self.caret..end, "");
+        }
+    }
+
+    pub fn delete_word_left(&mut self) {
+        if !self.delete_selection() {
+            let start = self.previous_word();
+            self.text.replace_range(start..self.caret, "");
+            self.caret = start;
+        }
+    }
+
+    pub fn move_left(&mut self, select: bool) {
+        match self.selection() {
+            Some((start, _)) if !select => self.move_to(start, false),
+            _ => self.move_to(self.previous_grapheme(), select),
+        }
+    }
+
+    pub fn move_right(&mut self, select: bool) {
+        match self.selection() {
+            Some((_, end)) if !select => self.move_to(end, false),
+            _ => self.move_to(self.next_grapheme(), select),
+        }
+    }
+
+    pub fn move_word_left(&mut self, select: bool) {
+        self.move_to(self.previous_word(), select);
+    }
+
+    pub fn move_word_right(&mut self, select: bool) {
+        self.move_to(self.next_word(), select);
+    }
+
+    pub fn move_home(&mut self, select: bool) {
+        self.move_to(0, select);
+    }
+
+    pub fn move_end(&mut self, select: bool) {
+        self.move_to(self.text.len(), select);
+    }
+
+    pub fn copy(&self, text_input: &mut TextInput) {
+        if self.selection().is_some() {
+            text_input.set_clipboard_text(self.selected_text());
+        }
+    }
+
+    pub fn cut(&mut self, text_input: &mut TextInput) {
+        self.copy(text_input);
+        self.delete_selection();
+    }
+
+    pub fn paste(&mut self, text_input: &TextInput) {
+        // Single line, so pasted line breaks become spaces
+        let text: String = text_input.clipboard_text().chars().map(|x| if x == '\n' || x == '\r' { ' ' } else { x }).collect();
+        self.insert(&text);
+    }
+
+    /// Applies committed text and editing keys, returning whether the event was used.
+    pub fn handle_event(&mut self, event: &Event, text_input: &mut TextInput) -> bool {
+        let (keycode, key_mod) = match event {
+            Event::TextInput { text, .. } => {
+                self.insert(text);
+                return true;
+            },
+            Event::KeyDown { keycode: Some(keycode), key_mod, .. } => (*keycode, *key_mod),
+            _ => return false,
+        };
+
+        let select = key_mod.intersects(Mod::SHIFT);
+        let word = key_mod.intersects(Mod::CONTROL) || key_mod.intersects(Mod::ALT);
+        let command = key_mod.intersects(Mod::CONTROL) || key_mod.intersects(Mod::GUI);
+
+        match keycode {
+            KeyCode::Left if word       => self.move_word_left(select),
+            KeyCode::Left               => self.move_left(select),
+            KeyCode::Right if word      => self.move_word_right(select),
+            KeyCode::Right              => self.move_right(select),
+            KeyCode::Home               => self.move_home(select),
+            KeyCode::End                => self.move_end(select),
+            KeyCode::Backspace if word  => self.delete_word_left(),
+            KeyCode::Backspace          => self.backspace(),
+            KeyCode::Delete             => self.delete(),
+            KeyCode::A if command       => self.select_all(),
+            KeyCode::C if command       => self.copy(text_input),
+            KeyCode::X if command       => self.cut(text_input),
+            KeyCode::V if command       => self.paste(text_input),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn move_to(&mut self, position: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.caret);
+        } else {
+            self.anchor = None;
+        }
+
+        self.caret = position;
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let selection = self.selection();
+        self.anchor = None;
+
+        match selection {
+            Some((start, end)) => {
+                self.text.replace_range(start..end, "");
+                self.caret = start;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn previous_grapheme(&self) -> usize {
+        self.text[..self.caret].grapheme_indices(true).next_back().map(|(i, _)| i).unwrap_or(0)
+    }
+
+    fn next_grapheme(&self) -> usize {
+        self.text[self.caret..].graphemes(true).next().map(|x| self.caret + x.len()).unwrap_or(self.caret)
+    }
+
+    /// Start of the word before the caret, skipping any whitespace and punctuation first.
+    fn previous_word(&self) -> usize {
+        self.text[..self.caret]
+            .unicode_word_indices()
+            .next_back()
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    /// End of the word after the caret, skipping any whitespace and punctuation first.
+    fn next_word(&self) -> usize {
+        self.text[self.caret..]
+            .unicode_word_indices()
+            .next()
+            .map(|(i, x)| self.caret + i + x.len())
+            .unwrap_or_else(|| self.text.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(keycode: KeyCode, key_mod: Mod) -> Event {
+        Event::KeyDown { window_id: 0, keycode: Some(keycode), scancode: None, key_mod, repeat: false }
+    }
+
+    #[test]
+    fn composition() {
+        let mut text_input = TextInput::new();
+
+        text_input.handle_event(&Event::TextEditing { window_id: 0, text: String::from("にほ"), start: 2, length: 0 });
+
+        assert_eq!(text_input.composition(), Some(&Composition { text: String::from("にほ"), cursor: 2, selection: 0 }));
+
+        text_input.handle_event(&Event::TextInput { window_id: 0, text: String::from("日本") });
+
+        assert_eq!(text_input.composition(), None);
+    }
+
+    #[test]
+    fn start_and_stop() {
+        let mut text_input = TextInput::new();
+        text_input.start();
+
+        assert!(text_input.is_active());
+
+        text_input.handle_event(&Event::TextEditing { window_id: 0, text: String::from("a"), start: 1, length: 0 });
+        text_input.stop();
+
+        assert!(!text_input.is_active());
+        assert_eq!(text_input.composition(), None);
+    }
+
+    #[test]
+    fn insert_and_delete_graphemes() {
+        let mut buffer = TextBuffer::from("ae\u{301}👍🏽");
+
+        buffer.backspace();
+
+        assert_eq!(buffer.text(), "ae\u{301}");
+
+        buffer.move_left(false);
+        buffer.insert("x");
+
+        assert_eq!(buffer.text(), "axe\u{301}");
+
+        buffer.delete();
+
+        assert_eq!(buffer.text(), "ax");
+        assert_eq!(buffer.caret(), 2);
+    }
+
+    #[test]
+    fn word_jumps() {
+        let mut buffer = TextBuffer::from("hello big  world");
+
+        buffer.move_word_left(false);
+
+        assert_eq!(buffer.caret(), 11);
+
+        buffer.move_word_left(false);
+
+        assert_eq!(buffer.caret(), 6);
+
+        buffer.move_word_right(true);
+
+        assert_eq!(buffer.selected_text(), "big");
+
+        buffer.move_home(false);
+        buffer.move_word_right(false);
+
+        assert_eq!(buffer.caret(), 5);
+    }
+
+    #[test]
+    fn selection() {
+        let mut buffer = TextBuffer::from("abcdef");
+
+        buffer.move_left(true);
+        buffer.move_left(true);
+
+        assert_eq!(buffer.selected_text(), "ef");
+
+        buffer.insert("Z");
+
+        assert_eq!(buffer.text(), "abcdZ");
+        assert_eq!(buffer.selection(), None);
+
+        buffer.move_home(true);
+        buffer.move_right(false);
+
+        assert_eq!(buffer.caret(), 5);
+    }
+
+    #[test]
+    fn clipboard() {
+        let mut text_input = TextInput::new();
+        let mut buffer = TextBuffer::new();
+
+        buffer.handle_event(&Event::TextInput { window_id: 0, text: String::from("copy me") }, &mut text_input);
+        buffer.handle_event(&key(KeyCode::A, Mod::LCONTROL), &mut text_input);
+        buffer.handle_event(&key(KeyCode::X, Mod::LCONTROL), &mut text_input);
+
+        assert_eq!(buffer.text(), "");
+        assert_eq!(text_input.clipboard_text(), "copy me");
+
+        buffer.handle_event(&key(KeyCode::V, Mod::RGUI), &mut text_input);
+        buffer.handle_event(&key(KeyCode::V, Mod::RGUI), &mut text_input);
+
+        assert_eq!(buffer.text(), "copy mecopy me");
+    }
+
+    #[test]
+    fn editing_keys() {
+        let mut text_input = TextInput::new();
+        let mut buffer = TextBuffer::from("one two");
+
+        assert!(buffer.handle_event(&key(KeyCode::Backspace, Mod::LCONTROL), &mut text_input));
+        assert_eq!(buffer.text(), "one ");
+
+        assert!(buffer.handle_event(&key(KeyCode::Left, Mod::LSHIFT), &mut text_input));
+        assert_eq!(buffer.selected_text(), " ");
+
+        assert!(buffer.handle_event(&key(KeyCode::Delete, Mod::NONE), &mut text_input));
+        assert_eq!(buffer.text(), "one");
+
+        assert!(!buffer.handle_event(&key(KeyCode::F1, Mod::NONE), &mut text_input));
+    }
+}
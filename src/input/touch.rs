@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use crate::core::event::Event;
+use crate::input::mouse;
+
+/// Mouse id SDL uses for mouse events synthesised from touches.
+pub const TOUCH_MOUSE_ID: u32 = u32::MAX;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SwipeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Gestures recognised by `TouchTracker`. Positions are normalised to `[0, 1]` like SDL's.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Gesture {
+    Tap {
+        x: f32,
+        y: f32,
+    },
+    DoubleTap {
+        x: f32,
+        y: f32,
+    },
+    LongPress {
+        x: f32,
+        y: f32,
+    },
+    Swipe {
+        direction: SwipeDirection,
+        dx: f32,
+        dy: f32,
+    },
+    /// Two fingers moving apart (`scale > 1`) or together (`scale < 1`) since the last pinch.
+    Pinch {
+        scale: f32,
+        x: f32,
+        y: f32,
+    },
+    /// Two fingers turning around their centre, `angle` in radians since the last rotation.
+    Rotate {
+        angle: f32,
+        x: f32,
+        y: f32,
+    },
+}
+
+/// Thresholds for gesture recognition. Distances are in normalised units and times in seconds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TouchConfig {
+    pub tap_max_duration: f32,
+    pub tap_max_distance: f32,
+    pub double_tap_interval: f32,
+    pub long_press_duration: f32,
+    pub swipe_min_distance: f32,
+    pub swipe_max_duration: f32,
+    /// Relative change in the distance between two fingers before it counts as a pinch.
+    pub pinch_min_scale: f32,
+    /// Radians two fingers must turn before it counts as a rotation.
+    pub rotate_min_angle: f32,
+}
+
+impl Default for TouchConfig {
+    fn default() -> TouchConfig {
+        TouchConfig {
+            tap_max_duration: 0.25,
+            tap_max_distance: 0.02,
+            double_tap_interval: 0.3,
+            long_press_duration: 0.5,
+            swipe_min_distance: 0.1,
+            swipe_max_duration: 0.5,
+            pinch_min_scale: 0.05,
+            rotate_min_angle: 0.05,
+        }
+    }
+}
+
+/// A finger currently on the touch device.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Finger {
+    pub touch_id: i64,
+    pub finger_id: i64,
+    pub x: f32,
+    pub y: f32,
+    pub start_x: f32,
+    pub start_y: f32,
+    pub pressure: f32,
+    /// Seconds since the finger went down.
+    pub held_for: f32,
+    long_pressed: bool,
+}
+
+impl Finger {
+    fn travelled(&self) -> f32 {
+        ((self.x - self.start_x).powi(2) + (self.y - self.start_y).powi(2)).sqrt()
+    }
+}
+
+/// Tracks active fingers from `Finger*` events and derives gestures from them.
+///
+/// Feed it every event with `handle_event` and advance its clock with `update`, which is what
+/// lets long presses and double taps be detected. Recognised gestures collect until `take_gestures`.
+pub struct TouchTracker {
+    config: TouchConfig,
+    fingers: HashMap<i64, Finger>,
+    /// Most fingers down at once since the last time none were.
+    session_fingers: usize,
+    time: f32,
+    last_tap: Option<(f32, f32, f32)>,
+    gestures: Vec<Gesture>,
+    /// Distance and angle between the two fingers down when the last pinch and rotation fired.
+    span: Option<(f32, f32)>,
+    mouse_emulation: Option<(f32, f32)>,
+    primary: Option<i64>,
+    last_mouse: Option<(i32, i32)>,
+    mouse_events: Vec<Event>,
+}
+
+impl Default for TouchTracker {
+    fn default() -> TouchTracker {
+        TouchTracker::new(TouchConfig::default())
+    }
+}
+
+impl TouchTracker {
+    pub fn new(config: TouchConfig) -> TouchTracker {
+        TouchTracker {
+            config,
+            fingers: HashMap::new(),
+            session_fingers: 0,
+            time: 0.,
+            last_tap: None,
+            gestures: Vec::new(),
+            span: None,
+            mouse_emulation: None,
+            primary: None,
+            last_mouse: None,
+            mouse_events: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &TouchConfig {
+        &self.config
+    }
+
+    /// Makes the first finger down also produce mouse events, scaled to a window of the
+    /// given size. Collect them with `take_mouse_events`.
+    ///
+    /// Off by default: SDL already synthesises mouse events from touches unless the
+    /// `SDL_TOUCH_MOUSE_EVENTS` hint is `0`, so only turn this on when that hint is.
+    pub fn set_mouse_emulation(&mut self, window_size: Option<(u32, u32)>) {
+        self.mouse_emulation = window_size.map(|(w, h)| (w as f32, h as f32));
+    }
+
+    pub fn finger(&self, finger_id: i64) -> Option<&Finger> {
+        self.fingers.get(&finger_id)
+    }
+
+    pub fn fingers(&self) -> impl Iterator<Item = &Finger> {
+        self.fingers.values()
+    }
+
+    pub fn finger_count(&self) -> usize {
+        self.fingers.len()
+    }
+
+    pub fn take_gestures(&mut self) -> Vec<Gesture> {
+        std::mem::take(&mut self.gestures)
+    }
+
+    pub fn take_mouse_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.mouse_events)
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+
+        let single = self.session_fingers == 1;
+
+        for finger in self.fingers.values_mut() {
+            finger.held_for += dt;
+
+            if single && !finger.long_pressed && finger.held_for >= self.config.long_press_duration && finger.travelled() <= self.config.tap_max_distance {
+                finger.long_pressed = true;
+                self.gestures.push(Gesture::LongPress { x: finger.x, y: finger.y });
+            }
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::FingerDown { touch_id, finger_id, x, y, pressure, .. } => {
+                self.fingers.insert(finger_id, Finger {
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    start_x: x,
+                    start_y: y,
+                    pressure,
+                    held_for: 0.,
+                    long_pressed: false,
+                });
+
+                self.session_fingers = self.session_fingers.max(self.fingers.len());
+                self.span = self.pair().map(span);
+
+                if self.primary.is_none() {
+                    self.primary = Some(finger_id);
+                    self.emulate_mouse(x, y, Some(true));
+                }
+            },
+            Event::FingerMotion { finger_id, x, y, pressure, .. } => {
+                if let Some(finger) = self.fingers.get_mut(&finger_id) {
+                    finger.x = x;
+                    finger.y = y;
+                    finger.pressure = pressure;
+                }
+
+                if let Some(pair) = self.pair() {
+                    self.two_finger_gestures(pair);
+                }
+
+                if self.primary == Some(finger_id) {
+                    self.emulate_mouse(x, y, None);
+                }
+            },
+            Event::FingerUp { finger_id, x, y, .. } => {
+                if let Some(mut finger) = self.fingers.remove(&finger_id) {
+                    finger.x = x;
+                    finger.y = y;
+                    self.finger_up(&finger);
+                }
+
+                self.span = self.pair().map(span);
+
+                if self.primary == Some(finger_id) {
+                    self.primary = None;
+                    self.emulate_mouse(x, y, Some(false));
+                }
+
+                if self.fingers.is_empty() {
+                    self.session_fingers = 0;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn finger_up(&mut self, finger: &Finger) {
+        if self.session_fingers != 1 || finger.long_pressed {
+            return;
+        }
+
+        let dx = finger.x - finger.start_x;
+        let dy = finger.y - finger.start_y;
+        let distance = finger.travelled();
+
+        if finger.held_for <= self.config.tap_max_duration && distance <= self.config.tap_max_distance {
+            self.gestures.push(Gesture::Tap { x: finger.x, y: finger.y });
+
+            let double = match self.last_tap {
+                Some((time, x, y)) => {
+                    self.time - time <= self.config.double_tap_interval
+                        && ((finger.x - x).powi(2) + (finger.y - y).powi(2)).sqrt() <= self.config.tap_max_distance * 2.
+                },
+                None => false,
+            };
+
+            if double {
+                self.gestures.push(Gesture::DoubleTap { x: finger.x, y: finger.y });
+                self.last_tap = None;
+            } else {
+                self.last_tap = Some((self.time, finger.x, finger.y));
+            }
+        } else if finger.held_for <= self.config.swipe_max_duration && distance >= self.config.swipe_min_distance {
+            let direction = if dx.abs() >= dy.abs() {
+                if dx > 0. { SwipeDirection::Right } else { SwipeDirection::Left }
+            } else if dy > 0. {
+                SwipeDirection::Down
+            } else {
+                SwipeDirection::Up
+            };
+
+            self.gestures.push(Gesture::Swipe { direction, dx, dy });
+        }
+    }
+
+    /// Positions of the two fingers down, if exactly two are.
+    fn pair(&self) -> Option<((f32, f32), (f32, f32))> {
+        if self.fingers.len() != 2 {
+            return None;
+        }
+
+        let mut fingers: Vec<&Finger> = self.fingers.values().collect();
+        fingers.sort_by_key(|x| x.finger_id);
+
+        Some(((fingers[0].x, fingers[0].y), (fingers[1].x, fingers[1].y)))
+    }
+
+    fn two_finger_gestures(&mut self, pair: ((f32, f32), (f32, f32))) {
+        let (distance, angle) = span(pair);
+        let (base_distance, base_angle) = match self.span {
+            Some(x) => x,
+            None => {
+                self.span = Some((distance, angle));
+                return;
+            },
+        };
+
+        let x = ((pair.0).0 + (pair.1).0) / 2.;
+        let y = ((pair.0).1 + (pair.1).1) / 2.;
+
+        if base_distance > 0. && (distance / base_distance - 1.).abs() >= self.config.pinch_min_scale {
+            self.gestures.push(Gesture::Pinch { scale: distance / base_distance, x, y });
+            self.span = Some((distance, base_angle));
+        }
+
+        let mut turned = angle - base_angle;
+
+        if turned > std::f32::consts::PI {
+            turned -= 2. * std::f32::consts::PI;
+        } else if turned < -std::f32::consts::PI {
+            turned += 2. * std::f32::consts::PI;
+        }
+
+        if turned.abs() >= self.config.rotate_min_angle {
+            self.gestures.push(Gesture::Rotate { angle: turned, x, y });
+            self.span = self.span.map(|(distance, _)| (distance, angle));
+        }
+    }
+
+    /// `pressed` is `Some` for button changes and `None` for motion.
+    fn emulate_mouse(&mut self, x: f32, y: f32, pressed: Option<bool>) {
+        let (width, height) = match self.mouse_emulation {
+            Some(x) => x,
+            None => return,
+        };
+
+        let x = (x * width) as i32;
+        let y = (y * height) as i32;
+
+        let event = match pressed {
+            Some(true) => Event::MouseButtonDown { window_id: 0, which: TOUCH_MOUSE_ID, mouse_button: mouse::Button::Left, clicks: 1, x, y },
+            Some(false) => Event::MouseButtonUp { window_id: 0, which: TOUCH_MOUSE_ID, mouse_button: mouse::Button::Left, clicks: 1, x, y },
+            None => {
+                let (last_x, last_y) = self.last_mouse.unwrap_or((x, y));
+                let mouse_state = mouse::State::from_buttons(&[mouse::Button::Left]);
+
+                Event::MouseMotion { window_id: 0, which: TOUCH_MOUSE_ID, mouse_state, x, y, xrel: x - last_x, yrel: y - last_y }
+            },
+        };
+
+        self.last_mouse = Some((x, y));
+        self.mouse_events.push(event);
+    }
+}
+
+/// Distance and angle from the first finger of a pair to the second.
+fn span(((ax, ay), (bx, by)): ((f32, f32), (f32, f32))) -> (f32, f32) {
+    let (dx, dy) = (bx - ax, by - ay);
+
+    ((dx * dx + dy * dy).sqrt(), dy.atan2(dx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down(finger_id: i64, x: f32, y: f32) -> Event {
+        Event::FingerDown { touch_id: 0, finger_id, x, y, dx: 0., dy: 0., pressure: 1. }
+    }
+
+    fn motion(finger_id: i64, x: f32, y: f32) -> Event {
+        Event::FingerMotion { touch_id: 0, finger_id, x, y, dx: 0., dy: 0., pressure: 1. }
+    }
+
+    fn up(finger_id: i64, x: f32, y: f32) -> Event {
+        Event::FingerUp { touch_id: 0, finger_id, x, y, dx: 0., dy: 0., pressure: 0. }
+    }
+
+    #[test]
+    fn fingers() {
+        let mut touch = TouchTracker::default();
+
+        touch.handle_event(&down(1, 0.1, 0.2));
+        touch.handle_event(&down(2, 0.5, 0.5));
+        touch.handle_event(&motion(1, 0.15, 0.25));
+
+        assert_eq!(touch.finger_count(), 2);
+        assert_eq!(touch.finger(1).map(|x| (x.x, x.y, x.start_x)), Some((0.15, 0.25, 0.1)));
+
+        touch.handle_event(&up(1, 0.15, 0.25));
+
+        assert!(touch.finger(1).is_none());
+        assert_eq!(touch.finger_count(), 1);
+    }
+
+    #[test]
+    fn tap_and_double_tap() {
+        let mut touch = TouchTracker::default();
+
+        touch.handle_event(&down(1, 0.5, 0.5));
+        touch.update(0.1);
+        touch.handle_event(&up(1, 0.5, 0.5));
+
+        assert_eq!(touch.take_gestures(), vec![Gesture::Tap { x: 0.5, y: 0.5 }]);
+
+        touch.update(0.1);
+        touch.handle_event(&down(2, 0.51, 0.5));
+        touch.update(0.05);
+        touch.handle_event(&up(2, 0.51, 0.5));
+
+        assert_eq!(touch.take_gestures(), vec![Gesture::Tap { x: 0.51, y: 0.5 }, Gesture::DoubleTap { x: 0.51, y: 0.5 }]);
+    }
+
+    #[test]
+    fn long_press() {
+        let mut touch = TouchTracker::default();
+
+        touch.handle_event(&down(1, 0.5, 0.5));
+        touch.update(0.3);
+
+        assert!(touch.take_gestures().is_empty());
+
+        touch.update(0.3);
+        touch.update(0.3);
+        touch.handle_event(&up(1, 0.5, 0.5));
+
+        assert_eq!(touch.take_gestures(), vec![Gesture::LongPress { x: 0.5, y: 0.5 }]);
+    }
+
+    #[test]
+    fn swipe() {
+        let mut touch = TouchTracker::default();
+
+        touch.handle_event(&down(1, 0.5, 0.8));
+        touch.update(0.1);
+        touch.handle_event(&motion(1, 0.5, 0.5));
+        touch.update(0.1);
+        touch.handle_event(&up(1, 0.55, 0.3));
+
+        match touch.take_gestures().as_slice() {
+            [Gesture::Swipe { direction: SwipeDirection::Up, .. }] => {},
+            x => panic!("expected an upwards swipe, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn pinch_and_rotate() {
+        let mut touch = TouchTracker::default();
+
+        touch.handle_event(&down(1, 0.25, 0.5));
+        touch.handle_event(&down(2, 0.5, 0.5));
+        touch.handle_event(&motion(2, 0.505, 0.5));
+        touch.handle_event(&motion(2, 0.505, 0.502));
+
+        assert!(touch.take_gestures().is_empty());
+
+        touch.handle_event(&motion(2, 0.75, 0.5));
+
+        assert_eq!(touch.take_gestures(), vec![Gesture::Pinch { scale: 2., x: 0.5, y: 0.5 }]);
+
+        touch.handle_event(&motion(2, 0.25, 1.));
+
+        match touch.take_gestures().as_slice() {
+            [Gesture::Rotate { angle, .. }] => assert!((angle - std::f32::consts::FRAC_PI_2).abs() < 1e-5),
+            x => panic!("expected a rotation, got {:?}", x),
+        }
+
+        touch.handle_event(&up(1, 0.25, 0.5));
+        touch.handle_event(&up(2, 0.25, 1.));
+
+        assert!(touch.take_gestures().is_empty());
+    }
+
+    #[test]
+    fn mouse_emulation() {
+        let mut touch = TouchTracker::default();
+        touch.set_mouse_emulation(Some((200, 100)));
+
+        touch.handle_event(&down(1, 0.5, 0.5));
+        touch.handle_event(&down(2, 0.1, 0.1));
+        touch.handle_event(&motion(2, 0.2, 0.2));
+        touch.handle_event(&motion(1, 0.6, 0.5));
+        touch.handle_event(&up(1, 0.6, 0.5));
+
+        let events = touch.take_mouse_events();
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], Event::MouseButtonDown { mouse_button: mouse::Button::Left, x: 100, y: 50, .. }));
+        assert!(matches!(events[1], Event::MouseMotion { x: 120, y: 50, xrel: 20, yrel: 0, .. }));
+        assert!(matches!(events[2], Event::MouseButtonUp { x: 120, y: 50, .. }));
+    }
+}
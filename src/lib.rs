@@ -4,10 +4,16 @@ pub use lazy_static;
 
 pub mod audio;
 pub mod core;
+pub mod error;
 pub mod graphics;
 pub mod input;
 pub mod maths;
 
+pub use error::Error;
+
+#[cfg(test)]
+pub(crate) mod test_util;
+
 #[cfg(test)]
 mod tests {
     #[test]
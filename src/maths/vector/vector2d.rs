@@ -1,8 +1,9 @@
 use std::fmt;
 use std::cmp::{ PartialEq, Eq };
 use std::ops::{ Add, Sub, Mul, Div };
+use serde::{ Deserialize, Serialize };
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct Vector2D {
     x: f32,
     y: f32,
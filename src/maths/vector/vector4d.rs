@@ -11,6 +11,10 @@ pub struct Vector4D {
 }
 
 impl Vector4D {
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Vector4D {
+        Vector4D { x, y, z, w }
+    }
+
     pub fn norm(&self) -> f32 {
         (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).powf(1. / 2.)
     }